@@ -0,0 +1,4 @@
+-- Revert Delivery queue migration
+
+DROP INDEX IF EXISTS idx_delivery_queue_due;
+DROP TABLE IF EXISTS delivery_queue;
@@ -0,0 +1,17 @@
+-- Delivery queue migration
+
+-- Create delivery_queue table (per-destination outbox for routed records)
+CREATE TABLE IF NOT EXISTS delivery_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    destination_key TEXT NOT NULL, -- stable key of the destination ('{router_id}/{index}')
+    payload TEXT NOT NULL, -- JSON record
+    status TEXT NOT NULL DEFAULT 'pending', -- 'pending', 'in_flight', 'failed'
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL, -- Unix time in milliseconds
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for claiming due records per destination
+CREATE INDEX IF NOT EXISTS idx_delivery_queue_due ON delivery_queue(destination_key, status, next_attempt_at);
@@ -0,0 +1,4 @@
+-- Revert Dead letters migration
+
+DROP INDEX IF EXISTS idx_dead_letters_route;
+DROP TABLE IF EXISTS dead_letters;
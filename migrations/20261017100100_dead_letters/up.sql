@@ -0,0 +1,20 @@
+-- Dead letters migration
+
+-- Create dead_letters table (records that couldn't be routed)
+CREATE TABLE IF NOT EXISTS dead_letters (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    route_id TEXT NOT NULL,
+    rule_id TEXT,
+    destination_key TEXT, -- delivery queue key of the failed destination
+    stage TEXT NOT NULL, -- 'transformation', 'delivery'
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    record TEXT NOT NULL, -- original JSON record
+    payload TEXT, -- JSON record as sent to the destination
+    failed_at TIMESTAMP NOT NULL,
+    redriven_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for listing dead letters per route
+CREATE INDEX IF NOT EXISTS idx_dead_letters_route ON dead_letters(route_id);
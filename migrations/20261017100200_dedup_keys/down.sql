@@ -0,0 +1,4 @@
+-- Revert Dedup keys migration
+
+DROP INDEX IF EXISTS idx_dedup_keys_expires;
+DROP TABLE IF EXISTS dedup_keys;
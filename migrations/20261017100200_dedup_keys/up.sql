@@ -0,0 +1,13 @@
+-- Dedup keys migration
+
+-- Create dedup_keys table (idempotency keys of records a route has seen)
+CREATE TABLE IF NOT EXISTS dedup_keys (
+    route_id TEXT NOT NULL,
+    key_hash TEXT NOT NULL, -- SHA-256 of the record's dedup key
+    first_seen_at INTEGER NOT NULL, -- Unix time in milliseconds
+    expires_at INTEGER NOT NULL, -- Unix time in milliseconds
+    PRIMARY KEY (route_id, key_hash)
+);
+
+-- Index for purging expired keys
+CREATE INDEX IF NOT EXISTS idx_dedup_keys_expires ON dedup_keys(expires_at);
@@ -0,0 +1,4 @@
+-- Revert Delivery receipts migration
+
+DROP INDEX IF EXISTS idx_delivery_receipts_route;
+DROP TABLE IF EXISTS delivery_receipts;
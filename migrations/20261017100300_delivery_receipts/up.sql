@@ -0,0 +1,18 @@
+-- Delivery receipts migration
+
+-- Create delivery_receipts table (one row per batch handed to a destination)
+CREATE TABLE IF NOT EXISTS delivery_receipts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    route_id TEXT NOT NULL,
+    rule_id TEXT,
+    destination_id TEXT NOT NULL,
+    destination_key TEXT, -- delivery queue key of the destination
+    record_count INTEGER NOT NULL,
+    status TEXT NOT NULL, -- 'delivered', 'queued', 'failed'
+    error TEXT,
+    duration_ms INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for listing receipts per route
+CREATE INDEX IF NOT EXISTS idx_delivery_receipts_route ON delivery_receipts(route_id);
@@ -0,0 +1,4 @@
+-- Revert Delivery queue origin migration
+
+ALTER TABLE delivery_queue DROP COLUMN rule_id;
+ALTER TABLE delivery_queue DROP COLUMN record;
@@ -0,0 +1,6 @@
+-- Delivery queue origin migration
+
+-- Keep the original record and the rule that routed it with each queued record,
+-- so records that run out of retries become complete dead letters
+ALTER TABLE delivery_queue ADD COLUMN record TEXT; -- JSON record before transformations
+ALTER TABLE delivery_queue ADD COLUMN rule_id TEXT;
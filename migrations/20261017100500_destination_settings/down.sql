@@ -0,0 +1,4 @@
+-- Revert Destination settings migration
+
+ALTER TABLE destinations DROP COLUMN batch;
+ALTER TABLE destinations DROP COLUMN timeout;
@@ -0,0 +1,5 @@
+-- Destination settings migration
+
+-- Let shared destinations carry the same delivery settings as a route's own destinations
+ALTER TABLE destinations ADD COLUMN timeout INTEGER; -- seconds; the global default if NULL
+ALTER TABLE destinations ADD COLUMN batch TEXT; -- JSON blob for batching settings
@@ -0,0 +1,4 @@
+-- Revert Queued receipts migration
+
+ALTER TABLE delivery_receipts DROP COLUMN batch_receipt_id;
+ALTER TABLE delivery_queue DROP COLUMN receipt_id;
@@ -0,0 +1,7 @@
+-- Queued receipts migration
+
+-- Link queued records to their delivery receipt, and receipts to the batch
+-- that settled them, so queued receipts are settled once the record is
+-- delivered or given up on
+ALTER TABLE delivery_queue ADD COLUMN receipt_id INTEGER; -- queued delivery receipt of the record
+ALTER TABLE delivery_receipts ADD COLUMN batch_receipt_id INTEGER; -- receipt of the batch that settled a queued receipt
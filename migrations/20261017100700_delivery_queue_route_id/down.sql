@@ -0,0 +1,3 @@
+-- Revert Delivery queue route migration
+
+ALTER TABLE delivery_queue DROP COLUMN route_id;
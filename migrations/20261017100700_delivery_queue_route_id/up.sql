@@ -0,0 +1,10 @@
+-- Delivery queue route migration
+
+-- Keep the route that queued each record, rather than parsing it from the
+-- destination key ('{route_id}/{destination_id}'), since route IDs may contain '/'
+ALTER TABLE delivery_queue ADD COLUMN route_id TEXT NOT NULL DEFAULT '';
+
+-- Records queued before this migration only have the key to go by
+UPDATE delivery_queue
+SET route_id = substr(destination_key, 1, instr(destination_key, '/') - 1)
+WHERE instr(destination_key, '/') > 0;
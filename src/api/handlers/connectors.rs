@@ -1,10 +1,9 @@
 use axum::{
-    extract::{Path, State, Json as AxumJson},
-    response::IntoResponse,
+    extract::{Path, Json as AxumJson},
     http::StatusCode,
     response::Json,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::{json, Value};
 
 /// List all connectors
@@ -19,6 +18,7 @@ pub async fn get_connector(Path(id): Path<String>) -> (StatusCode, Json<Value>)
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code, reason = "only checks the request shape until connectors are stored")]
 pub struct CreateConnectorRequest {
     name: String,
     connector_type: String,
@@ -29,12 +29,13 @@ pub struct CreateConnectorRequest {
 
 /// Create a new connector
 pub async fn create_connector(
-    AxumJson(payload): AxumJson<CreateConnectorRequest>
+    AxumJson(_payload): AxumJson<CreateConnectorRequest>
 ) -> (StatusCode, Json<Value>) {
     (StatusCode::CREATED, Json(json!({"id": "new-connector", "status": "created"})))
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code, reason = "only checks the request shape until connectors are stored")]
 pub struct UpdateConnectorRequest {
     name: Option<String>,
     enabled: Option<bool>,
@@ -45,13 +46,13 @@ pub struct UpdateConnectorRequest {
 /// Update an existing connector
 pub async fn update_connector(
     Path(id): Path<String>,
-    AxumJson(payload): AxumJson<UpdateConnectorRequest>
+    AxumJson(_payload): AxumJson<UpdateConnectorRequest>
 ) -> (StatusCode, Json<Value>) {
     (StatusCode::OK, Json(json!({"id": id, "status": "updated"})))
 }
 
 /// Delete a connector
-pub async fn delete_connector(Path(id): Path<String>) -> StatusCode {
+pub async fn delete_connector(Path(_id): Path<String>) -> StatusCode {
     StatusCode::NO_CONTENT
 }
 
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::router::{DeadLetterFilter, DeadLetterStore, DeliveryQueue, RedriveOutcome};
+
+/// Build the error response for a failed store operation
+fn store_error(e: anyhow::Error) -> (StatusCode, Json<Value>) {
+    tracing::error!("Dead letter store error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+/// List dead letters, newest first
+pub async fn list_dead_letters(
+    Extension(store): Extension<Arc<DeadLetterStore>>,
+    Query(filter): Query<DeadLetterFilter>,
+) -> (StatusCode, Json<Value>) {
+    match store.list(&filter).await {
+        Ok(letters) => (StatusCode::OK, Json(json!(letters))),
+        Err(e) => store_error(e),
+    }
+}
+
+/// Get a dead letter by ID
+pub async fn get_dead_letter(
+    Extension(store): Extension<Arc<DeadLetterStore>>,
+    Path(id): Path<i64>,
+) -> (StatusCode, Json<Value>) {
+    match store.get(id).await {
+        Ok(Some(letter)) => (StatusCode::OK, Json(json!(letter))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({"error": format!("Dead letter {} not found", id)}))),
+        Err(e) => store_error(e),
+    }
+}
+
+/// Delete a dead letter
+pub async fn delete_dead_letter(
+    Extension(store): Extension<Arc<DeadLetterStore>>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    match store.delete(id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((StatusCode::NOT_FOUND, Json(json!({"error": format!("Dead letter {} not found", id)})))),
+        Err(e) => Err(store_error(e)),
+    }
+}
+
+/// Put a dead letter back on its destination's delivery queue
+pub async fn redrive_dead_letter(
+    Extension(store): Extension<Arc<DeadLetterStore>>,
+    Extension(queue): Extension<Arc<DeliveryQueue>>,
+    Path(id): Path<i64>,
+) -> (StatusCode, Json<Value>) {
+    match store.redrive(id, &queue).await {
+        Ok(RedriveOutcome::Redriven) => (StatusCode::ACCEPTED, Json(json!({"id": id, "status": "redriven"}))),
+        Ok(RedriveOutcome::NotFound) => (StatusCode::NOT_FOUND, Json(json!({"error": format!("Dead letter {} not found", id)}))),
+        Ok(RedriveOutcome::AlreadyRedriven) => (StatusCode::CONFLICT, Json(json!({"error": format!("Dead letter {} was already re-driven", id)}))),
+        Ok(RedriveOutcome::NotRedrivable) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": format!("Dead letter {} failed before delivery and can't be re-driven", id)})),
+        ),
+        Ok(RedriveOutcome::NotQueued) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": format!(
+                "No delivery queue worker is running for the destination of dead letter {}; re-driving needs the router's delivery_queue enabled", id
+            )})),
+        ),
+        Ok(RedriveOutcome::QueueFull) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({"error": format!("The delivery queue for the destination of dead letter {} is full", id)})),
+        ),
+        Err(e) => store_error(e),
+    }
+}
+
+/// Query parameters for re-driving dead letters in bulk
+#[derive(Deserialize)]
+pub struct RedriveAllRequest {
+    /// Only re-drive the dead letters of this route
+    route_id: Option<String>,
+}
+
+/// Re-drive every delivery failure, optionally for one route.
+///
+/// Fails with 409 when letters were left alone and none could be re-driven.
+pub async fn redrive_dead_letters(
+    Extension(store): Extension<Arc<DeadLetterStore>>,
+    Extension(queue): Extension<Arc<DeliveryQueue>>,
+    Query(request): Query<RedriveAllRequest>,
+) -> (StatusCode, Json<Value>) {
+    match store.redrive_all(request.route_id.as_deref(), &queue).await {
+        Ok(summary) if summary.redriven == 0 && summary.not_queued + summary.queue_full > 0 => {
+            (StatusCode::CONFLICT, Json(json!(summary)))
+        },
+        Ok(summary) => (StatusCode::ACCEPTED, Json(json!(summary))),
+        Err(e) => store_error(e),
+    }
+}
@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Extension, Path, Json as JsonExtractor},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::router::{BatchSettings, DestinationInUse, DestinationRegistry, RouterIntegration};
+use crate::storage::DestinationRecord;
+
+/// List all shared destinations, with secrets redacted
+pub async fn list_destinations(
+    Extension(registry): Extension<Arc<DestinationRegistry>>,
+) -> (StatusCode, Json<Value>) {
+    (StatusCode::OK, Json(json!(registry.list().iter().map(DestinationRecord::redacted).collect::<Vec<_>>())))
+}
+
+/// Get a shared destination by ID, with secrets redacted
+pub async fn get_destination(
+    Extension(registry): Extension<Arc<DestinationRegistry>>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<Value>) {
+    match registry.find(&id).await {
+        Ok(Some(record)) => (StatusCode::OK, Json(json!(record.redacted()))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({"error": format!("Destination {} not found", id)}))),
+        Err(e) => {
+            tracing::error!("Failed to get destination {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PutDestinationRequest {
+    name: Option<String>,
+    destination_type: String,
+    enabled: Option<bool>,
+    config: Value,
+    timeout: Option<u64>,
+    batch: Option<BatchSettings>,
+}
+
+/// Create or replace a shared destination and rebuild the routes using it
+///
+/// Secret fields sent back as they were redacted keep their stored values.
+pub async fn put_destination(
+    Extension(registry): Extension<Arc<DestinationRegistry>>,
+    Extension(router): Extension<Arc<RouterIntegration>>,
+    Path(id): Path<String>,
+    JsonExtractor(payload): JsonExtractor<PutDestinationRequest>,
+) -> (StatusCode, Json<Value>) {
+    let mut record = DestinationRecord::new(
+        id.clone(),
+        payload.name.unwrap_or_else(|| id.clone()),
+        payload.destination_type,
+        payload.enabled.unwrap_or(true),
+        payload.config,
+    )
+    .with_timeout(payload.timeout)
+    .with_batch(payload.batch.as_ref());
+
+    match registry.find(&id).await {
+        Ok(Some(existing)) => record = record.with_secrets_from(&existing),
+        Ok(None) => {},
+        Err(e) => {
+            tracing::error!("Failed to get destination {}: {}", id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})));
+        }
+    }
+
+    match router.put_destination(record).await {
+        Ok(()) => (StatusCode::OK, Json(json!({"id": id, "status": "saved"}))),
+        Err(e) if e.is::<DestinationInUse>() => (StatusCode::CONFLICT, Json(json!({"error": e.to_string()}))),
+        // Storing failed; the definition itself was valid
+        Err(e) if e.downcast_ref::<sqlx::Error>().is_some() => {
+            tracing::error!("Failed to save destination {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+        },
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))),
+    }
+}
+
+/// Delete a shared destination that no running route uses
+pub async fn delete_destination(
+    Extension(router): Extension<Arc<RouterIntegration>>,
+    Path(id): Path<String>,
+) -> Response {
+    match router.remove_destination(&id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) if e.is::<DestinationInUse>() => (StatusCode::CONFLICT, Json(json!({"error": e.to_string()}))).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to delete destination {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
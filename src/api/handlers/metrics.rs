@@ -0,0 +1,40 @@
+use axum::{
+    extract::Extension,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use std::sync::Arc;
+
+use crate::router::{DeliveryMetrics, Destination, RouteRegistry};
+
+/// Serve Muxly's own metrics, followed by the metrics the routes'
+/// Prometheus destinations collected from records, in the Prometheus text
+/// exposition format
+pub async fn get_metrics(
+    Extension(metrics): Extension<Arc<DeliveryMetrics>>,
+    Extension(routes): Extension<Arc<RouteRegistry>>,
+) -> impl IntoResponse {
+    let mut body = metrics.render();
+
+    let mut routes = routes.list();
+    routes.sort_by(|a, b| a.config.id.cmp(&b.config.id));
+
+    // A shared destination is rendered once, however many routes use it
+    let mut scraped: Vec<&Arc<dyn Destination>> = Vec::new();
+    for destination in routes.iter().flat_map(|route| route.destinations()) {
+        if scraped.iter().any(|seen| Arc::ptr_eq(seen, destination)) {
+            continue;
+        }
+        scraped.push(destination);
+
+        if let Some(rendered) = destination.scrape() {
+            body.push_str(&rendered);
+        }
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+}
@@ -1,3 +1,7 @@
-pub mod health;
 pub mod connectors;
-pub mod outputs; 
\ No newline at end of file
+pub mod outputs;
+pub mod dead_letters;
+pub mod destinations;
+pub mod metrics;
+pub mod receipts;
+pub mod routes;
\ No newline at end of file
@@ -13,7 +13,7 @@ pub async fn list_outputs() -> (StatusCode, Json<Value>) {
 
 /// Create a new output
 pub async fn create_output(
-    JsonExtractor(payload): JsonExtractor<Value>
+    JsonExtractor(_payload): JsonExtractor<Value>
 ) -> (StatusCode, Json<Value>) {
     (StatusCode::CREATED, Json(json!({"id": "new-output", "status": "created"})))
 }
@@ -26,12 +26,12 @@ pub async fn get_output(Path(id): Path<String>) -> (StatusCode, Json<Value>) {
 /// Update an output by ID
 pub async fn update_output(
     Path(id): Path<String>,
-    JsonExtractor(payload): JsonExtractor<Value>
+    JsonExtractor(_payload): JsonExtractor<Value>
 ) -> (StatusCode, Json<Value>) {
     (StatusCode::OK, Json(json!({"id": id, "status": "updated"})))
 }
 
 /// Delete an output by ID
-pub async fn delete_output(Path(id): Path<String>) -> StatusCode {
+pub async fn delete_output(Path(_id): Path<String>) -> StatusCode {
     StatusCode::NO_CONTENT
 } 
\ No newline at end of file
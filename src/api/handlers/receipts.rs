@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::router::{ReceiptFilter, ReceiptStore};
+use crate::storage::DatabasePool;
+
+/// Build the error response for a failed store operation
+fn store_error(e: anyhow::Error) -> (StatusCode, Json<Value>) {
+    tracing::error!("Receipt store error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()})))
+}
+
+/// List delivery receipts, newest first
+pub async fn list_receipts(
+    Extension(pool): Extension<Arc<DatabasePool>>,
+    Query(filter): Query<ReceiptFilter>,
+) -> (StatusCode, Json<Value>) {
+    let store = ReceiptStore::new(pool);
+
+    match store.list(&filter).await {
+        Ok(receipts) => (StatusCode::OK, Json(json!(receipts))),
+        Err(e) => store_error(e),
+    }
+}
+
+/// Get a delivery receipt by ID
+pub async fn get_receipt(
+    Extension(pool): Extension<Arc<DatabasePool>>,
+    Path(id): Path<i64>,
+) -> (StatusCode, Json<Value>) {
+    let store = ReceiptStore::new(pool);
+
+    match store.get(id).await {
+        Ok(Some(receipt)) => (StatusCode::OK, Json(json!(receipt))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({"error": format!("Receipt {} not found", id)}))),
+        Err(e) => store_error(e),
+    }
+}
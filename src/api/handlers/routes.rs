@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Extension, Path, Json as JsonExtractor},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::router::RouteRegistry;
+
+#[derive(Deserialize)]
+pub struct DryRunRequest {
+    data: Value,
+}
+
+/// Run a sample record through a route without sending anything
+pub async fn dry_run_route(
+    Extension(routes): Extension<Arc<RouteRegistry>>,
+    Path(id): Path<String>,
+    JsonExtractor(payload): JsonExtractor<DryRunRequest>,
+) -> (StatusCode, Json<Value>) {
+    match routes.get(&id) {
+        Some(route) => (StatusCode::OK, Json(json!(route.dry_run(payload.data)))),
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": format!("Route {} not found", id)}))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SendRecordRequest {
+    data: Value,
+    metadata: Option<Value>,
+}
+
+/// Send a record through a route to its destinations
+///
+/// `metadata` describes the record's fields the way a connector's
+/// `get_metadata` does, for destinations that write typed output. Routes
+/// with `on_error: fail` answer 422 when the record fails.
+pub async fn send_record(
+    Extension(routes): Extension<Arc<RouteRegistry>>,
+    Path(id): Path<String>,
+    JsonExtractor(payload): JsonExtractor<SendRecordRequest>,
+) -> (StatusCode, Json<Value>) {
+    let route = match routes.get(&id) {
+        Some(route) => route,
+        None => return (StatusCode::NOT_FOUND, Json(json!({"error": format!("Route {} not found", id)}))),
+    };
+
+    if let Some(metadata) = &payload.metadata {
+        route.apply_source_metadata(metadata);
+    }
+
+    match route.process(payload.data).await {
+        Ok(results) => (StatusCode::OK, Json(json!({"route_id": id, "results": results}))),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": e.to_string()}))),
+    }
+}
@@ -1,14 +1,20 @@
 use axum::{
+    extract::Extension,
     routing::get,
     Router,
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use utoipa::ToSchema;
 
+use crate::router::{BreakerRegistry, BreakerState, BreakerStatus};
+
 // Define routes
 pub fn routes() -> Router {
-    Router::new().route("/health", get(health_check))
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/destinations", get(destination_health))
 }
 
 // Health check response model
@@ -43,4 +49,70 @@ pub async fn health_check() -> Json<HealthResponse> {
         version: env!("CARGO_PKG_VERSION").to_string(),
         uptime,
     })
-} 
\ No newline at end of file
+}
+
+// Destination health response model
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DestinationHealthResponse {
+    /// "ok" when every circuit breaker is closed, "degraded" otherwise
+    status: String,
+    destinations: Vec<DestinationHealth>,
+}
+
+// Circuit breaker state of a single destination
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DestinationHealth {
+    /// Stable destination key (`{route_id}/{destination_id}`)
+    key: String,
+    destination_id: String,
+    destination_type: String,
+    /// closed, open or half_open
+    state: String,
+    consecutive_failures: u32,
+    /// When the breaker opened (RFC 3339), while it isn't closed
+    open_since: Option<String>,
+    last_error: Option<String>,
+}
+
+impl From<BreakerStatus> for DestinationHealth {
+    fn from(status: BreakerStatus) -> Self {
+        let state = match status.state {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        };
+
+        Self {
+            key: status.key,
+            destination_id: status.destination_id,
+            destination_type: status.destination_type,
+            state: state.to_string(),
+            consecutive_failures: status.consecutive_failures,
+            open_since: status.open_since.map(|t| t.to_rfc3339()),
+            last_error: status.last_error,
+        }
+    }
+}
+
+/// Get destination health
+///
+/// Returns the circuit breaker state of every router destination
+#[utoipa::path(
+    get,
+    path = "/health/destinations",
+    tag = "health",
+    responses(
+        (status = 200, description = "Destination health", body = DestinationHealthResponse)
+    )
+)]
+pub async fn destination_health(
+    Extension(breakers): Extension<Arc<BreakerRegistry>>,
+) -> Json<DestinationHealthResponse> {
+    let statuses = breakers.statuses();
+    let degraded = statuses.iter().any(|status| status.state != BreakerState::Closed);
+
+    Json(DestinationHealthResponse {
+        status: if degraded { "degraded" } else { "ok" }.to_string(),
+        destinations: statuses.into_iter().map(DestinationHealth::from).collect(),
+    })
+}
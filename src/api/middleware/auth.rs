@@ -1,19 +0,0 @@
-use anyhow::Result;
-use axum::{
-    body::{Body, BoxBody},
-    http::{Request, StatusCode},
-    middleware::Next,
-    response::Response,
-};
-
-/// Middleware for authenticating API requests
-pub async fn auth_middleware<B>(
-    req: Request<B>,
-    next: Next<B>,
-) -> Result<Response, StatusCode> {
-    // In a real implementation, this would validate the auth header
-    // For now, we just pass the request through directly
-    
-    // Simply pass the original request through
-    Ok(next.run(req).await)
-}
\ No newline at end of file
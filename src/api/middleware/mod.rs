@@ -1,5 +1,3 @@
-pub mod auth;
 pub mod logging;
 
-pub use auth::auth_middleware;
-pub use logging::logging_middleware; 
\ No newline at end of file
+pub use logging::logging_middleware;
\ No newline at end of file
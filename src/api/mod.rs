@@ -6,15 +6,32 @@ pub mod openapi;
 
 use axum::{
     Router,
+    middleware::{from_fn, from_fn_with_state},
     routing::{get, post, put, delete},
 };
+use std::sync::Arc;
 
-pub fn api_router() -> Router {
+use crate::auth::{KeycloakAuth, keycloak_auth_middleware};
+
+/// The API's routes; with `auth`, every route but the health checks and
+/// the API documentation needs a bearer token that Keycloak accepts
+pub fn api_router(auth: Option<Arc<KeycloakAuth>>) -> Router {
     // Start with the regular routes
-    let router = Router::new()
-        .merge(health::routes())  // Use our new documented health routes
+    let mut router = Router::new()
         .merge(connector_routes())
-        .merge(output_routes());
+        .merge(output_routes())
+        .merge(route_routes())
+        .merge(destination_routes())
+        .merge(dead_letter_routes())
+        .merge(receipt_routes());
+    
+    if let Some(auth) = auth {
+        router = router.layer(from_fn_with_state(auth, keycloak_auth_middleware));
+    }
+    
+    let router = router
+        .merge(health::routes())  // Use our new documented health routes
+        .layer(from_fn(middleware::logging_middleware));
     
     // Add OpenAPI documentation routes
     openapi::add_documentation_routes(router)
@@ -22,14 +39,6 @@ pub fn api_router() -> Router {
 
 // These routes will be replaced/updated with OpenAPI documentation in the future
 pub mod connectors {
-    // Placeholder that will be documented later
-    pub async fn list_connectors() {}
-    pub async fn get_connector() {}
-    pub async fn create_connector() {}
-    pub async fn update_connector() {}
-    pub async fn delete_connector() {}
-    pub async fn test_connection() {}
-    
     // Placeholder schema types for OpenAPI
     #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct ConnectorRequest {}
@@ -45,13 +54,6 @@ pub mod connectors {
 }
 
 pub mod router {
-    // Placeholder that will be documented later
-    pub async fn list_routes() {}
-    pub async fn get_route() {}
-    pub async fn create_route() {}
-    pub async fn update_route() {}
-    pub async fn delete_route() {}
-    
     // Placeholder schema types for OpenAPI
     #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct RouteRequest {}
@@ -64,15 +66,6 @@ pub mod router {
 }
 
 pub mod scheduler {
-    // Placeholder that will be documented later
-    pub async fn list_jobs() {}
-    pub async fn get_job() {}
-    pub async fn create_job() {}
-    pub async fn update_job() {}
-    pub async fn delete_job() {}
-    pub async fn run_job() {}
-    pub async fn get_job_executions() {}
-    
     // Placeholder schema types for OpenAPI
     #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct JobRequest {}
@@ -108,4 +101,39 @@ fn output_routes() -> Router {
         .route("/outputs/:id", get(handlers::outputs::get_output))
         .route("/outputs/:id", put(handlers::outputs::update_output))
         .route("/outputs/:id", delete(handlers::outputs::delete_output))
-} 
\ No newline at end of file
+}
+
+fn route_routes() -> Router {
+    Router::new()
+        .route("/router/routes/:id/records", post(handlers::routes::send_record))
+        .route("/router/routes/:id/dry-run", post(handlers::routes::dry_run_route))
+}
+
+fn destination_routes() -> Router {
+    Router::new()
+        .route("/router/destinations", get(handlers::destinations::list_destinations))
+        .route("/router/destinations/:id", get(handlers::destinations::get_destination))
+        .route("/router/destinations/:id", put(handlers::destinations::put_destination))
+        .route("/router/destinations/:id", delete(handlers::destinations::delete_destination))
+}
+
+fn dead_letter_routes() -> Router {
+    Router::new()
+        .route("/router/dead-letters", get(handlers::dead_letters::list_dead_letters))
+        .route("/router/dead-letters/redrive", post(handlers::dead_letters::redrive_dead_letters))
+        .route("/router/dead-letters/:id", get(handlers::dead_letters::get_dead_letter))
+        .route("/router/dead-letters/:id", delete(handlers::dead_letters::delete_dead_letter))
+        .route("/router/dead-letters/:id/redrive", post(handlers::dead_letters::redrive_dead_letter))
+}
+
+fn receipt_routes() -> Router {
+    Router::new()
+        .route("/router/receipts", get(handlers::receipts::list_receipts))
+        .route("/router/receipts/:id", get(handlers::receipts::get_receipt))
+}
+
+/// Metrics for Prometheus to scrape, served on the configured `metrics_path`
+pub fn metrics_router(metrics_path: &str) -> Router {
+    Router::new()
+        .route(metrics_path, get(handlers::metrics::get_metrics))
+}
\ No newline at end of file
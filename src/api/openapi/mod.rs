@@ -14,35 +14,14 @@ use crate::api::scheduler;
     paths(
         // Health endpoints
         health::health_check,
-        
-        // Connector endpoints
-        connectors::list_connectors,
-        connectors::get_connector,
-        connectors::create_connector,
-        connectors::update_connector,
-        connectors::delete_connector,
-        connectors::test_connection,
-        
-        // Router endpoints
-        router::list_routes,
-        router::get_route,
-        router::create_route,
-        router::update_route,
-        router::delete_route,
-        
-        // Scheduler endpoints
-        scheduler::list_jobs,
-        scheduler::get_job,
-        scheduler::create_job,
-        scheduler::update_job,
-        scheduler::delete_job,
-        scheduler::run_job,
-        scheduler::get_job_executions,
+        health::destination_health,
     ),
     components(
         schemas(
             // Health schemas
             health::HealthResponse,
+            health::DestinationHealthResponse,
+            health::DestinationHealth,
             
             // Connector schemas
             connectors::ConnectorRequest,
@@ -71,7 +50,6 @@ use crate::api::scheduler;
     ),
     info(
         title = "Muxly API",
-        version = env!("CARGO_PKG_VERSION"),
         description = "Muxly API for collecting, unifying, and routing product metrics",
         license(
             name = "MIT",
@@ -1,27 +1,14 @@
-use anyhow::{Result, anyhow};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tracing::{debug, error, info};
 use axum::{
+    extract::State,
     http::{HeaderMap, Request, StatusCode},
     middleware::Next,
     response::Response,
-    Json,
-    extract::FromRef,
 };
-use std::sync::Arc;
 use std::collections::HashSet;
-use futures::future::BoxFuture;
-use keycloak::{KeycloakError, KeycloakAdmin};
-use std::future::{ready, Ready};
-use tower_http::auth::AsyncAuthorizeRequest;
+use std::sync::Arc;
 use crate::error::{MuxlyError, Result};
-use std::future::Future;
-use std::pin::Pin;
-
-use crate::auth::tokens::AuthToken;
 
 /// Keycloak configuration
 #[derive(Debug, Clone, Deserialize)]
@@ -37,6 +24,18 @@ pub struct KeycloakConfig {
     pub required_role: Option<String>,
 }
 
+impl KeycloakConfig {
+    /// URL of an OpenID Connect endpoint of the realm
+    fn endpoint(&self, path: &str) -> String {
+        format!(
+            "{}/realms/{}/protocol/openid-connect/{}",
+            self.server_url.trim_end_matches('/'),
+            self.realm,
+            path
+        )
+    }
+}
+
 /// User information extracted from JWT token
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
@@ -46,11 +45,27 @@ pub struct UserInfo {
     pub roles: Vec<String>,
 }
 
+/// Token introspection response
+#[derive(Debug, Deserialize)]
+struct Introspection {
+    active: bool,
+    sub: Option<String>,
+    preferred_username: Option<String>,
+    email: Option<String>,
+    realm_access: Option<RealmAccess>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RealmAccess {
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
 /// Keycloak auth service
 #[derive(Debug, Clone)]
 pub struct KeycloakAuth {
     /// HTTP client
-    client: Arc<KeycloakAdmin>,
+    client: Client,
     /// Configuration for the Keycloak client
     config: KeycloakConfig,
     required_roles: HashSet<String>,
@@ -59,11 +74,10 @@ pub struct KeycloakAuth {
 impl KeycloakAuth {
     /// Create a new Keycloak auth service
     pub async fn new(config: KeycloakConfig) -> Result<Self> {
-        let client = KeycloakAdmin::new(&config.server_url, &config.realm)
-            .auth_client(&config.client_id, &config.client_secret)
+        let client = Client::builder()
             .build()
             .map_err(|e| MuxlyError::Authentication(format!("Failed to create Keycloak client: {}", e)))?;
-        
+
         // Parse required roles if any
         let required_roles = if let Some(role) = &config.required_role {
             let mut roles = HashSet::new();
@@ -72,31 +86,39 @@ impl KeycloakAuth {
         } else {
             HashSet::new()
         };
-        
+
         Ok(KeycloakAuth {
-            client: Arc::new(client),
+            client,
             config,
             required_roles,
         })
     }
-    
+
     /// Verify a token and extract user info
     pub async fn verify_token(&self, token: &str) -> Result<UserInfo> {
-        // Decode and validate the token
-        let decoded = self.client.decode_token(token)
+        // Validate the token through the introspection endpoint
+        let decoded: Introspection = self.client.post(self.config.endpoint("token/introspect"))
+            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
+            .form(&[("token", token)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| MuxlyError::Authentication(format!("Token validation failed: {}", e)))?
+            .json()
+            .await
             .map_err(|e| MuxlyError::Authentication(format!("Token validation failed: {}", e)))?;
-            
+
+        if !decoded.active {
+            return Err(MuxlyError::Authentication("Token is not active".to_string()));
+        }
+
         // Get user ID
-        let sub = decoded.subject()
-            .ok_or_else(|| MuxlyError::Authentication("Subject not found in token".to_string()))?
-            .to_string();
-            
+        let sub = decoded.sub
+            .ok_or_else(|| MuxlyError::Authentication("Subject not found in token".to_string()))?;
+
         // Extract roles
-        let mut roles = Vec::new();
-        if let Some(realm_access) = decoded.realm_access() {
-            roles = realm_access.roles.clone();
-        }
-        
+        let roles = decoded.realm_access.map(|access| access.roles).unwrap_or_default();
+
         // Verify required roles if any
         if !self.required_roles.is_empty() {
             let has_required_role = roles.iter().any(|role| self.required_roles.contains(role));
@@ -104,49 +126,46 @@ impl KeycloakAuth {
                 return Err(MuxlyError::Authentication("Insufficient permissions".to_string()));
             }
         }
-        
-        // Extract name and email
-        let name = decoded.preferred_username().map(String::from);
-        let email = decoded.email().map(String::from);
-        
+
         // Create user info
         let user_info = UserInfo {
             sub,
-            name,
-            email,
+            name: decoded.preferred_username,
+            email: decoded.email,
             roles,
         };
-        
+
         Ok(user_info)
     }
-    
+
     /// Extract token from authorization header
     pub fn extract_token(headers: &HeaderMap) -> Result<String> {
         let auth_header = headers.get("Authorization")
             .ok_or_else(|| MuxlyError::Authentication("Missing Authorization header".to_string()))?
             .to_str()
             .map_err(|_| MuxlyError::Authentication("Invalid Authorization header".to_string()))?;
-            
+
         if !auth_header.starts_with("Bearer ") {
             return Err(MuxlyError::Authentication("Invalid Authorization header format. Expected Bearer token".to_string()));
         }
-        
+
         Ok(auth_header[7..].to_string())
     }
 }
 
-/// Middleware for authentication
+/// Middleware that lets through requests with a bearer token Keycloak
+/// accepts, storing the token's [`UserInfo`] in the request extensions
 pub async fn keycloak_auth_middleware<B>(
-    auth: KeycloakAuth,
+    State(auth): State<Arc<KeycloakAuth>>,
     mut req: Request<B>,
     next: Next<B>,
-) -> Result<Response, StatusCode> {
+) -> std::result::Result<Response, StatusCode> {
     // Extract token
     let token = match KeycloakAuth::extract_token(req.headers()) {
         Ok(token) => token,
         Err(_) => return Err(StatusCode::UNAUTHORIZED),
     };
-    
+
     // Verify token
     match auth.verify_token(&token).await {
         Ok(user_info) => {
@@ -157,34 +176,3 @@ pub async fn keycloak_auth_middleware<B>(
         Err(_) => Err(StatusCode::UNAUTHORIZED),
     }
 }
-
-/// Extract current user from request
-pub fn get_current_user<B>(req: &Request<B>) -> Option<UserInfo> {
-    req.extensions().get::<UserInfo>().cloned()
-}
-
-/// Check if user has a specific role
-pub fn has_role<B>(req: &Request<B>, role: &str) -> bool {
-    if let Some(user) = req.extensions().get::<UserInfo>() {
-        user.roles.iter().any(|r| r == role)
-    } else {
-        false
-    }
-}
-
-/// Role-based access middleware
-pub fn require_role(role: String) -> impl Fn(Request<B>) -> Pin<Box<dyn Future<Output = Result<Request<B>, StatusCode>> + Send>> + Clone
-where
-    B: Send + 'static,
-{
-    move |req: Request<B>| {
-        let role_clone = role.clone();
-        Box::pin(async move {
-            if has_role(&req, &role_clone) {
-                Ok(req)
-            } else {
-                Err(StatusCode::FORBIDDEN)
-            }
-        })
-    }
-} 
\ No newline at end of file
@@ -4,7 +4,6 @@
 //! from various sources including files, environment variables, and defaults.
 
 pub mod loader;
-pub mod schema;
 pub mod validation;
 pub mod models;
 
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 /// Connectors configuration container
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ConnectorsConfig {
     /// Global connector settings
     #[serde(default)]
@@ -30,18 +30,6 @@ pub struct ConnectorsConfig {
     pub custom: HashMap<String, CustomConnectorConfig>,
 }
 
-impl Default for ConnectorsConfig {
-    fn default() -> Self {
-        Self {
-            global: GlobalConnectorConfig::default(),
-            bigquery: BigQueryConfig::default(),
-            ga4: GA4Config::default(),
-            hubspot: HubSpotConfig::default(),
-            custom: HashMap::new(),
-        }
-    }
-}
-
 /// Global connector settings that apply to all connectors
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalConnectorConfig {
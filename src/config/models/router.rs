@@ -6,8 +6,10 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::router::RouteDefinition;
+
 /// Router configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RouterConfig {
     /// Global router settings
     #[serde(default)]
@@ -40,21 +42,10 @@ pub struct RouterConfig {
     /// Custom outputs
     #[serde(default)]
     pub custom: HashMap<String, CustomOutputConfig>,
-}
-
-impl Default for RouterConfig {
-    fn default() -> Self {
-        Self {
-            global: GlobalRouterConfig::default(),
-            prometheus: PrometheusConfig::default(),
-            webhook: WebhookOutputConfig::default(),
-            file: FileOutputConfig::default(),
-            s3: S3OutputConfig::default(),
-            database: DatabaseOutputConfig::default(),
-            slack: SlackConfig::default(),
-            custom: HashMap::new(),
-        }
-    }
+    
+    /// Routes started with the service
+    #[serde(default)]
+    pub routes: Vec<RouteDefinition>,
 }
 
 /// Global router settings
@@ -76,13 +67,35 @@ pub struct GlobalRouterConfig {
     #[serde(default = "default_flush_interval")]
     pub flush_interval: u64,
     
-    /// Number of retries for failed deliveries
+    /// Number of retries for failed deliveries; only the delivery queue
+    /// retries, so this has no effect without `delivery_queue`
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
     
     /// Enable routing transformation
     #[serde(default = "default_enable_transformation")]
     pub enable_transformation: bool,
+    
+    /// Maximum number of destinations a record is sent to at once
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    
+    /// Default timeout in seconds for sending to a destination
+    #[serde(default = "default_timeout")]
+    pub destination_timeout: u64,
+    
+    /// Deliver through the durable delivery queue instead of sending directly.
+    ///
+    /// On by default, since retries (`max_retries`), `buffer_size`
+    /// backpressure and re-driving dead letters all need it. Without it a
+    /// route delivers synchronously and reports every destination's result
+    /// to its caller, and a failed delivery becomes a dead letter right away.
+    #[serde(default = "default_enabled")]
+    pub delivery_queue: bool,
+    
+    /// Circuit breaker settings for destinations
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 impl Default for GlobalRouterConfig {
@@ -94,6 +107,36 @@ impl Default for GlobalRouterConfig {
             flush_interval: default_flush_interval(),
             max_retries: default_max_retries(),
             enable_transformation: default_enable_transformation(),
+            max_concurrency: default_max_concurrency(),
+            destination_timeout: default_timeout(),
+            delivery_queue: default_enabled(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+        }
+    }
+}
+
+/// Circuit breaker settings for destinations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Enable circuit breakers
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    
+    /// Consecutive failures that open a destination's breaker
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    
+    /// Seconds a breaker stays open before the destination is probed
+    #[serde(default = "default_open_duration")]
+    pub open_duration: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            failure_threshold: default_failure_threshold(),
+            open_duration: default_open_duration(),
         }
     }
 }
@@ -405,23 +448,31 @@ fn default_enable_transformation() -> bool {
     true
 }
 
-fn default_metrics_path() -> String {
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_open_duration() -> u64 {
+    30
+}
+
+pub(crate) fn default_metrics_path() -> String {
     "/metrics".to_string()
 }
 
-fn default_include_labels() -> bool {
-    true
+pub(crate) fn default_include_labels() -> bool {
+    false
 }
 
-fn default_metric_type() -> String {
+pub(crate) fn default_metric_type() -> String {
     "gauge".to_string()
 }
 
-fn default_max_concurrency() -> usize {
+pub(crate) fn default_max_concurrency() -> usize {
     10
 }
 
-fn default_timeout() -> u64 {
+pub(crate) fn default_timeout() -> u64 {
     30
 }
 
@@ -5,7 +5,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Scheduler configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SchedulerConfig {
     /// API scheduler configuration
     #[serde(default)]
@@ -20,16 +20,6 @@ pub struct SchedulerConfig {
     pub webhook: WebhookConfig,
 }
 
-impl Default for SchedulerConfig {
-    fn default() -> Self {
-        Self {
-            api: ApiSchedulerConfig::default(),
-            cron: CronConfig::default(),
-            webhook: WebhookConfig::default(),
-        }
-    }
-}
-
 /// API scheduler configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiSchedulerConfig {
@@ -3,7 +3,7 @@
 //! This module provides functions for validating configuration values
 //! and ensuring configuration consistency.
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use tracing::{debug, warn};
 use std::path::Path;
 
@@ -162,8 +162,7 @@ fn validate_connectors_config(config: &super::models::connectors::ConnectorsConf
                 }
             }
             
-            if connector.plugin_path.is_some() {
-                let path = connector.plugin_path.as_ref().unwrap();
+            if let Some(path) = &connector.plugin_path {
                 if !Path::new(path).exists() {
                     warn!("Plugin file for custom connector '{}' does not exist: {}", name, path);
                 }
@@ -183,12 +182,13 @@ fn validate_router_config(config: &super::models::router::RouterConfig) -> Resul
         warn!("Router flush interval is set to 0, this may cause high CPU usage");
     }
     
+    // Metrics are served on the metrics path even with Prometheus output disabled
+    if !config.prometheus.metrics_path.starts_with('/') {
+        return Err(anyhow::anyhow!("Prometheus metrics path must start with a '/'"));
+    }
+    
     // Validate Prometheus output if enabled
     if config.prometheus.enabled {
-        if !config.prometheus.metrics_path.starts_with('/') {
-            return Err(anyhow::anyhow!("Prometheus metrics path must start with a '/'"));
-        }
-        
         match config.prometheus.default_metric_type.to_lowercase().as_str() {
             "counter" | "gauge" | "histogram" => {}
             _ => {
@@ -260,10 +260,11 @@ fn validate_router_config(config: &super::models::router::RouterConfig) -> Resul
             return Err(anyhow::anyhow!("AWS region must be specified when S3 output is enabled"));
         }
         
-        let has_credentials = config.s3.access_key_id.is_some() && config.s3.secret_access_key.is_some();
-        if !has_credentials && !config.s3.use_instance_profile {
+        // Without static keys the default credential chain is used, but a
+        // lone key is most likely a typo
+        if config.s3.access_key_id.is_some() != config.s3.secret_access_key.is_some() {
             return Err(anyhow::anyhow!(
-                "S3 output is enabled but neither AWS credentials nor instance profile is configured"
+                "S3 access_key_id and secret_access_key must be specified together"
             ));
         }
     }
@@ -290,20 +291,15 @@ fn validate_router_config(config: &super::models::router::RouterConfig) -> Resul
     }
     
     // Validate Slack notifications if enabled
-    if config.slack.enabled {
-        if config.slack.webhook_url.is_none() || config.slack.webhook_url.as_ref().unwrap().is_empty() {
-            return Err(anyhow::anyhow!("Slack webhook URL must be specified when Slack notifications are enabled"));
-        }
+    if config.slack.enabled && config.slack.webhook_url.as_ref().is_none_or(|url| url.is_empty()) {
+        return Err(anyhow::anyhow!("Slack webhook URL must be specified when Slack notifications are enabled"));
     }
     
     // Validate custom outputs
     for (name, output) in &config.custom {
-        if output.enabled {
-            if output.plugin_path.is_some() {
-                let path = output.plugin_path.as_ref().unwrap();
-                if !Path::new(path).exists() {
-                    warn!("Plugin file for custom output '{}' does not exist: {}", name, path);
-                }
+        if let (true, Some(path)) = (output.enabled, &output.plugin_path) {
+            if !Path::new(path).exists() {
+                warn!("Plugin file for custom output '{}' does not exist: {}", name, path);
             }
         }
     }
@@ -1,10 +1,8 @@
-use std::fmt;
 use sqlx::Error as SqlxError;
 use thiserror::Error;
 use axum::response::{IntoResponse, Response};
 use axum::http::StatusCode;
 use axum::Json;
-use serde_json::json;
 use serde::{Serialize, Deserialize};
 
 #[cfg(test)]
@@ -20,6 +18,7 @@ pub enum MuxlyError {
     #[error("Configuration error: {0}")]
     Configuration(String),
 
+    #[allow(dead_code, reason = "part of the API error codes; no handler returns it yet")]
     #[error("Connector error: {0}")]
     Connector(String),
 
@@ -32,9 +31,11 @@ pub enum MuxlyError {
     #[error("Authentication error: {0}")]
     Authentication(String),
 
+    #[allow(dead_code, reason = "part of the API error codes; no handler returns it yet")]
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[allow(dead_code, reason = "part of the API error codes; no handler returns it yet")]
     #[error("Not found: {0}")]
     NotFound(String),
 
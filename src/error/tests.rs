@@ -1,9 +1,10 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use super::*;
+    use super::super::*;
     use axum::response::IntoResponse;
     use axum::http::StatusCode;
-    use serde_json::json;
+    
 
     #[test]
     fn test_error_status_code() {
@@ -1,37 +1,46 @@
 mod api;
 mod auth;
-mod collector;
 mod config;
 mod error;
+mod router;
 mod scheduler;
 mod storage;
-mod transform;
 
 use std::sync::Arc;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use axum::{
     routing::get,
     Router,
     http::Method,
-    middleware,
     Extension,
 };
 use tower_http::cors::{CorsLayer, Any};
-use tracing::{info, warn, error, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{info, warn, error};
 use tokio::signal;
 
 use error::{MuxlyError, Result};
-use auth::{KeycloakAuth, KeycloakConfig, AuthState};
+use auth::{KeycloakAuth, KeycloakConfig};
 use scheduler::{SchedulerConfig, SchedulerIntegration, ApiSchedulerConfig, CronConfig, WebhookConfig};
 use storage::{DatabaseConfig, init_database, shutdown_database};
+use router::RouterIntegration;
 
 async fn hello_world() -> &'static str {
     "Hello, Muxly!"
 }
 
-async fn health_check() -> &'static str {
-    "OK"
+/// Path of the configuration file: the `--config` argument, or
+/// `config.yml` in the current directory if it exists
+fn config_path() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+
+    let default = PathBuf::from("config.yml");
+    default.exists().then_some(default)
 }
 
 // Graceful shutdown handler
@@ -72,6 +81,11 @@ async fn main() -> Result<()> {
 
     info!("Starting Muxly service");
 
+    // Load configuration
+    let config = config::init_config(config_path())
+        .await
+        .map_err(|e| MuxlyError::Configuration(e.to_string()))?;
+
     // Initialize database
     let db_config = DatabaseConfig {
         url: "sqlite:muxly.db".to_string(),
@@ -81,36 +95,26 @@ async fn main() -> Result<()> {
     let db_pool = init_database(&db_config).await?;
     info!("Database initialized successfully");
 
-    // Initialize Keycloak authentication
-    let keycloak_config = KeycloakConfig {
-        server_url: std::env::var("KEYCLOAK_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()),
-        realm: std::env::var("KEYCLOAK_REALM").unwrap_or_else(|_| "muxly".to_string()),
-        client_id: std::env::var("KEYCLOAK_CLIENT_ID").unwrap_or_else(|_| "muxly-api".to_string()),
-        client_secret: std::env::var("KEYCLOAK_CLIENT_SECRET").unwrap_or_else(|_| "secret".to_string()),
-        required_role: Some("user".to_string()),
-    };
-    
-    let keycloak_auth = match KeycloakAuth::new(keycloak_config).await {
-        Ok(auth) => {
+    // Check API tokens with Keycloak when it's configured
+    let keycloak_auth = match std::env::var("KEYCLOAK_URL") {
+        Ok(server_url) => {
+            let keycloak_config = KeycloakConfig {
+                server_url,
+                realm: std::env::var("KEYCLOAK_REALM").unwrap_or_else(|_| "muxly".to_string()),
+                client_id: std::env::var("KEYCLOAK_CLIENT_ID").unwrap_or_else(|_| "muxly-api".to_string()),
+                client_secret: std::env::var("KEYCLOAK_CLIENT_SECRET").unwrap_or_else(|_| "secret".to_string()),
+                required_role: Some("user".to_string()),
+            };
+            
+            let auth = KeycloakAuth::new(keycloak_config).await?;
             info!("Keycloak authentication initialized successfully");
-            Arc::new(auth)
+            Some(Arc::new(auth))
         },
-        Err(e) => {
-            warn!("Failed to initialize Keycloak authentication: {}", e);
-            warn!("Starting without authentication. THIS IS NOT SECURE FOR PRODUCTION!");
-            Arc::new(KeycloakAuth::new(KeycloakConfig {
-                server_url: "http://localhost:8080".to_string(),
-                realm: "muxly".to_string(),
-                client_id: "muxly-api".to_string(),
-                client_secret: "secret".to_string(),
-                required_role: None,
-            }).await?)
+        Err(_) => {
+            warn!("KEYCLOAK_URL is not set, starting without authentication. THIS IS NOT SECURE FOR PRODUCTION!");
+            None
         }
     };
-    
-    let auth_state = AuthState {
-        keycloak: keycloak_auth.clone(),
-    };
 
     // Initialize scheduler
     let scheduler_config = SchedulerConfig {
@@ -164,18 +168,35 @@ async fn main() -> Result<()> {
     let cron_scheduler = scheduler_integration.cron_scheduler.clone();
     let cron_handler = Arc::new(|| {
         info!("Cron job executed!");
-        Ok(()) as Result<(), anyhow::Error>
+        Ok(()) as anyhow::Result<()>
     });
     
     cron_scheduler.add_job(
         "*/5 * * * * *", // Every 5 seconds
         cron_handler,
-        true,  // enabled
-        false, // catch up
+        true, // enabled
     ).await.map_err(|e| MuxlyError::Scheduler(e.to_string()))?;
     
     info!("Registered cron job");
 
+    // Register an example webhook, checked against the webhook scheduler's secret
+    let webhook_scheduler = scheduler_integration.webhook_scheduler.clone();
+    webhook_scheduler.register_webhook(
+        "example",
+        Arc::new(|payload| Ok(serde_json::json!({ "received": payload }))),
+        None,
+        true,
+    ).await.map_err(|e| MuxlyError::Scheduler(e.to_string()))?;
+
+    info!("Registered webhook at /webhooks/webhook/example");
+
+    // Start the configured routes, after returning deliveries the last run
+    // left in flight to the delivery queue
+    let router_integration = RouterIntegration::start(db_pool.clone(), &config.router).await
+        .map_err(|e| MuxlyError::Router(e.to_string()))?;
+    let router_integration = Arc::new(router_integration);
+    info!("Router started");
+
     // Setup CORS
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
@@ -185,22 +206,30 @@ async fn main() -> Result<()> {
     // Build application with routes
     let app = Router::new()
         .route("/", get(hello_world))
-        .merge(api::api_router())
+        .merge(api::api_router(keycloak_auth))
+        .merge(api::metrics_router(&config.router.prometheus.metrics_path))
         .merge(scheduler_integration.routes())
         .layer(cors)
         .layer(Extension(db_pool.clone()))
-        .layer(Extension(auth_state));
+        .layer(Extension(router_integration.breakers.clone()))
+        .layer(Extension(router_integration.metrics.clone()))
+        .layer(Extension(router_integration.destinations.clone()))
+        .layer(Extension(router_integration.routes.clone()))
+        .layer(Extension(router_integration.queue.clone()))
+        .layer(Extension(router_integration.dead_letters.clone()))
+        .layer(Extension(router_integration.clone()));
 
     // Start server
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     info!("Listening on http://{}", addr);
 
     // Build the server
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let server = axum::Server::try_bind(&addr)
+        .map_err(|e| MuxlyError::Internal(format!("Failed to bind {}: {}", addr, e)))?;
     info!("Server started successfully!");
     
     // Start the server with graceful shutdown
-    axum::serve(listener, app)
+    server.serve(app.into_make_service())
         .with_graceful_shutdown(shutdown_signal())
         .await
         .map_err(|e| MuxlyError::Internal(format!("Server error: {}", e)))?;
@@ -212,6 +241,11 @@ async fn main() -> Result<()> {
     if let Err(e) = scheduler_integration.stop().await {
         error!("Error stopping scheduler: {}", e);
     }
+
+    // Stop the router
+    if let Err(e) = router_integration.stop().await {
+        error!("Error stopping router: {}", e);
+    }
     
     // Shutdown database
     if let Err(e) = shutdown_database(&db_pool).await {
@@ -0,0 +1,351 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use crate::router::{
+    DeadLetter, DeadLetterStore, DeliveryMetrics, DeliveryReceipt, Destination, FailureStage, PartialDelivery,
+    ReceiptStatus, ReceiptStore,
+};
+
+/// Windowed batching settings for a destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSettings {
+    /// How often collected records are delivered, in seconds
+    pub flush_interval: u64,
+    /// Records that trigger a delivery before the interval is over
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+fn default_batch_size() -> usize {
+    500
+}
+
+/// Destination wrapper that collects records and delivers them together
+/// through [`Destination::send_batch`].
+///
+/// Records are delivered every `flush_interval`, or as soon as `batch_size`
+/// of them have been collected. Collected records are held in memory; if a
+/// delivery fails they are kept for the next one. Beyond ten batches the
+/// oldest are dead-lettered, once a route has provided a dead letter store.
+///
+/// Each delivery is recorded in the metrics and receipt store the first
+/// route to use the destination provides. The queued receipts of the
+/// records are settled with the receipt of the batch that delivered them,
+/// or of the failed delivery that got them dead-lettered.
+pub struct BatchingDestination {
+    inner: Arc<dyn Destination>,
+    settings: BatchSettings,
+    /// Records collected for the next delivery
+    records: Mutex<Vec<HeldRecord>>,
+    /// Most records held while deliveries are failing
+    capacity: usize,
+    /// Where records that don't fit are dead-lettered
+    dead_letters: OnceLock<EvictionTarget>,
+    /// Where receipts of deliveries are stored
+    receipts: OnceLock<ReceiptTarget>,
+    /// Metrics deliveries are recorded in, and the route labelling them
+    metrics: OnceLock<(Arc<DeliveryMetrics>, String)>,
+}
+
+/// A collected record and its queued receipt
+struct HeldRecord {
+    record: Value,
+    receipt_id: Option<i64>,
+}
+
+/// Dead letter store and names used for evicted records
+struct EvictionTarget {
+    store: Arc<DeadLetterStore>,
+    route_id: String,
+    destination_key: String,
+}
+
+/// Receipt store and names used for receipts of deliveries
+struct ReceiptTarget {
+    store: Arc<ReceiptStore>,
+    route_id: String,
+    destination_key: Option<String>,
+}
+
+impl BatchingDestination {
+    /// Wrap a destination and start delivering its batches in the background
+    pub fn start(inner: Arc<dyn Destination>, settings: BatchSettings) -> Arc<Self> {
+        let settings = BatchSettings {
+            flush_interval: settings.flush_interval.max(1),
+            batch_size: settings.batch_size.max(1),
+        };
+
+        let destination = Arc::new(Self {
+            inner,
+            records: Mutex::new(Vec::new()),
+            capacity: settings.batch_size.saturating_mul(10),
+            settings,
+            dead_letters: OnceLock::new(),
+            receipts: OnceLock::new(),
+            metrics: OnceLock::new(),
+        });
+
+        // The task stops once the destination is dropped
+        let weak: Weak<Self> = Arc::downgrade(&destination);
+        let period = Duration::from_secs(destination.settings.flush_interval);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let destination = match weak.upgrade() {
+                    Some(destination) => destination,
+                    None => break,
+                };
+
+                if let Err(e) = destination.flush().await {
+                    tracing::error!("Failed to deliver batch to {}: {}", destination.inner.get_id(), e);
+                }
+            }
+        });
+
+        destination
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<HeldRecord>> {
+        match self.records.lock() {
+            Ok(records) => records,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Deliver every collected record now
+    pub async fn flush(&self) -> Result<()> {
+        let batch = std::mem::take(&mut *self.lock());
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let count = batch.len();
+        let records = batch.iter().map(|held| held.record.clone()).collect();
+
+        let started = Instant::now();
+        let result = self.inner.send_batch(records).await;
+        let duration = started.elapsed();
+
+        if let Some((metrics, route_id)) = self.metrics.get() {
+            metrics.record(route_id, self.inner.as_ref(), count, duration, &result);
+        }
+
+        // Only the records the destination rejected are sent again
+        let (accepted, rejected) = match &result {
+            Ok(()) => (batch, Vec::new()),
+            Err(e) => match e.downcast_ref::<PartialDelivery>() {
+                Some(partial) => partial.split(batch),
+                None => (Vec::new(), batch),
+            },
+        };
+
+        if !accepted.is_empty() {
+            self.receipt(&accepted, duration, None, true).await;
+        }
+
+        if let Err(e) = result {
+            let failure = self.receipt(&rejected, duration, Some(&e), false).await;
+            let evicted = self.restore(rejected);
+            self.evict(evicted, &e, failure).await;
+            return Err(e);
+        }
+
+        tracing::debug!("Delivered batch of {} records to {}", count, self.inner.get_id());
+        Ok(())
+    }
+
+    /// Store a receipt for records of a batch that was delivered, or
+    /// rejected with `error`; settles their queued receipts with it if
+    /// `settle` is set. Returns the stored receipt.
+    ///
+    /// Failing to store it is logged, not returned, so the delivery itself
+    /// still counts.
+    async fn receipt(
+        &self,
+        batch: &[HeldRecord],
+        duration: Duration,
+        error: Option<&anyhow::Error>,
+        settle: bool,
+    ) -> Option<DeliveryReceipt> {
+        let target = self.receipts.get()?;
+
+        let status = match error {
+            None => ReceiptStatus::Delivered,
+            Some(_) => ReceiptStatus::Failed,
+        };
+
+        let mut receipt = DeliveryReceipt::new(&target.route_id, self.inner.get_id(), batch.len(), status, duration);
+        if let Some(key) = &target.destination_key {
+            receipt = receipt.with_destination_key(key);
+        }
+        if let Some(e) = error {
+            receipt = receipt.with_error(e);
+        }
+
+        let queued: Vec<i64> = if settle {
+            batch.iter().filter_map(|held| held.receipt_id).collect()
+        } else {
+            Vec::new()
+        };
+
+        match target.store.record_batch(&receipt, &queued).await {
+            Ok(id) => {
+                receipt.id = Some(id);
+                Some(receipt)
+            },
+            Err(e) => {
+                tracing::error!("Failed to record delivery receipt for {}: {}", self.inner.get_id(), e);
+                None
+            }
+        }
+    }
+
+    /// Put a batch that couldn't be delivered back in front of newer
+    /// records; returns the oldest records that no longer fit
+    fn restore(&self, mut batch: Vec<HeldRecord>) -> Vec<HeldRecord> {
+        let mut records = self.lock();
+        batch.append(&mut records);
+
+        let evicted = if batch.len() > self.capacity {
+            let excess = batch.len() - self.capacity;
+            batch.drain(..excess).collect()
+        } else {
+            Vec::new()
+        };
+
+        *records = batch;
+        evicted
+    }
+
+    /// Dead-letter records that were evicted after failed deliveries, and
+    /// settle their queued receipts with the receipt of the last failure
+    async fn evict(&self, records: Vec<HeldRecord>, error: &anyhow::Error, failure: Option<DeliveryReceipt>) {
+        if records.is_empty() {
+            return;
+        }
+
+        if let (Some(target), Some(failure)) = (self.receipts.get(), failure) {
+            let queued: Vec<i64> = records.iter().filter_map(|held| held.receipt_id).collect();
+            if let Err(e) = target.store.settle(&queued, &failure).await {
+                tracing::error!("Failed to settle delivery receipts for {}: {}", self.inner.get_id(), e);
+            }
+        }
+
+        let target = match self.dead_letters.get() {
+            Some(target) => target,
+            None => {
+                tracing::warn!("Dropped {} undelivered records for {}", records.len(), self.inner.get_id());
+                return;
+            }
+        };
+
+        let count = records.len();
+        for held in records {
+            let letter = DeadLetter::new(&target.route_id, FailureStage::Delivery, held.record, error)
+                .with_destination(&target.destination_key);
+
+            if let Err(e) = target.store.publish(letter).await {
+                tracing::error!("Failed to dead-letter record evicted from {}: {}", self.inner.get_id(), e);
+            }
+        }
+
+        tracing::warn!("Dead-lettered {} undelivered records for {}", count, self.inner.get_id());
+    }
+
+    /// Collect records with their queued receipts, delivering them if the
+    /// batch is full.
+    ///
+    /// A failed delivery isn't reported to the caller: the records were
+    /// accepted and are retried with the next batch.
+    async fn collect(&self, data: Vec<Value>, receipt_ids: Vec<Option<i64>>) -> Result<()> {
+        let receipt_ids = receipt_ids.into_iter().chain(std::iter::repeat(None));
+
+        let full = {
+            let mut records = self.lock();
+            records.extend(data.into_iter().zip(receipt_ids).map(|(record, receipt_id)| HeldRecord { record, receipt_id }));
+            records.len() >= self.settings.batch_size
+        };
+
+        if full {
+            if let Err(e) = self.flush().await {
+                tracing::error!("Failed to deliver batch to {}: {}", self.inner.get_id(), e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Destination for BatchingDestination {
+    fn get_type(&self) -> &str {
+        self.inner.get_type()
+    }
+
+    fn get_id(&self) -> &str {
+        self.inner.get_id()
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.collect(vec![data], Vec::new()).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        self.collect(data, Vec::new()).await
+    }
+
+    async fn send_with_receipts(&self, data: Vec<Value>, receipt_ids: Vec<Option<i64>>) -> Result<()> {
+        self.collect(data, receipt_ids).await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        self.inner.check_availability().await
+    }
+
+    fn set_source_metadata(&self, metadata: &Value) {
+        self.inner.set_source_metadata(metadata);
+    }
+
+    fn is_buffered(&self) -> bool {
+        true
+    }
+
+    /// Keeps the first store it is given; a shared destination's evicted
+    /// records are dead-lettered under the first route that uses it
+    fn set_dead_letters(&self, store: Arc<DeadLetterStore>, route_id: &str, destination_key: &str) {
+        let _ = self.dead_letters.set(EvictionTarget {
+            store,
+            route_id: route_id.to_string(),
+            destination_key: destination_key.to_string(),
+        });
+    }
+
+    /// Keeps the first store it is given, like the dead letter store
+    fn set_receipts(&self, store: Arc<ReceiptStore>, route_id: &str, destination_key: Option<&str>) {
+        let _ = self.receipts.set(ReceiptTarget {
+            store,
+            route_id: route_id.to_string(),
+            destination_key: destination_key.map(str::to_string),
+        });
+    }
+
+    fn set_metrics(&self, metrics: Arc<DeliveryMetrics>, route_id: &str) {
+        let _ = self.metrics.set((metrics, route_id.to_string()));
+    }
+
+    async fn flush(&self) -> Result<()> {
+        BatchingDestination::flush(self).await
+    }
+
+    fn scrape(&self) -> Option<String> {
+        self.inner.scrape()
+    }
+}
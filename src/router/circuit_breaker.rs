@@ -0,0 +1,297 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::config::models::router::GlobalRouterConfig;
+use crate::router::Destination;
+
+/// Settings for destination circuit breakers
+#[derive(Debug, Clone)]
+pub struct BreakerSettings {
+    /// Whether breakers ever open
+    pub enabled: bool,
+    /// Consecutive failures that open a breaker
+    pub failure_threshold: u32,
+    /// How long a breaker stays open before the destination is probed
+    pub open_duration: Duration,
+}
+
+impl From<&GlobalRouterConfig> for BreakerSettings {
+    fn from(config: &GlobalRouterConfig) -> Self {
+        let breaker = &config.circuit_breaker;
+
+        Self {
+            enabled: breaker.enabled,
+            failure_threshold: breaker.failure_threshold.max(1),
+            open_duration: Duration::from_secs(breaker.open_duration.max(1)),
+        }
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Records are sent to the destination
+    Closed,
+    /// The destination is failing; records are not sent to it
+    Open,
+    /// The destination passed a probe and one trial record is being sent
+    HalfOpen,
+}
+
+/// Snapshot of a circuit breaker, as reported by the health API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakerStatus {
+    /// Stable key of the destination
+    pub key: String,
+    /// Destination ID
+    pub destination_id: String,
+    /// Destination type
+    pub destination_type: String,
+    /// Current state
+    pub state: BreakerState,
+    /// Failures since the last success
+    pub consecutive_failures: u32,
+    /// When the breaker last opened, while it isn't closed
+    pub open_since: Option<DateTime<Utc>>,
+    /// Last error returned by the destination
+    pub last_error: Option<String>,
+}
+
+/// Mutable state of a breaker
+#[derive(Debug)]
+struct BreakerInner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    /// When the breaker opened, for the open duration
+    opened_at: Option<Instant>,
+    /// When the breaker opened, for reporting
+    open_since: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// Permission to send to a destination, given by [`CircuitBreaker::allow`].
+///
+/// Hold it until the send's result is recorded. The permit for a trial
+/// record opens the breaker again if it is dropped while the breaker is
+/// still half-open, e.g. because the probe or the send was cancelled, so
+/// the breaker isn't left half-open for good.
+#[must_use]
+pub struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    trial: bool,
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if !self.trial {
+            return;
+        }
+
+        let mut inner = self.breaker.lock();
+        if inner.state == BreakerState::HalfOpen {
+            self.breaker.open(&mut inner);
+        }
+    }
+}
+
+/// Circuit breaker for a single destination.
+///
+/// Opens after `failure_threshold` consecutive failures. Once
+/// `open_duration` has passed, the next record triggers a probe with
+/// [`Destination::check_availability`]; if the destination reports itself
+/// available the breaker goes half-open and lets that record through as a
+/// trial, which closes the breaker on success or opens it again on failure.
+/// A probe that doesn't answer within the destination's timeout counts as
+/// unavailable.
+pub struct CircuitBreaker {
+    key: String,
+    destination_id: String,
+    destination_type: String,
+    settings: BreakerSettings,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker for a destination
+    pub fn new(key: &str, destination: &dyn Destination, settings: BreakerSettings) -> Self {
+        Self {
+            key: key.to_string(),
+            destination_id: destination.get_id().to_string(),
+            destination_type: destination.get_type().to_string(),
+            settings,
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                open_since: None,
+                last_error: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerInner> {
+        match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Current state of the breaker
+    #[cfg(test)]
+    pub fn state(&self) -> BreakerState {
+        self.lock().state
+    }
+
+    /// Permission to send a record to the destination now, or `None` if
+    /// the breaker doesn't allow it.
+    ///
+    /// Probes the destination, within `timeout`, when an open breaker is
+    /// due for it.
+    pub async fn allow(&self, destination: &dyn Destination, timeout: Duration) -> Option<BreakerPermit<'_>> {
+        {
+            let mut inner = self.lock();
+
+            match inner.state {
+                BreakerState::Closed => return Some(BreakerPermit { breaker: self, trial: false }),
+                // A trial is already under way
+                BreakerState::HalfOpen => return None,
+                BreakerState::Open => {
+                    let due = inner.opened_at
+                        .is_none_or(|opened_at| opened_at.elapsed() >= self.settings.open_duration);
+                    if !due {
+                        return None;
+                    }
+
+                    // Only the caller that makes this transition probes
+                    inner.state = BreakerState::HalfOpen;
+                }
+            }
+        }
+
+        // Opens the breaker again unless the trial goes ahead
+        let permit = BreakerPermit { breaker: self, trial: true };
+
+        let available = match tokio::time::timeout(timeout, destination.check_availability()).await {
+            Ok(Ok(available)) => available,
+            Ok(Err(e)) => {
+                tracing::debug!("Availability probe of {} failed: {}", self.key, e);
+                false
+            },
+            Err(_) => {
+                tracing::debug!("Availability probe of {} timed out after {}s", self.key, timeout.as_secs());
+                false
+            },
+        };
+
+        if !available {
+            return None;
+        }
+
+        tracing::info!("Destination {} is available again, sending a trial record", self.key);
+        Some(permit)
+    }
+
+    /// Record a successful send
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+
+        if inner.state != BreakerState::Closed {
+            tracing::info!("Circuit breaker for destination {} closed", self.key);
+        }
+
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.open_since = None;
+    }
+
+    /// Record a failed send
+    pub fn record_failure(&self, error: &str) {
+        let mut inner = self.lock();
+
+        inner.consecutive_failures += 1;
+        inner.last_error = Some(error.to_string());
+
+        let trip = match inner.state {
+            BreakerState::HalfOpen => true,
+            BreakerState::Closed => {
+                self.settings.enabled && inner.consecutive_failures >= self.settings.failure_threshold
+            },
+            BreakerState::Open => false,
+        };
+
+        if trip {
+            tracing::warn!(
+                "Circuit breaker for destination {} opened after {} consecutive failures: {}",
+                self.key, inner.consecutive_failures, error
+            );
+            self.open(&mut inner);
+        }
+    }
+
+    /// Open the breaker, keeping the time it first opened for reporting
+    fn open(&self, inner: &mut BreakerInner) {
+        inner.state = BreakerState::Open;
+        inner.opened_at = Some(Instant::now());
+        inner.open_since.get_or_insert_with(Utc::now);
+    }
+
+    /// Snapshot of the breaker
+    pub fn status(&self) -> BreakerStatus {
+        let inner = self.lock();
+
+        BreakerStatus {
+            key: self.key.clone(),
+            destination_id: self.destination_id.clone(),
+            destination_type: self.destination_type.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            open_since: inner.open_since,
+            last_error: inner.last_error.clone(),
+        }
+    }
+}
+
+/// Circuit breakers of all destinations, by destination key
+pub struct BreakerRegistry {
+    settings: BreakerSettings,
+    breakers: Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+}
+
+impl BreakerRegistry {
+    /// Create an empty registry
+    pub fn new(settings: BreakerSettings) -> Self {
+        Self {
+            settings,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a destination's breaker, creating it if needed
+    pub fn breaker(&self, key: &str, destination: &dyn Destination) -> Arc<CircuitBreaker> {
+        let mut breakers = match self.breakers.lock() {
+            Ok(breakers) => breakers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        Arc::clone(breakers.entry(key.to_string()).or_insert_with(|| {
+            Arc::new(CircuitBreaker::new(key, destination, self.settings.clone()))
+        }))
+    }
+
+    /// Snapshot of every breaker, sorted by destination key
+    pub fn statuses(&self) -> Vec<BreakerStatus> {
+        let breakers = match self.breakers.lock() {
+            Ok(breakers) => breakers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let mut statuses: Vec<_> = breakers.values().map(|breaker| breaker.status()).collect();
+        statuses.sort_by(|a, b| a.key.cmp(&b.key));
+        statuses
+    }
+}
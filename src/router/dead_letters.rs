@@ -0,0 +1,371 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use sqlx::sqlite::SqliteRow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::router::{DeliveryOrigin, DeliveryQueue, Destination};
+use crate::storage::DatabasePool;
+
+/// Stage of routing a record failed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureStage {
+    /// A transformation failed, before any destination was tried
+    Transformation,
+    /// The route's or a rule's condition couldn't be evaluated
+    Condition,
+    /// A destination rejected the record
+    Delivery,
+}
+
+impl FailureStage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FailureStage::Transformation => "transformation",
+            FailureStage::Condition => "condition",
+            FailureStage::Delivery => "delivery",
+        }
+    }
+
+    fn parse(stage: &str) -> Self {
+        match stage {
+            "transformation" => FailureStage::Transformation,
+            "condition" => FailureStage::Condition,
+            _ => FailureStage::Delivery,
+        }
+    }
+}
+
+/// Envelope around a record that couldn't be routed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// ID in the dead letter store (set once stored)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Route the record was processed by
+    pub route_id: String,
+    /// Rule that matched the record (`default` when the route has no rules)
+    pub rule_id: Option<String>,
+    /// Key of the destination that failed, for delivery failures
+    pub destination: Option<String>,
+    /// Stage the record failed in
+    pub stage: FailureStage,
+    /// Error message
+    pub error: String,
+    /// Number of delivery attempts made
+    pub attempts: u32,
+    /// The original record, before any transformation
+    pub record: Value,
+    /// The record as it was sent to the destination, for delivery failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Value>,
+    /// When the record failed
+    pub failed_at: DateTime<Utc>,
+    /// When the record was re-driven, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redriven_at: Option<DateTime<Utc>>,
+}
+
+impl DeadLetter {
+    /// Create an envelope for a record that failed now
+    pub fn new(route_id: &str, stage: FailureStage, record: Value, error: impl ToString) -> Self {
+        Self {
+            id: None,
+            route_id: route_id.to_string(),
+            rule_id: None,
+            destination: None,
+            stage,
+            error: error.to_string(),
+            attempts: 1,
+            record,
+            payload: None,
+            failed_at: Utc::now(),
+            redriven_at: None,
+        }
+    }
+
+    /// Set the rule the record matched
+    pub fn with_rule(mut self, rule_id: &str) -> Self {
+        self.rule_id = Some(rule_id.to_string());
+        self
+    }
+
+    /// Set the destination that failed
+    pub fn with_destination(mut self, destination: &str) -> Self {
+        self.destination = Some(destination.to_string());
+        self
+    }
+
+    /// Set the record as it was sent to the destination
+    pub fn with_payload(mut self, payload: Value) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Set the number of attempts made
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self> {
+        let record: String = row.try_get("record")?;
+        let payload: Option<String> = row.try_get("payload")?;
+        let stage: String = row.try_get("stage")?;
+
+        Ok(Self {
+            id: Some(row.try_get("id")?),
+            route_id: row.try_get("route_id")?,
+            rule_id: row.try_get("rule_id")?,
+            destination: row.try_get("destination_key")?,
+            stage: FailureStage::parse(&stage),
+            error: row.try_get("error")?,
+            attempts: row.try_get::<i64, _>("attempts")? as u32,
+            record: serde_json::from_str(&record)?,
+            payload: payload.map(|p| serde_json::from_str(&p)).transpose()?,
+            failed_at: row.try_get("failed_at")?,
+            redriven_at: row.try_get("redriven_at")?,
+        })
+    }
+}
+
+/// Filter for listing dead letters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeadLetterFilter {
+    /// Only letters from this route
+    pub route_id: Option<String>,
+    /// Include letters that were already re-driven
+    #[serde(default)]
+    pub include_redriven: bool,
+    /// Maximum number of letters to return
+    pub limit: Option<i64>,
+    /// Number of letters to skip
+    pub offset: Option<i64>,
+}
+
+/// Outcome of re-driving a dead letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedriveOutcome {
+    /// The record was put back on its destination's delivery queue
+    Redriven,
+    /// No dead letter with that ID
+    NotFound,
+    /// The record failed before delivery, so there is no destination to retry
+    NotRedrivable,
+    /// The record was already re-driven
+    AlreadyRedriven,
+    /// No queue worker delivers to the record's destination, because the
+    /// route doesn't deliver through the delivery queue or isn't running
+    NotQueued,
+    /// The destination's delivery queue already holds `buffer_size` records
+    QueueFull,
+}
+
+/// Outcome of re-driving dead letters in bulk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RedriveSummary {
+    /// Letters put back on a delivery queue
+    pub redriven: usize,
+    /// Letters left alone because no queue worker delivers to their destination
+    pub not_queued: usize,
+    /// Letters left alone because their destination's queue is full
+    pub queue_full: usize,
+}
+
+/// Store of records that couldn't be routed, kept in the SQLite database.
+///
+/// Every dead letter is stored so it can be inspected and re-driven, and is
+/// also sent to its route's error destination when one is configured.
+pub struct DeadLetterStore {
+    /// Database holding the dead letters
+    pool: Arc<DatabasePool>,
+    /// Error destination of each route, by route ID
+    error_destinations: RwLock<HashMap<String, Arc<dyn Destination>>>,
+}
+
+impl DeadLetterStore {
+    /// Create a new dead letter store
+    pub fn new(pool: Arc<DatabasePool>) -> Self {
+        Self {
+            pool,
+            error_destinations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Send a route's dead letters to an error destination
+    pub fn register_error_destination(&self, route_id: &str, destination: Arc<dyn Destination>) {
+        if let Ok(mut destinations) = self.error_destinations.write() {
+            destinations.insert(route_id.to_string(), destination);
+        }
+    }
+
+    /// Store a dead letter and forward it to the route's error destination
+    pub async fn publish(&self, mut letter: DeadLetter) -> Result<i64> {
+        let id = self.insert(&letter).await?;
+        letter.id = Some(id);
+
+        let destination = self.error_destinations.read()
+            .ok()
+            .and_then(|destinations| destinations.get(&letter.route_id).cloned());
+
+        if let Some(destination) = destination {
+            if let Err(e) = destination.send(serde_json::to_value(&letter)?).await {
+                tracing::error!(
+                    "Failed to send dead letter {} to error destination {}: {}",
+                    id, destination.get_id(), e
+                );
+            }
+        }
+
+        Ok(id)
+    }
+
+    /// Store a dead letter
+    async fn insert(&self, letter: &DeadLetter) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO dead_letters
+                (route_id, rule_id, destination_key, stage, error, attempts, record, payload, failed_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&letter.route_id)
+        .bind(&letter.rule_id)
+        .bind(&letter.destination)
+        .bind(letter.stage.as_str())
+        .bind(&letter.error)
+        .bind(letter.attempts as i64)
+        .bind(serde_json::to_string(&letter.record)?)
+        .bind(letter.payload.as_ref().map(serde_json::to_string).transpose()?)
+        .bind(letter.failed_at)
+        .execute(self.pool.as_ref())
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// List dead letters, newest first
+    pub async fn list(&self, filter: &DeadLetterFilter) -> Result<Vec<DeadLetter>> {
+        let rows = sqlx::query(
+            "SELECT * FROM dead_letters
+             WHERE (?1 IS NULL OR route_id = ?1)
+               AND (?2 OR redriven_at IS NULL)
+             ORDER BY id DESC
+             LIMIT ?3 OFFSET ?4"
+        )
+        .bind(&filter.route_id)
+        .bind(filter.include_redriven)
+        .bind(filter.limit.unwrap_or(100))
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        rows.iter().map(DeadLetter::from_row).collect()
+    }
+
+    /// Get a dead letter by ID
+    pub async fn get(&self, id: i64) -> Result<Option<DeadLetter>> {
+        let row = sqlx::query("SELECT * FROM dead_letters WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool.as_ref())
+            .await?;
+
+        row.as_ref().map(DeadLetter::from_row).transpose()
+    }
+
+    /// Delete a dead letter; returns whether it existed
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM dead_letters WHERE id = ?")
+            .bind(id)
+            .execute(self.pool.as_ref())
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Put a dead letter's record back on its destination's delivery queue.
+    ///
+    /// The record is queued as it was sent to the destination, so it isn't
+    /// transformed again. Only records whose destination has a running
+    /// queue worker are queued, so none is left waiting for a worker that
+    /// may never start, and the queue's `buffer_size` applies as it does to
+    /// routed records.
+    pub async fn redrive(&self, id: i64, queue: &DeliveryQueue) -> Result<RedriveOutcome> {
+        let letter = match self.get(id).await? {
+            Some(letter) => letter,
+            None => return Ok(RedriveOutcome::NotFound),
+        };
+
+        if letter.redriven_at.is_some() {
+            return Ok(RedriveOutcome::AlreadyRedriven);
+        }
+
+        let destination = match (&letter.stage, &letter.destination) {
+            (FailureStage::Delivery, Some(destination)) => destination,
+            _ => return Ok(RedriveOutcome::NotRedrivable),
+        };
+
+        if !queue.is_running(destination) {
+            return Ok(RedriveOutcome::NotQueued);
+        }
+
+        let payload = letter.payload.as_ref().unwrap_or(&letter.record);
+        let origin = letter.rule_id.as_deref().map(|rule_id| DeliveryOrigin { rule_id, record: &letter.record });
+
+        let mut tx = self.pool.begin().await?;
+
+        // Claim the letter before queueing it, so of two concurrent
+        // re-drives only one queues the record
+        let claimed = sqlx::query("UPDATE dead_letters SET redriven_at = ? WHERE id = ? AND redriven_at IS NULL")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        if claimed.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(RedriveOutcome::AlreadyRedriven);
+        }
+
+        let pending = match queue.insert(&mut tx, &letter.route_id, destination, std::slice::from_ref(payload), origin, None).await? {
+            Some(pending) => pending,
+            None => {
+                tx.rollback().await?;
+                return Ok(RedriveOutcome::QueueFull);
+            },
+        };
+
+        tx.commit().await?;
+        queue.wake(destination, pending);
+
+        tracing::info!("Re-drove dead letter {} to {}", id, destination);
+        Ok(RedriveOutcome::Redriven)
+    }
+
+    /// Re-drive every delivery failure that wasn't re-driven yet, optionally for one route
+    pub async fn redrive_all(&self, route_id: Option<&str>, queue: &DeliveryQueue) -> Result<RedriveSummary> {
+        let ids: Vec<(i64,)> = sqlx::query_as(
+            "SELECT id FROM dead_letters
+             WHERE (?1 IS NULL OR route_id = ?1)
+               AND stage = 'delivery' AND destination_key IS NOT NULL AND redriven_at IS NULL
+             ORDER BY id"
+        )
+        .bind(route_id)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let mut summary = RedriveSummary::default();
+        for (id,) in ids {
+            match self.redrive(id, queue).await? {
+                RedriveOutcome::Redriven => summary.redriven += 1,
+                RedriveOutcome::NotQueued => summary.not_queued += 1,
+                RedriveOutcome::QueueFull => summary.queue_full += 1,
+                RedriveOutcome::NotFound | RedriveOutcome::NotRedrivable | RedriveOutcome::AlreadyRedriven => {},
+            }
+        }
+
+        Ok(summary)
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::router::routing::select_field;
+use crate::storage::DatabasePool;
+
+/// How often expired keys are purged
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Deduplication settings for a route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupSettings {
+    /// Fields that together identify a record (names or JSONPaths)
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// JSONPath selecting the record's key, instead of `fields`
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// How long a key is remembered, in seconds
+    #[serde(default = "default_window")]
+    pub window: u64,
+}
+
+fn default_window() -> u64 {
+    7 * 24 * 3600
+}
+
+impl DedupSettings {
+    /// Check that the record key is configured exactly one way
+    pub fn validate(&self) -> Result<()> {
+        match (self.fields.is_empty(), &self.key_path) {
+            (true, None) => Err(anyhow!("Dedup needs either fields or key_path")),
+            (false, Some(_)) => Err(anyhow!("Dedup takes fields or key_path, not both")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compute a record's idempotency key.
+    ///
+    /// Returns `None` when a key field is missing, so records that can't be
+    /// identified are never dropped.
+    pub fn key(&self, record: &Value) -> Result<Option<String>> {
+        let paths = match &self.key_path {
+            Some(path) => std::slice::from_ref(path),
+            None => self.fields.as_slice(),
+        };
+
+        let mut parts = Vec::with_capacity(paths.len());
+        for path in paths {
+            match select_field(path, record)? {
+                Some(value) => parts.push(value),
+                None => return Ok(None),
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_string(&parts)?.as_bytes());
+        Ok(Some(hex::encode(hasher.finalize())))
+    }
+}
+
+/// Time-bounded set of the record keys each route has seen, stored in the
+/// SQLite database so duplicates are caught across restarts
+pub struct DedupStore {
+    /// Database holding the keys
+    pool: Arc<DatabasePool>,
+    /// When expired keys were last purged
+    last_purge: Mutex<Instant>,
+}
+
+impl DedupStore {
+    /// Create a new dedup store
+    pub fn new(pool: Arc<DatabasePool>) -> Self {
+        Self {
+            pool,
+            last_purge: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Claim a key for `window`; returns `false` if the key is already
+    /// claimed, i.e. the record is a duplicate.
+    ///
+    /// Claiming is atomic, so of several records with the same key
+    /// processed at once only one gets through. A key that is still live
+    /// keeps its original window, so repeated deliveries don't extend it.
+    pub async fn claim(&self, route_id: &str, key: &str, window: Duration) -> Result<bool> {
+        self.purge_if_due().await;
+
+        let now = Utc::now().timestamp_millis();
+        let expires_at = now + window.as_millis() as i64;
+
+        // An expired key can be claimed again
+        sqlx::query("DELETE FROM dedup_keys WHERE route_id = ? AND key_hash = ? AND expires_at <= ?")
+            .bind(route_id)
+            .bind(key)
+            .bind(now)
+            .execute(self.pool.as_ref())
+            .await?;
+
+        let result = sqlx::query(
+            "INSERT INTO dedup_keys (route_id, key_hash, first_seen_at, expires_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT (route_id, key_hash) DO NOTHING"
+        )
+        .bind(route_id)
+        .bind(key)
+        .bind(now)
+        .bind(expires_at)
+        .execute(self.pool.as_ref())
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Give up a claim on a key, so the next record with it isn't a
+    /// duplicate
+    pub async fn release(&self, route_id: &str, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM dedup_keys WHERE route_id = ? AND key_hash = ?")
+            .bind(route_id)
+            .bind(key)
+            .execute(self.pool.as_ref())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete expired keys; returns how many were deleted
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM dedup_keys WHERE expires_at <= ?")
+            .bind(Utc::now().timestamp_millis())
+            .execute(self.pool.as_ref())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Purge expired keys at most once per `PURGE_INTERVAL`
+    async fn purge_if_due(&self) {
+        {
+            let mut last_purge = match self.last_purge.lock() {
+                Ok(last_purge) => last_purge,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+
+            if last_purge.elapsed() < PURGE_INTERVAL {
+                return;
+            }
+            *last_purge = Instant::now();
+        }
+
+        match self.purge_expired().await {
+            Ok(purged) if purged > 0 => tracing::debug!("Purged {} expired dedup keys", purged),
+            Ok(_) => {},
+            Err(e) => tracing::warn!("Failed to purge expired dedup keys: {}", e),
+        }
+    }
+}
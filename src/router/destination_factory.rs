@@ -1,25 +1,101 @@
 use anyhow::{Result, anyhow};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::sync::Arc;
 
+use crate::config::models::router::{PrometheusConfig, RouterConfig, WebhookOutputConfig};
 use crate::router::{
-    Destination, DestinationSettings,
-    DatabaseDestination, EmailDestination, FileDestination, 
-    PrometheusDestination, SlackDestination, S3Destination, WebhookDestination
+    BatchingDestination, Destination, DestinationSettings,
+    DatabaseDestination, EmailDestination, FileDestination, InfluxDestination,
+    OtlpDestination, PrometheusDestination, SearchIndexDestination, SlackDestination,
+    S3Destination, StatsdDestination, WebhookDestination
 };
+#[cfg(feature = "kafka")]
+use crate::router::KafkaDestination;
+
+/// Router-wide settings that destinations fall back on for the options
+/// their own configuration leaves out
+#[derive(Debug, Clone, Default)]
+pub struct DestinationDefaults {
+    /// Defaults of Prometheus destinations (`include_labels`, `default_metric_type`)
+    pub prometheus: PrometheusConfig,
+    /// Defaults of webhook destinations (`max_concurrency`, `timeout`)
+    pub webhook: WebhookOutputConfig,
+}
+
+impl From<&RouterConfig> for DestinationDefaults {
+    fn from(config: &RouterConfig) -> Self {
+        Self {
+            prometheus: config.prometheus.clone(),
+            webhook: config.webhook.clone(),
+        }
+    }
+}
+
+impl DestinationDefaults {
+    /// Fill in the options a Prometheus destination's config leaves out
+    fn prometheus(&self, config: &Value) -> Value {
+        Self::merge(config, json!({
+            "include_labels": self.prometheus.include_labels,
+            "default_metric_type": self.prometheus.default_metric_type,
+        }))
+    }
+    
+    /// Fill in the options a webhook destination's config leaves out
+    fn webhook(&self, config: &Value) -> Value {
+        Self::merge(config, json!({
+            "max_concurrency": self.webhook.max_concurrency,
+            "timeout": self.webhook.timeout,
+        }))
+    }
+    
+    /// `config` with every key of `defaults` it doesn't have
+    fn merge(config: &Value, defaults: Value) -> Value {
+        let mut config = config.clone();
+        if let (Some(options), Value::Object(defaults)) = (config.as_object_mut(), defaults) {
+            for (key, value) in defaults {
+                options.entry(key).or_insert(value);
+            }
+        }
+        config
+    }
+}
 
 /// Factory for creating destination instances from configuration
 pub struct DestinationFactory;
 
 impl DestinationFactory {
-    /// Create a new destination based on the provided settings
-    pub fn create_destination(settings: &DestinationSettings) -> Result<Arc<dyn Destination>> {
+    /// ID for a destination: the configured one, or a random one with the type's prefix
+    fn destination_id(settings: &DestinationSettings, prefix: &str) -> String {
+        settings.id.clone().unwrap_or_else(|| format!("{}_{}", prefix, uuid::Uuid::new_v4()))
+    }
+    
+    /// Create a new destination, taking the options its settings leave out
+    /// from the router-wide `defaults`, wrapped in a [`BatchingDestination`]
+    /// if it batches
+    pub fn create_destination_with_defaults(
+        settings: &DestinationSettings,
+        defaults: &DestinationDefaults,
+    ) -> Result<Arc<dyn Destination>> {
+        let destination = Self::create_unbatched(settings, defaults)?;
+        
+        Ok(match &settings.batch {
+            Some(batch) => BatchingDestination::start(destination, batch.clone()),
+            None => destination,
+        })
+    }
+    
+    /// Create the destination itself
+    fn create_unbatched(settings: &DestinationSettings, defaults: &DestinationDefaults) -> Result<Arc<dyn Destination>> {
         match settings.destination_type.as_str() {
             "database" => {
                 let config: crate::router::destinations::database::DatabaseDestinationConfig = 
                     serde_json::from_value(settings.config.clone())?;
+
+                // Reject invalid settings up front; the pool connects on first use
+                config.validate()?;
+
                 Ok(Arc::new(DatabaseDestination::new(
-                    format!("db_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "db"),
                     config,
                 )))
             },
@@ -27,23 +103,62 @@ impl DestinationFactory {
                 let config: crate::router::destinations::email::EmailDestinationConfig = 
                     serde_json::from_value(settings.config.clone())?;
                 Ok(Arc::new(EmailDestination::new(
-                    format!("email_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "email"),
                     config,
                 )))
             },
             "file" => {
                 let config: crate::router::destinations::file::FileDestinationConfig = 
                     serde_json::from_value(settings.config.clone())?;
+                if config.format == "parquet" {
+                    config.parquet.validate()?;
+                }
                 Ok(Arc::new(FileDestination::new(
-                    format!("file_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "file"),
                     config,
                 )))
             },
-            "prometheus" => {
-                let config: crate::router::destinations::prometheus::PrometheusDestinationConfig = 
+            "influx" => {
+                let config: crate::router::destinations::influx::InfluxDestinationConfig =
+                    serde_json::from_value(settings.config.clone())?;
+                Ok(Arc::new(InfluxDestination::new(
+                    Self::destination_id(settings, "influx"),
+                    config,
+                )))
+            },
+            #[cfg(feature = "kafka")]
+            "kafka" => {
+                let config: crate::router::destinations::kafka::KafkaDestinationConfig =
+                    serde_json::from_value(settings.config.clone())?;
+                Ok(Arc::new(KafkaDestination::new(
+                    Self::destination_id(settings, "kafka"),
+                    config,
+                )))
+            },
+            #[cfg(not(feature = "kafka"))]
+            "kafka" => Err(anyhow!("Kafka destinations need Muxly built with the `kafka` feature")),
+            "otlp" => {
+                let config: crate::router::destinations::otlp::OtlpDestinationConfig =
                     serde_json::from_value(settings.config.clone())?;
+                config.validate()?;
+                Ok(Arc::new(OtlpDestination::new(
+                    Self::destination_id(settings, "otlp"),
+                    config,
+                )))
+            },
+            "prometheus" => {
+                let config: crate::router::destinations::prometheus::PrometheusDestinationConfig =
+                    serde_json::from_value(defaults.prometheus(&settings.config))?;
                 Ok(Arc::new(PrometheusDestination::new(
-                    format!("prometheus_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "prometheus"),
+                    config,
+                )))
+            },
+            "search_index" => {
+                let config: crate::router::destinations::search_index::SearchIndexDestinationConfig =
+                    serde_json::from_value(settings.config.clone())?;
+                Ok(Arc::new(SearchIndexDestination::new(
+                    Self::destination_id(settings, "search_index"),
                     config,
                 )))
             },
@@ -51,23 +166,34 @@ impl DestinationFactory {
                 let config: crate::router::destinations::slack::SlackDestinationConfig = 
                     serde_json::from_value(settings.config.clone())?;
                 Ok(Arc::new(SlackDestination::new(
-                    format!("slack_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "slack"),
                     config,
                 )))
             },
             "s3" => {
                 let config: crate::router::destinations::storage::S3DestinationConfig = 
                     serde_json::from_value(settings.config.clone())?;
+                if config.format == "parquet" {
+                    config.parquet.validate()?;
+                }
                 Ok(Arc::new(S3Destination::new(
-                    format!("s3_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "s3"),
+                    config,
+                )))
+            },
+            "statsd" => {
+                let config: crate::router::destinations::statsd::StatsdDestinationConfig =
+                    serde_json::from_value(settings.config.clone())?;
+                Ok(Arc::new(StatsdDestination::new(
+                    Self::destination_id(settings, "statsd"),
                     config,
                 )))
             },
             "webhook" => {
                 let config: crate::router::destinations::webhook::WebhookDestinationConfig = 
-                    serde_json::from_value(settings.config.clone())?;
+                    serde_json::from_value(defaults.webhook(&settings.config))?;
                 Ok(Arc::new(WebhookDestination::new(
-                    format!("webhook_{}", uuid::Uuid::new_v4()),
+                    Self::destination_id(settings, "webhook"),
                     config,
                 )))
             },
@@ -75,12 +201,16 @@ impl DestinationFactory {
         }
     }
     
-    /// Create multiple destinations from a list of settings
-    pub fn create_destinations(settings: &[DestinationSettings]) -> Result<Vec<Arc<dyn Destination>>> {
+    /// Create multiple destinations, taking the options their settings
+    /// leave out from the router-wide `defaults`
+    pub fn create_destinations_with_defaults(
+        settings: &[DestinationSettings],
+        defaults: &DestinationDefaults,
+    ) -> Result<Vec<Arc<dyn Destination>>> {
         let mut destinations = Vec::new();
         
         for setting in settings {
-            destinations.push(Self::create_destination(setting)?);
+            destinations.push(Self::create_destination_with_defaults(setting, defaults)?);
         }
         
         Ok(destinations)
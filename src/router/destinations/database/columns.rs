@@ -0,0 +1,153 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::router::destinations::formats::{self, FieldType, SourceSchema, parse_timestamp};
+
+/// Records mapped onto table columns
+#[derive(Debug, Default)]
+pub struct MappedRows {
+    /// Column name to the record field it is read from, sorted by column
+    pub columns: BTreeMap<String, String>,
+    /// Row values keyed by column name
+    pub rows: Vec<Map<String, Value>>,
+}
+
+/// Map records onto table columns.
+///
+/// Records are flattened with `.` so mappings can name nested fields
+/// (`geo.country`). Mapped fields go to their configured column; with
+/// `infer` every other field becomes a column named after it, with dots
+/// replaced by underscores.
+pub fn map_records(
+    records: &[&Value],
+    mappings: Option<&HashMap<String, String>>,
+    infer: bool,
+) -> MappedRows {
+    let mut mapped = MappedRows::default();
+
+    for record in records {
+        let flat = formats::flatten_record(record, ".");
+        let mut row = Map::new();
+
+        for (field, value) in flat {
+            let column = match mappings.and_then(|m| m.get(&field)) {
+                Some(column) => column.clone(),
+                None if infer => field.replace('.', "_"),
+                None => continue,
+            };
+
+            mapped.columns.entry(column.clone()).or_insert_with(|| field.clone());
+            row.insert(column, value);
+        }
+
+        mapped.rows.push(row);
+    }
+
+    mapped
+}
+
+/// Resolve the type of every mapped column.
+///
+/// The type of an existing table column always wins, then an explicit
+/// `column_types` entry, then the type the source reports for the field,
+/// and finally the type inferred from the batch.
+pub fn resolve_types(
+    mapped: &MappedRows,
+    existing: &HashMap<String, FieldType>,
+    overrides: &HashMap<String, String>,
+    source_schema: Option<&SourceSchema>,
+) -> Result<Vec<(String, FieldType)>> {
+    let columns: Vec<String> = mapped.columns.keys().cloned().collect();
+    let inferred = formats::infer_types(&columns, &mapped.rows);
+
+    inferred.into_iter()
+        .map(|(column, inferred)| {
+            if let Some(field_type) = existing.get(&column) {
+                return Ok((column, *field_type));
+            }
+
+            if let Some(name) = overrides.get(&column) {
+                let field_type = FieldType::from_type_name(name)
+                    .ok_or_else(|| anyhow!("Unknown type {} for column {}", name, column))?;
+                return Ok((column, field_type));
+            }
+
+            let reported = source_schema
+                .and_then(|schema| schema.field_type(&mapped.columns[&column]));
+
+            Ok((column, reported.unwrap_or(inferred)))
+        })
+        .collect()
+}
+
+/// A value converted to the type of the column it is written to
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    /// A typed null, since some backends won't coerce untyped parameters
+    Null(FieldType),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Timestamp(DateTime<Utc>),
+    Json(Value),
+}
+
+impl SqlValue {
+    /// Convert a record value for a column of the given type
+    pub fn convert(column: &str, value: Option<&Value>, field_type: FieldType) -> Result<Self> {
+        let value = match value {
+            None | Some(Value::Null) => return Ok(SqlValue::Null(field_type)),
+            Some(value) => value,
+        };
+
+        let converted = match field_type {
+            FieldType::Boolean => match value {
+                Value::Bool(b) => Some(SqlValue::Bool(*b)),
+                Value::String(s) if s == "true" || s == "false" => Some(SqlValue::Bool(s == "true")),
+                _ => None,
+            },
+            FieldType::Integer => match value {
+                // Booleans land in integer columns on MySQL and SQLite
+                Value::Bool(b) => Some(SqlValue::Int(*b as i64)),
+                _ => value.as_i64()
+                    .or_else(|| value.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))
+                    .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                    .map(SqlValue::Int),
+            },
+            FieldType::Float => value.as_f64()
+                .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                .map(SqlValue::Float),
+            FieldType::Timestamp => parse_timestamp(value).map(SqlValue::Timestamp),
+            FieldType::Text => match value {
+                Value::String(s) => Some(SqlValue::Text(s.clone())),
+                other => Some(SqlValue::Text(other.to_string())),
+            },
+            FieldType::Json => Some(SqlValue::Json(value.clone())),
+        };
+
+        converted.ok_or_else(|| {
+            anyhow!("Column {} expects {} values, found {}", column, field_type.as_str(), value)
+        })
+    }
+}
+
+impl PartialOrd for SqlValue {
+    /// Order values of the same type; nulls sort before everything else
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (SqlValue::Null(_), SqlValue::Null(_)) => Some(Ordering::Equal),
+            (SqlValue::Null(_), _) => Some(Ordering::Less),
+            (_, SqlValue::Null(_)) => Some(Ordering::Greater),
+            (SqlValue::Bool(a), SqlValue::Bool(b)) => a.partial_cmp(b),
+            (SqlValue::Int(a), SqlValue::Int(b)) => a.partial_cmp(b),
+            (SqlValue::Float(a), SqlValue::Float(b)) => a.partial_cmp(b),
+            (SqlValue::Text(a), SqlValue::Text(b)) => a.partial_cmp(b),
+            (SqlValue::Timestamp(a), SqlValue::Timestamp(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
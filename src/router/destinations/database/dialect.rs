@@ -0,0 +1,288 @@
+use anyhow::{Result, anyhow};
+
+use crate::router::destinations::formats::FieldType;
+
+/// SQL dialect of a database backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    /// Parse the `db_type` of a destination configuration
+    pub fn from_db_type(db_type: &str) -> Result<Self> {
+        match db_type.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(Dialect::Postgres),
+            "mysql" | "mariadb" => Ok(Dialect::MySql),
+            "sqlite" | "sqlite3" => Ok(Dialect::Sqlite),
+            other => Err(anyhow!("Unsupported database type: {}", other)),
+        }
+    }
+
+    /// Quote an identifier
+    pub fn quote(&self, ident: &str) -> String {
+        match self {
+            Dialect::MySql => format!("`{}`", ident.replace('`', "``")),
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    /// Quoted, schema-qualified table name.
+    ///
+    /// Postgres defaults to the `public` schema. On MySQL the schema is the
+    /// database name, and SQLite has no schemas, so it is ignored there.
+    pub fn table_name(&self, schema: Option<&str>, table: &str) -> String {
+        match (self, schema) {
+            (Dialect::Postgres, schema) => format!(
+                "{}.{}",
+                self.quote(schema.unwrap_or("public")),
+                self.quote(table)
+            ),
+            (Dialect::MySql, Some(schema)) => format!("{}.{}", self.quote(schema), self.quote(table)),
+            _ => self.quote(table),
+        }
+    }
+
+    /// Column type used to store JSON documents
+    pub fn json_type(&self) -> &'static str {
+        match self {
+            Dialect::Postgres => "JSONB",
+            Dialect::MySql => "JSON",
+            Dialect::Sqlite => "TEXT",
+        }
+    }
+
+    /// Definition of an auto-incrementing primary key column
+    pub fn id_column(&self) -> &'static str {
+        match self {
+            Dialect::Postgres => "BIGSERIAL PRIMARY KEY",
+            Dialect::MySql => "BIGINT AUTO_INCREMENT PRIMARY KEY",
+            Dialect::Sqlite => "INTEGER PRIMARY KEY AUTOINCREMENT",
+        }
+    }
+
+    /// Definition of a column holding the insertion time
+    pub fn created_at_column(&self) -> &'static str {
+        match self {
+            Dialect::Postgres => "TIMESTAMP WITH TIME ZONE DEFAULT NOW()",
+            Dialect::MySql => "TIMESTAMP DEFAULT CURRENT_TIMESTAMP",
+            Dialect::Sqlite => "TEXT DEFAULT CURRENT_TIMESTAMP",
+        }
+    }
+
+    /// DDL creating the table that stores records as JSON documents
+    pub fn create_table(&self, table_name: &str) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                {} {},
+                {} {} NOT NULL,
+                {} {}
+            )",
+            table_name,
+            self.quote("id"), self.id_column(),
+            self.quote("data"), self.json_type(),
+            self.quote("created_at"), self.created_at_column(),
+        )
+    }
+
+    /// Column type used to store values of a field type
+    pub fn column_type(&self, field_type: FieldType) -> &'static str {
+        match (self, field_type) {
+            (Dialect::Postgres, FieldType::Boolean) => "BOOLEAN",
+            (Dialect::Postgres, FieldType::Integer) => "BIGINT",
+            (Dialect::Postgres, FieldType::Float) => "DOUBLE PRECISION",
+            (Dialect::Postgres, FieldType::Text) => "TEXT",
+            (Dialect::Postgres, FieldType::Timestamp) => "TIMESTAMP WITH TIME ZONE",
+            (Dialect::Postgres, FieldType::Json) => "JSONB",
+            (Dialect::MySql, FieldType::Boolean) => "BOOLEAN",
+            (Dialect::MySql, FieldType::Integer) => "BIGINT",
+            (Dialect::MySql, FieldType::Float) => "DOUBLE",
+            (Dialect::MySql, FieldType::Text) => "TEXT",
+            (Dialect::MySql, FieldType::Timestamp) => "DATETIME(6)",
+            (Dialect::MySql, FieldType::Json) => "JSON",
+            (Dialect::Sqlite, FieldType::Boolean) => "BOOLEAN",
+            (Dialect::Sqlite, FieldType::Integer) => "INTEGER",
+            (Dialect::Sqlite, FieldType::Float) => "REAL",
+            (Dialect::Sqlite, FieldType::Text) => "TEXT",
+            (Dialect::Sqlite, FieldType::Timestamp) => "TIMESTAMP",
+            (Dialect::Sqlite, FieldType::Json) => "JSON",
+        }
+    }
+
+    /// Field type of a column as reported by the database.
+    ///
+    /// Matches the whole type name, ignoring a length or precision and an
+    /// `unsigned` suffix, so types such as `point` or `interval` stay text.
+    /// MySQL reports `BOOLEAN` columns as `tinyint`, so they come back as
+    /// integers; values are converted accordingly.
+    pub fn field_type(&self, column_type: &str) -> FieldType {
+        let column_type = column_type.to_lowercase();
+        let name = match column_type.split_once('(') {
+            Some((name, rest)) => format!("{} {}", name.trim(), rest.split_once(')').map_or("", |(_, suffix)| suffix.trim())),
+            None => column_type,
+        };
+        let name = name.trim();
+        let name = name.strip_suffix("unsigned").unwrap_or(name).trim();
+
+        match name {
+            "bool" | "boolean" => FieldType::Boolean,
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint"
+            | "int2" | "int4" | "int8" | "smallserial" | "serial" | "bigserial" => FieldType::Integer,
+            "real" | "float" | "float4" | "float8" | "double" | "double precision"
+            | "numeric" | "decimal" => FieldType::Float,
+            "timestamp" | "timestamptz" | "timestamp with time zone" | "timestamp without time zone"
+            | "datetime" => FieldType::Timestamp,
+            "json" | "jsonb" => FieldType::Json,
+            _ => FieldType::Text,
+        }
+    }
+
+    /// Most bind parameters a single statement may carry
+    pub fn max_bind_params(&self) -> usize {
+        match self {
+            Dialect::Postgres | Dialect::MySql => 65_535,
+            Dialect::Sqlite => 32_766,
+        }
+    }
+
+    /// DDL creating a table with typed columns and an optional unique key.
+    ///
+    /// The generated `id` and `created_at` columns are left out when the
+    /// records have fields of the same name. MySQL can't index `TEXT`
+    /// columns without a prefix length, so text key columns are created as
+    /// `VARCHAR(255)` there.
+    pub fn create_typed_table(&self, table_name: &str, columns: &[(String, FieldType)], unique: &[String]) -> String {
+        let has_column = |name: &str| columns.iter().any(|(column, _)| column == name);
+        let mut definitions = Vec::new();
+
+        if !has_column("id") {
+            definitions.push(format!("{} {}", self.quote("id"), self.id_column()));
+        }
+
+        definitions.extend(columns.iter().map(|(column, field_type)| {
+            let column_type = match (self, field_type) {
+                (Dialect::MySql, FieldType::Text) if unique.contains(column) => "VARCHAR(255)",
+                _ => self.column_type(*field_type),
+            };
+            format!("{} {}", self.quote(column), column_type)
+        }));
+
+        if !has_column("created_at") {
+            definitions.push(format!("{} {}", self.quote("created_at"), self.created_at_column()));
+        }
+
+        if !unique.is_empty() {
+            definitions.push(format!("UNIQUE ({})", self.column_list(unique)));
+        }
+
+        format!("CREATE TABLE IF NOT EXISTS {} ({})", table_name, definitions.join(", "))
+    }
+
+    /// Comma-separated list of quoted column names
+    pub fn column_list(&self, columns: &[String]) -> String {
+        columns.iter()
+            .map(|column| self.quote(column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// DDL adding a unique index on the key columns of an existing table.
+    ///
+    /// Returns `None` on MySQL, which has no `CREATE INDEX IF NOT EXISTS`;
+    /// existing MySQL tables need the unique key already.
+    pub fn create_unique_index(&self, table: &str, table_name: &str, keys: &[String]) -> Option<String> {
+        if *self == Dialect::MySql {
+            return None;
+        }
+
+        let index = format!("{}_{}_key", table, keys.join("_"));
+        Some(format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS {} ON {} ({})",
+            self.quote(&index),
+            table_name,
+            self.column_list(keys)
+        ))
+    }
+
+    /// Clause turning a multi-row `INSERT` into an upsert on `keys`.
+    ///
+    /// `table` is the unqualified table name used to refer to the existing
+    /// row. With `newer`, a conflicting row is only updated when the incoming
+    /// value of that column is greater than the stored one (or the stored one
+    /// is null).
+    pub fn upsert_clause(&self, table: &str, keys: &[String], columns: &[String], newer: Option<&str>) -> String {
+        let updates: Vec<&String> = columns.iter()
+            .filter(|column| !keys.contains(column))
+            .collect();
+
+        match self {
+            Dialect::Postgres | Dialect::Sqlite => {
+                if updates.is_empty() {
+                    return format!(" ON CONFLICT ({}) DO NOTHING", self.column_list(keys));
+                }
+
+                let assignments: Vec<String> = updates.iter()
+                    .map(|column| format!("{0} = excluded.{0}", self.quote(column)))
+                    .collect();
+
+                let mut clause = format!(
+                    " ON CONFLICT ({}) DO UPDATE SET {}",
+                    self.column_list(keys),
+                    assignments.join(", ")
+                );
+
+                if let Some(newer) = newer {
+                    let stored = format!("{}.{}", self.quote(table), self.quote(newer));
+                    clause.push_str(&format!(
+                        " WHERE {0} IS NULL OR excluded.{1} > {0}",
+                        stored,
+                        self.quote(newer)
+                    ));
+                }
+
+                clause
+            },
+            Dialect::MySql => {
+                if updates.is_empty() {
+                    let key = self.quote(&keys[0]);
+                    return format!(" ON DUPLICATE KEY UPDATE {0} = {0}", key);
+                }
+
+                let condition = newer.map(|newer| {
+                    format!("{0} IS NULL OR VALUES({0}) > {0}", self.quote(newer))
+                });
+
+                // Assignments apply left to right, so the column the condition
+                // reads has to be updated last
+                let mut ordered = updates;
+                if let Some(newer) = newer {
+                    ordered.sort_by_key(|column| column.as_str() == newer);
+                }
+
+                let assignments: Vec<String> = ordered.iter()
+                    .map(|column| {
+                        let column = self.quote(column);
+                        match &condition {
+                            Some(condition) => format!("{0} = IF({1}, VALUES({0}), {0})", column, condition),
+                            None => format!("{0} = VALUES({0})", column),
+                        }
+                    })
+                    .collect();
+
+                format!(" ON DUPLICATE KEY UPDATE {}", assignments.join(", "))
+            },
+        }
+    }
+
+    /// DDL adding a column to an existing table
+    pub fn add_column(&self, table_name: &str, column: &str, field_type: FieldType) -> String {
+        format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table_name,
+            self.quote(column),
+            self.column_type(field_type)
+        )
+    }
+}
@@ -0,0 +1,593 @@
+mod columns;
+mod dialect;
+
+#[cfg(test)]
+mod tests;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{MySqlPool, PgPool, SqlitePool, query_builder::QueryBuilder};
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::router::Destination;
+use crate::router::destinations::formats::{self, FieldType, SourceSchema};
+
+use columns::{MappedRows, SqlValue};
+pub use dialect::Dialect;
+
+/// Configuration for the database destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseDestinationConfig {
+    /// Type of database (postgres, mysql, sqlite)
+    pub db_type: String,
+    /// Connection string
+    pub connection_string: String,
+    /// Table to insert into
+    pub table: String,
+    /// Schema to use (optional; the database name on MySQL, ignored on SQLite)
+    pub schema: Option<String>,
+    /// Column or columns identifying a row for upserts (optional)
+    pub upsert_key: Option<UpsertKey>,
+    /// Only update an existing row when this column is newer in the incoming record (optional)
+    #[serde(default)]
+    pub update_if_newer: Option<String>,
+    /// Batch size for inserts
+    pub batch_size: usize,
+    /// Whether to create the table if it doesn't exist
+    pub create_if_not_exists: bool,
+    /// Column mappings (from JSON key to DB column)
+    pub column_mappings: Option<HashMap<String, String>>,
+    /// Map every record field to a column, not just the mapped ones
+    #[serde(default)]
+    pub infer_columns: bool,
+    /// Explicit column types (column name to type name), overriding inference
+    #[serde(default)]
+    pub column_types: HashMap<String, String>,
+    /// How to handle fields without a column
+    #[serde(default)]
+    pub schema_mode: SchemaMode,
+    /// Maximum number of pooled connections
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+}
+
+fn default_max_connections() -> u32 {
+    5
+}
+
+/// How to handle record fields the table has no column for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaMode {
+    /// Add the missing columns
+    #[default]
+    Evolve,
+    /// Reject the batch
+    Strict,
+}
+
+/// Key columns for upserts: a single column or a composite key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UpsertKey {
+    Single(String),
+    Composite(Vec<String>),
+}
+
+impl UpsertKey {
+    /// Names of the key columns
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            UpsertKey::Single(column) => vec![column.clone()],
+            UpsertKey::Composite(columns) => columns.clone(),
+        }
+    }
+}
+
+impl DatabaseDestinationConfig {
+    /// Whether records are written to typed columns rather than a JSON document column
+    pub fn typed_columns(&self) -> bool {
+        self.column_mappings.is_some() || self.infer_columns
+    }
+
+    /// Key columns for upserts; empty for plain inserts
+    pub fn upsert_columns(&self) -> Vec<String> {
+        self.upsert_key.as_ref().map(UpsertKey::columns).unwrap_or_default()
+    }
+
+    /// Check settings that can't be combined
+    pub fn validate(&self) -> Result<()> {
+        Dialect::from_db_type(&self.db_type)?;
+
+        if self.upsert_key.is_some() && !self.typed_columns() {
+            return Err(anyhow!("upsert_key requires column_mappings or infer_columns"));
+        }
+
+        if matches!(&self.upsert_key, Some(UpsertKey::Composite(columns)) if columns.is_empty()) {
+            return Err(anyhow!("upsert_key must name at least one column"));
+        }
+
+        if self.update_if_newer.is_some() && self.upsert_key.is_none() {
+            return Err(anyhow!("update_if_newer requires upsert_key"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Connection pool for one of the supported backends
+enum Backend {
+    Postgres(PgPool),
+    MySql(MySqlPool),
+    Sqlite(SqlitePool),
+}
+
+/// Run the same code against whichever pool a backend holds
+macro_rules! with_pool {
+    ($backend:expr, $pool:ident => $body:expr) => {
+        match $backend {
+            Backend::Postgres($pool) => $body,
+            Backend::MySql($pool) => $body,
+            Backend::Sqlite($pool) => $body,
+        }
+    };
+}
+
+/// Bind a `SqlValue` to a query builder, with the Rust type matching its column
+macro_rules! push_sql_value {
+    ($builder:expr, $value:expr) => {
+        match $value {
+            SqlValue::Null(FieldType::Boolean) => $builder.push_bind(None::<bool>),
+            SqlValue::Null(FieldType::Integer) => $builder.push_bind(None::<i64>),
+            SqlValue::Null(FieldType::Float) => $builder.push_bind(None::<f64>),
+            SqlValue::Null(FieldType::Text) => $builder.push_bind(None::<String>),
+            SqlValue::Null(FieldType::Timestamp) => $builder.push_bind(None::<chrono::DateTime<chrono::Utc>>),
+            SqlValue::Null(FieldType::Json) => $builder.push_bind(None::<Value>),
+            SqlValue::Bool(v) => $builder.push_bind(v),
+            SqlValue::Int(v) => $builder.push_bind(v),
+            SqlValue::Float(v) => $builder.push_bind(v),
+            SqlValue::Text(v) => $builder.push_bind(v),
+            SqlValue::Timestamp(v) => $builder.push_bind(v),
+            SqlValue::Json(v) => $builder.push_bind(v),
+        }
+    };
+}
+
+impl Backend {
+    /// Dialect of the backend
+    fn dialect(&self) -> Dialect {
+        match self {
+            Backend::Postgres(_) => Dialect::Postgres,
+            Backend::MySql(_) => Dialect::MySql,
+            Backend::Sqlite(_) => Dialect::Sqlite,
+        }
+    }
+
+    /// Execute a statement without parameters
+    async fn execute(&self, sql: &str) -> Result<()> {
+        with_pool!(self, pool => {
+            sqlx::query(sql).execute(pool).await?;
+        });
+
+        Ok(())
+    }
+
+    /// Columns of a table and their types; empty if the table doesn't exist
+    async fn table_columns(&self, schema: Option<&str>, table: &str) -> Result<HashMap<String, FieldType>> {
+        let dialect = self.dialect();
+
+        let rows: Vec<(String, String)> = match self {
+            Backend::Postgres(pool) => {
+                sqlx::query_as(
+                    "SELECT column_name::text, data_type::text FROM information_schema.columns
+                     WHERE table_schema = $1 AND table_name = $2"
+                )
+                .bind(schema.unwrap_or("public"))
+                .bind(table)
+                .fetch_all(pool)
+                .await?
+            },
+            Backend::MySql(pool) => {
+                sqlx::query_as(
+                    "SELECT CAST(column_name AS CHAR), CAST(data_type AS CHAR) FROM information_schema.columns
+                     WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?"
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_all(pool)
+                .await?
+            },
+            Backend::Sqlite(pool) => {
+                sqlx::query_as("SELECT name, type FROM pragma_table_info(?)")
+                    .bind(table)
+                    .fetch_all(pool)
+                    .await?
+            },
+        };
+
+        Ok(rows.into_iter()
+            .map(|(name, column_type)| {
+                let field_type = dialect.field_type(&column_type);
+                (name, field_type)
+            })
+            .collect())
+    }
+}
+
+/// Destination that writes data to a database
+pub struct DatabaseDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the database destination
+    pub config: DatabaseDestinationConfig,
+    /// Database connection pool, created on first use
+    backend: OnceCell<Backend>,
+    /// Columns of the target table, loaded on the first typed write
+    table_columns: Mutex<Option<HashMap<String, FieldType>>>,
+    /// Schema reported by the source connector, if any
+    source_schema: RwLock<Option<SourceSchema>>,
+}
+
+impl DatabaseDestination {
+    /// Create a new database destination
+    pub fn new(id: String, config: DatabaseDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            backend: OnceCell::new(),
+            table_columns: Mutex::new(None),
+            source_schema: RwLock::new(None),
+        }
+    }
+
+    /// Get the connection pool, connecting on first use
+    async fn backend(&self) -> Result<&Backend> {
+        self.backend.get_or_try_init(|| self.connect()).await
+    }
+
+    /// Connect to the configured database
+    async fn connect(&self) -> Result<Backend> {
+        let max_connections = self.config.max_connections.max(1);
+
+        let backend = match Dialect::from_db_type(&self.config.db_type)? {
+            Dialect::Postgres => Backend::Postgres(
+                PgPoolOptions::new()
+                    .max_connections(max_connections)
+                    .connect(&self.config.connection_string)
+                    .await?
+            ),
+            Dialect::MySql => Backend::MySql(
+                MySqlPoolOptions::new()
+                    .max_connections(max_connections)
+                    .connect(&self.config.connection_string)
+                    .await?
+            ),
+            Dialect::Sqlite => {
+                // Create the database file if it doesn't exist yet
+                let options = SqliteConnectOptions::from_str(&self.config.connection_string)?
+                    .create_if_missing(true);
+
+                Backend::Sqlite(
+                    SqlitePoolOptions::new()
+                        .max_connections(max_connections)
+                        .connect_with(options)
+                        .await?
+                )
+            },
+        };
+
+        // Typed tables are created on the first write, once column types are known
+        if self.config.create_if_not_exists && !self.config.typed_columns() {
+            self.ensure_table_exists(&backend).await?;
+        }
+
+        Ok(backend)
+    }
+
+    /// Quoted name of the target table
+    fn table_name(&self, dialect: Dialect) -> String {
+        dialect.table_name(self.config.schema.as_deref(), &self.config.table)
+    }
+
+    /// Ensure the target table exists
+    async fn ensure_table_exists(&self, backend: &Backend) -> Result<()> {
+        let dialect = backend.dialect();
+        backend.execute(&dialect.create_table(&self.table_name(dialect))).await
+    }
+
+    /// Bring the table in line with the columns of a batch.
+    ///
+    /// Creates the table on first use and adds missing columns in evolve
+    /// mode; strict mode rejects batches with fields the table has no
+    /// column for. Returns the type of every column in the batch.
+    async fn sync_schema(&self, backend: &Backend, mapped: &MappedRows) -> Result<Vec<(String, FieldType)>> {
+        let dialect = backend.dialect();
+        let table_name = self.table_name(dialect);
+        let mut cached = self.table_columns.lock().await;
+
+        if cached.is_none() {
+            let loaded = backend
+                .table_columns(self.config.schema.as_deref(), &self.config.table)
+                .await?;
+
+            // Upserts need a unique index on the key of an existing table
+            let keys = self.config.upsert_columns();
+            if !loaded.is_empty() && !keys.is_empty() && self.config.create_if_not_exists {
+                if let Some(sql) = dialect.create_unique_index(&self.config.table, &table_name, &keys) {
+                    backend.execute(&sql).await?;
+                }
+            }
+
+            *cached = Some(loaded);
+        }
+        let existing = cached.get_or_insert_with(HashMap::new);
+
+        let columns = {
+            let schema = self.source_schema.read()
+                .map_err(|_| anyhow!("Source schema lock poisoned"))?;
+            columns::resolve_types(mapped, existing, &self.config.column_types, schema.as_ref())?
+        };
+
+        if existing.is_empty() {
+            if !self.config.create_if_not_exists {
+                return Err(anyhow!("Table {} does not exist", table_name));
+            }
+
+            let keys = self.config.upsert_columns();
+            backend.execute(&dialect.create_typed_table(&table_name, &columns, &keys)).await?;
+            tracing::info!("Created table {} with {} columns", table_name, columns.len());
+
+            existing.extend(columns.iter().cloned());
+            return Ok(columns);
+        }
+
+        let missing: Vec<&(String, FieldType)> = columns.iter()
+            .filter(|(column, _)| !existing.contains_key(column))
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(columns);
+        }
+
+        if self.config.schema_mode == SchemaMode::Strict {
+            let names: Vec<&str> = missing.iter().map(|(column, _)| column.as_str()).collect();
+            return Err(anyhow!(
+                "Table {} has no columns for {} (schema_mode is strict)",
+                table_name, names.join(", ")
+            ));
+        }
+
+        for (column, field_type) in missing {
+            backend.execute(&dialect.add_column(&table_name, column, *field_type)).await?;
+            tracing::info!("Added column {} {} to {}", column, dialect.column_type(*field_type), table_name);
+            existing.insert(column.clone(), *field_type);
+        }
+
+        Ok(columns)
+    }
+
+    /// Insert records into typed columns
+    async fn insert_typed(&self, records: &[&Value]) -> Result<()> {
+        let mapped = columns::map_records(
+            records,
+            self.config.column_mappings.as_ref(),
+            self.config.infer_columns,
+        );
+
+        if mapped.columns.is_empty() {
+            return Ok(());
+        }
+
+        let backend = self.backend().await?;
+        let dialect = backend.dialect();
+        let columns = self.sync_schema(backend, &mapped).await?;
+
+        // Convert every value up front so a bad record fails the whole batch
+        let rows = mapped.rows.iter()
+            .map(|row| {
+                columns.iter()
+                    .map(|(column, field_type)| SqlValue::convert(column, row.get(column), *field_type))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let names: Vec<String> = columns.iter().map(|(column, _)| column.clone()).collect();
+        let keys = self.config.upsert_columns();
+
+        let (rows, upsert) = if keys.is_empty() {
+            (rows, String::new())
+        } else {
+            let rows = self.dedupe_rows(&names, &keys, rows)?;
+            let upsert = dialect.upsert_clause(
+                &self.config.table,
+                &keys,
+                &names,
+                self.config.update_if_newer.as_deref(),
+            );
+            (rows, upsert)
+        };
+
+        let insert = format!(
+            "INSERT INTO {} ({}) ",
+            self.table_name(dialect),
+            dialect.column_list(&names)
+        );
+
+        // Split the rows over as many statements as the bind parameter
+        // limit needs, in one transaction so the batch is still all or nothing
+        let rows_per_statement = (dialect.max_bind_params() / names.len()).max(1);
+
+        with_pool!(backend, pool => {
+            let mut tx = pool.begin().await?;
+            let mut rows = rows.into_iter().peekable();
+
+            while rows.peek().is_some() {
+                let mut query_builder = QueryBuilder::new(&insert);
+
+                query_builder.push_values(rows.by_ref().take(rows_per_statement), |mut b, row| {
+                    for value in row {
+                        push_sql_value!(b, value);
+                    }
+                });
+                query_builder.push(&upsert);
+
+                query_builder.build().execute(&mut *tx).await?;
+            }
+
+            tx.commit().await?;
+        });
+
+        Ok(())
+    }
+
+    /// Collapse rows sharing an upsert key into one.
+    ///
+    /// A single statement can't touch the same row twice, so the last row
+    /// for each key wins, or the newest one when `update_if_newer` is set.
+    fn dedupe_rows(&self, names: &[String], keys: &[String], rows: Vec<Vec<SqlValue>>) -> Result<Vec<Vec<SqlValue>>> {
+        let key_indexes = keys.iter()
+            .map(|key| {
+                names.iter()
+                    .position(|name| name == key)
+                    .ok_or_else(|| anyhow!("Upsert key column {} is missing from the records", key))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let newer_index = self.config.update_if_newer.as_ref()
+            .and_then(|newer| names.iter().position(|name| name == newer));
+
+        let mut deduped: Vec<Vec<SqlValue>> = Vec::with_capacity(rows.len());
+        let mut positions: HashMap<String, usize> = HashMap::new();
+
+        for row in rows {
+            if key_indexes.iter().any(|&i| matches!(row[i], SqlValue::Null(_))) {
+                return Err(anyhow!("Upsert key {} is null in a record", keys.join(", ")));
+            }
+
+            let key = key_indexes.iter()
+                .map(|&i| format!("{:?}", row[i]))
+                .collect::<Vec<_>>()
+                .join("\u{1f}");
+
+            match positions.get(&key) {
+                Some(&position) => {
+                    let replace = match newer_index {
+                        Some(i) => row[i] >= deduped[position][i],
+                        None => true,
+                    };
+                    if replace {
+                        deduped[position] = row;
+                    }
+                },
+                None => {
+                    positions.insert(key, deduped.len());
+                    deduped.push(row);
+                },
+            }
+        }
+
+        Ok(deduped)
+    }
+
+    /// Insert records as JSON documents
+    async fn insert_documents(&self, data: &[Value]) -> Result<()> {
+        let backend = self.backend().await?;
+        let dialect = backend.dialect();
+        let insert = format!(
+            "INSERT INTO {} ({}) ",
+            self.table_name(dialect),
+            dialect.quote("data")
+        );
+
+        with_pool!(backend, pool => {
+            let mut tx = pool.begin().await?;
+
+            for chunk in data.chunks(dialect.max_bind_params()) {
+                let mut query_builder = QueryBuilder::new(&insert);
+
+                query_builder.push_values(chunk, |mut b, item| {
+                    b.push_bind(item);
+                });
+
+                query_builder.build().execute(&mut *tx).await?;
+            }
+
+            tx.commit().await?;
+        });
+
+        Ok(())
+    }
+
+    /// Insert multiple records to the database in a single statement
+    async fn insert_batch(&self, data: &[Value]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if self.config.typed_columns() {
+            let records: Vec<&Value> = data.iter()
+                .flat_map(formats::records_of)
+                .collect();
+            self.insert_typed(&records).await
+        } else {
+            self.insert_documents(data).await
+        }
+    }
+}
+
+#[async_trait]
+impl Destination for DatabaseDestination {
+    fn get_type(&self) -> &str {
+        "database"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.insert_batch(std::slice::from_ref(&data)).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        // Process in batches of the specified size
+        for chunk in data.chunks(self.config.batch_size.max(1)) {
+            self.insert_batch(chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    fn set_source_metadata(&self, metadata: &Value) {
+        if let Ok(mut schema) = self.source_schema.write() {
+            *schema = SourceSchema::from_metadata(metadata);
+        }
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        let backend = match self.backend().await {
+            Ok(backend) => backend,
+            Err(e) => {
+                tracing::error!("Database connection failed: {}", e);
+                return Ok(false);
+            }
+        };
+
+        // Try to execute a simple query to check connection
+        match backend.execute("SELECT 1").await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::error!("Database connection check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
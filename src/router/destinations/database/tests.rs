@@ -0,0 +1,246 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use super::super::columns::{SqlValue, map_records, resolve_types};
+    use super::super::{DatabaseDestination, DatabaseDestinationConfig, Dialect};
+    use crate::router::Destination;
+    use crate::router::destinations::formats::FieldType;
+    use serde_json::{json, Value};
+    use sqlx::SqlitePool;
+    use std::collections::HashMap;
+
+    /// SQLite destination writing to a fresh database file, with `options`
+    /// on top of a plain `events` table config
+    fn sqlite_destination(options: Value) -> (DatabaseDestination, String, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("muxly-destination-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite://{}", path.display());
+
+        let mut config = json!({
+            "db_type": "sqlite",
+            "connection_string": url,
+            "table": "events",
+            "batch_size": 100,
+            "create_if_not_exists": true
+        });
+        config.as_object_mut().unwrap().extend(options.as_object().unwrap().clone());
+
+        let config: DatabaseDestinationConfig = serde_json::from_value(config).unwrap();
+        (DatabaseDestination::new("db".to_string(), config), url, path)
+    }
+
+    #[test]
+    fn test_dialect_quoting_and_table_names() {
+        assert_eq!(Dialect::Postgres.table_name(None, "events"), "\"public\".\"events\"");
+        assert_eq!(Dialect::MySql.table_name(Some("crm"), "contacts"), "`crm`.`contacts`");
+        assert_eq!(Dialect::Sqlite.table_name(Some("ignored"), "events"), "\"events\"");
+        assert_eq!(Dialect::MySql.quote("we`ird"), "`we``ird`");
+    }
+
+    #[test]
+    fn test_map_records_with_mappings_and_inference() {
+        let records = [json!({"id": 1, "geo": {"country": "US"}, "visits": 3})];
+        let records: Vec<_> = records.iter().collect();
+
+        let mappings = HashMap::from([("geo.country".to_string(), "country".to_string())]);
+
+        // Only mapped fields become columns
+        let mapped = map_records(&records, Some(&mappings), false);
+        assert_eq!(mapped.columns.keys().collect::<Vec<_>>(), vec!["country"]);
+        assert_eq!(mapped.rows[0].get("country"), Some(&json!("US")));
+
+        // With inference every field becomes a column
+        let mapped = map_records(&records, Some(&mappings), true);
+        assert_eq!(mapped.columns.keys().collect::<Vec<_>>(), vec!["country", "id", "visits"]);
+    }
+
+    #[test]
+    fn test_resolve_types_precedence() {
+        let records = [json!({"id": 1, "amount": 5, "closed_at": "2024-01-01T00:00:00Z"})];
+        let records: Vec<_> = records.iter().collect();
+        let mapped = map_records(&records, None, true);
+
+        let existing = HashMap::from([("id".to_string(), FieldType::Text)]);
+        let overrides = HashMap::from([
+            ("id".to_string(), "integer".to_string()),
+            ("closed_at".to_string(), "timestamp".to_string()),
+        ]);
+
+        let columns = resolve_types(&mapped, &existing, &overrides, None).unwrap();
+        assert_eq!(columns, vec![
+            ("amount".to_string(), FieldType::Integer),
+            ("closed_at".to_string(), FieldType::Timestamp),
+            // The existing table column wins over the override
+            ("id".to_string(), FieldType::Text),
+        ]);
+    }
+
+    #[test]
+    fn test_convert_values() {
+        assert_eq!(SqlValue::convert("n", Some(&json!("42")), FieldType::Integer).unwrap(), SqlValue::Int(42));
+        assert_eq!(SqlValue::convert("n", Some(&json!(true)), FieldType::Integer).unwrap(), SqlValue::Int(1));
+        assert_eq!(SqlValue::convert("n", None, FieldType::Float).unwrap(), SqlValue::Null(FieldType::Float));
+        assert_eq!(SqlValue::convert("s", Some(&json!(1.5)), FieldType::Text).unwrap(), SqlValue::Text("1.5".to_string()));
+        assert!(SqlValue::convert("n", Some(&json!("abc")), FieldType::Integer).is_err());
+    }
+
+    #[test]
+    fn test_upsert_clauses() {
+        let keys = vec!["id".to_string()];
+        let columns = vec!["id".to_string(), "name".to_string(), "updated_at".to_string()];
+
+        assert_eq!(
+            Dialect::Postgres.upsert_clause("contacts", &keys, &columns, Some("updated_at")),
+            " ON CONFLICT (\"id\") DO UPDATE SET \"name\" = excluded.\"name\", \"updated_at\" = excluded.\"updated_at\" \
+             WHERE \"contacts\".\"updated_at\" IS NULL OR excluded.\"updated_at\" > \"contacts\".\"updated_at\""
+        );
+
+        // The timestamp column is assigned last so the condition sees the old value
+        let columns = vec!["id".to_string(), "updated_at".to_string(), "name".to_string()];
+        assert_eq!(
+            Dialect::MySql.upsert_clause("contacts", &keys, &columns, Some("updated_at")),
+            " ON DUPLICATE KEY UPDATE \
+             `name` = IF(`updated_at` IS NULL OR VALUES(`updated_at`) > `updated_at`, VALUES(`name`), `name`), \
+             `updated_at` = IF(`updated_at` IS NULL OR VALUES(`updated_at`) > `updated_at`, VALUES(`updated_at`), `updated_at`)"
+        );
+
+        assert_eq!(
+            Dialect::Sqlite.upsert_clause("contacts", &keys, &keys, None),
+            " ON CONFLICT (\"id\") DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn test_field_types_match_whole_type_names() {
+        assert_eq!(Dialect::MySql.field_type("int"), FieldType::Integer);
+        assert_eq!(Dialect::Sqlite.field_type("BIGINT UNSIGNED"), FieldType::Integer);
+        assert_eq!(Dialect::Postgres.field_type("double precision"), FieldType::Float);
+        assert_eq!(Dialect::MySql.field_type("decimal(10,2)"), FieldType::Float);
+        assert_eq!(Dialect::Sqlite.field_type("DATETIME(6)"), FieldType::Timestamp);
+        assert_eq!(Dialect::Postgres.field_type("timestamp with time zone"), FieldType::Timestamp);
+        assert_eq!(Dialect::Postgres.field_type("jsonb"), FieldType::Json);
+
+        // Names that merely contain a type name are text
+        assert_eq!(Dialect::Postgres.field_type("point"), FieldType::Text);
+        assert_eq!(Dialect::Postgres.field_type("interval"), FieldType::Text);
+        assert_eq!(Dialect::MySql.field_type("varchar(255)"), FieldType::Text);
+    }
+
+    #[test]
+    fn test_unknown_schema_modes_are_rejected() {
+        let config = json!({
+            "db_type": "sqlite",
+            "connection_string": "sqlite::memory:",
+            "table": "events",
+            "batch_size": 100,
+            "create_if_not_exists": true,
+            "schema_mode": "loose"
+        });
+
+        assert!(serde_json::from_value::<DatabaseDestinationConfig>(config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_inserts_documents() {
+        let (destination, url, path) = sqlite_destination(json!({}));
+
+        destination.send_batch(vec![json!({"order": 1}), json!({"order": 2})]).await.unwrap();
+
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT data FROM events ORDER BY id").fetch_all(&pool).await.unwrap();
+        let documents: Vec<Value> = rows.iter().map(|(data,)| serde_json::from_str(data).unwrap()).collect();
+        assert_eq!(documents, vec![json!({"order": 1}), json!({"order": 2})]);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_typed_inserts_evolve_the_schema() {
+        let (destination, url, path) = sqlite_destination(json!({"infer_columns": true}));
+
+        destination.send(json!({"id": 1, "name": "Ada"})).await.unwrap();
+        // A new field adds a column; earlier rows have it null
+        destination.send(json!({"id": 2, "name": "Grace", "score": 9.5})).await.unwrap();
+
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        let rows: Vec<(i64, String, Option<f64>)> = sqlx::query_as("SELECT id, name, score FROM events ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![(1, "Ada".to_string(), None), (2, "Grace".to_string(), Some(9.5))]);
+
+        let columns: Vec<(String, String)> = sqlx::query_as("SELECT name, type FROM pragma_table_info('events')")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert!(columns.contains(&("score".to_string(), "REAL".to_string())), "{:?}", columns);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_strict_schema_rejects_new_fields() {
+        let (destination, url, path) = sqlite_destination(json!({"infer_columns": true, "schema_mode": "strict"}));
+
+        destination.send(json!({"id": 1})).await.unwrap();
+        let error = destination.send(json!({"id": 2, "extra": true})).await.unwrap_err();
+        assert!(error.to_string().contains("has no columns for extra"), "{}", error);
+
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM events").fetch_one(&pool).await.unwrap();
+        assert_eq!(count.0, 1);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_upserts_keep_the_newest_row() {
+        let (destination, url, path) = sqlite_destination(json!({
+            "infer_columns": true,
+            "upsert_key": ["account", "id"],
+            "update_if_newer": "version"
+        }));
+
+        destination.send_batch(vec![
+            json!({"account": "a", "id": 1, "name": "first", "version": 1}),
+            json!({"account": "b", "id": 1, "name": "other", "version": 1}),
+            // The same key twice in a batch collapses to the newer row
+            json!({"account": "a", "id": 1, "name": "second", "version": 2}),
+        ]).await.unwrap();
+
+        // An older version doesn't overwrite the stored row, a newer one does
+        destination.send(json!({"account": "a", "id": 1, "name": "stale", "version": 1})).await.unwrap();
+        destination.send(json!({"account": "b", "id": 1, "name": "updated", "version": 3})).await.unwrap();
+
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        let rows: Vec<(String, i64, String, i64)> = sqlx::query_as("SELECT account, id, name, version FROM events ORDER BY account")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![
+            ("a".to_string(), 1, "second".to_string(), 2),
+            ("b".to_string(), 1, "updated".to_string(), 3),
+        ]);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_batches_beyond_the_bind_limit_are_split() {
+        let (destination, url, path) = sqlite_destination(json!({"infer_columns": true, "batch_size": 20000}));
+
+        // 3 columns x 20000 rows is well over SQLite's 32766 bind parameters
+        let records: Vec<Value> = (0..20000).map(|i| json!({"seq": i, "name": "row", "score": 1.5})).collect();
+        destination.send_batch(records).await.unwrap();
+
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM events").fetch_one(&pool).await.unwrap();
+        assert_eq!(count.0, 20000);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+}
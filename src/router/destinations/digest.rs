@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How a batch of records is summarized in one notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSettings {
+    /// Fields shown as columns (the first record's fields if empty)
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Records listed before the rest are counted as "N more"
+    #[serde(default = "default_max_rows")]
+    pub max_rows: usize,
+}
+
+fn default_max_rows() -> usize {
+    10
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            fields: Vec::new(),
+            max_rows: default_max_rows(),
+        }
+    }
+}
+
+/// Most fields shown when they aren't configured
+const DEFAULT_COLUMNS: usize = 5;
+
+/// Longest cell value before it is cut off
+const MAX_CELL_LENGTH: usize = 40;
+
+/// Summary table of a batch of records
+pub struct Digest {
+    /// Column headers
+    pub columns: Vec<String>,
+    /// Formatted cells of the listed records
+    pub rows: Vec<Vec<String>>,
+    /// Number of records in the batch
+    pub total: usize,
+}
+
+impl Digest {
+    /// Summarize a batch of records
+    pub fn new(records: &[Value], settings: &DigestSettings) -> Self {
+        let columns = if settings.fields.is_empty() {
+            records.first()
+                .and_then(|record| record.as_object())
+                .map(|obj| obj.keys().take(DEFAULT_COLUMNS).cloned().collect())
+                .unwrap_or_default()
+        } else {
+            settings.fields.clone()
+        };
+
+        let rows = records.iter()
+            .take(settings.max_rows)
+            .map(|record| {
+                columns.iter()
+                    .map(|column| record.get(column).map(format_cell).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            columns,
+            rows,
+            total: records.len(),
+        }
+    }
+
+    /// Records in the batch that aren't listed
+    pub fn remaining(&self) -> usize {
+        self.total - self.rows.len()
+    }
+
+    /// Render as a plain-text table with aligned columns
+    pub fn to_text(&self) -> String {
+        let widths: Vec<usize> = self.columns.iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(std::iter::once(column.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let render = |cells: &[String]| {
+            cells.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = vec![render(&self.columns)];
+        lines.push(widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join("  "));
+        lines.extend(self.rows.iter().map(|row| render(row)));
+        lines.join("\n")
+    }
+
+    /// Render as an HTML table, with a final row counting unlisted records
+    pub fn to_html(&self) -> String {
+        let mut table = String::from("<table border='1' cellpadding='4' style='border-collapse: collapse;'>");
+
+        table.push_str("<tr>");
+        for column in &self.columns {
+            table.push_str(&format!("<th>{}</th>", escape_html(column)));
+        }
+        table.push_str("</tr>");
+
+        for row in &self.rows {
+            table.push_str("<tr>");
+            for cell in row {
+                table.push_str(&format!("<td>{}</td>", escape_html(cell)));
+            }
+            table.push_str("</tr>");
+        }
+
+        if self.remaining() > 0 {
+            table.push_str(&format!(
+                "<tr><td colspan='{}'><em>{} more</em></td></tr>",
+                self.columns.len().max(1),
+                self.remaining()
+            ));
+        }
+
+        table.push_str("</table>");
+        table
+    }
+}
+
+/// Format a value for a table cell
+fn format_cell(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Array(_) => "[...]".to_string(),
+        Value::Object(_) => "{...}".to_string(),
+        other => other.to_string(),
+    };
+
+    if text.chars().count() > MAX_CELL_LENGTH {
+        let cut: String = text.chars().take(MAX_CELL_LENGTH - 3).collect();
+        format!("{}...", cut)
+    } else {
+        text
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -6,10 +6,12 @@ use lettre::{
     AsyncTransport, Message, message::{header, MultiPart, SinglePart}, 
     AsyncSmtpTransport, Tokio1Executor, transport::smtp::authentication::Credentials
 };
+use once_cell::sync::OnceCell;
 use std::collections::HashMap;
 use chrono::Utc;
 
 use crate::router::Destination;
+use super::digest::{Digest, DigestSettings};
 
 /// Configuration for the Email destination
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +42,9 @@ pub struct EmailDestinationConfig {
     pub body_template: Option<String>,
     /// Template variables (field name to JSON path)
     pub template_variables: HashMap<String, String>,
+    /// How batches are summarized
+    #[serde(default)]
+    pub digest: DigestSettings,
 }
 
 /// Destination that sends notifications via email
@@ -48,8 +53,8 @@ pub struct EmailDestination {
     pub id: String,
     /// Configuration for the Email destination
     pub config: EmailDestinationConfig,
-    /// SMTP transport
-    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    /// SMTP transport, created on first use
+    transport: OnceCell<AsyncSmtpTransport<Tokio1Executor>>,
 }
 
 impl EmailDestination {
@@ -58,28 +63,28 @@ impl EmailDestination {
         Self { 
             id, 
             config,
-            transport: None,
+            transport: OnceCell::new(),
         }
     }
     
-    /// Initialize the SMTP transport
-    async fn init_transport(&mut self) -> Result<()> {
-        let credentials = Credentials::new(
-            self.config.smtp_username.clone(),
-            self.config.smtp_password.clone(),
-        );
-        
-        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::relay(&self.config.smtp_host)?
-            .port(self.config.smtp_port)
-            .credentials(credentials);
+    /// SMTP transport, initialized on first use
+    fn transport(&self) -> Result<&AsyncSmtpTransport<Tokio1Executor>> {
+        self.transport.get_or_try_init(|| {
+            let credentials = Credentials::new(
+                self.config.smtp_username.clone(),
+                self.config.smtp_password.clone(),
+            );
             
-        if !self.config.use_tls {
-            builder = builder.tls(lettre::transport::smtp::client::Tls::None);
-        }
-        
-        self.transport = Some(builder.build());
-        
-        Ok(())
+            let mut builder = AsyncSmtpTransport::<Tokio1Executor>::relay(&self.config.smtp_host)?
+                .port(self.config.smtp_port)
+                .credentials(credentials);
+                
+            if !self.config.use_tls {
+                builder = builder.tls(lettre::transport::smtp::client::Tls::None);
+            }
+            
+            Ok(builder.build())
+        })
     }
     
     /// Format a string using the template and data
@@ -162,6 +167,24 @@ impl EmailDestination {
     
     /// Create a default HTML email body
     fn create_default_body(&self, data: &Value) -> String {
+        self.wrap_body(
+            "<p>Data was received from a connector. Details are below:</p>",
+            &self.create_data_table(data),
+        )
+    }
+    
+    /// Create an HTML email body summarizing a batch of records
+    pub(crate) fn create_digest_body(&self, data: &[Value]) -> String {
+        let digest = Digest::new(data, &self.config.digest);
+        
+        self.wrap_body(
+            &format!("<p>{} records were received from a connector. A summary is below:</p>", digest.total),
+            &digest.to_html(),
+        )
+    }
+    
+    /// Wrap an introduction and a table in the default HTML email layout
+    fn wrap_body(&self, intro: &str, table: &str) -> String {
         let mut body = String::from(r#"
         <html>
         <head>
@@ -175,10 +198,10 @@ impl EmailDestination {
         <body>
             <div class="container">
                 <h2>Muxly Data Notification</h2>
-                <p>Data was received from a connector. Details are below:</p>
         "#);
         
-        body.push_str(&self.create_data_table(data));
+        body.push_str(intro);
+        body.push_str(table);
         
         body.push_str(r#"
                 <div class="footer">
@@ -197,33 +220,10 @@ impl EmailDestination {
         
         body
     }
-}
-
-#[async_trait]
-impl Destination for EmailDestination {
-    fn get_type(&self) -> &str {
-        "email"
-    }
-    
-    fn get_id(&self) -> &str {
-        &self.id
-    }
     
-    async fn send(&mut self, data: Value) -> Result<()> {
-        // Initialize transport if not already initialized
-        if self.transport.is_none() {
-            self.init_transport().await?;
-        }
-        
-        // Format the subject
-        let subject = self.format_template(&self.config.subject_template, &data);
-        
-        // Format the body
-        let body = if let Some(template) = &self.config.body_template {
-            self.format_template(template, &data)
-        } else {
-            self.create_default_body(&data)
-        };
+    /// Send an email to the configured recipients
+    async fn send_message(&self, subject: String, body: String) -> Result<()> {
+        let transport = self.transport()?;
         
         // Create the message
         let mut message_builder = Message::builder()
@@ -269,44 +269,64 @@ impl Destination for EmailDestination {
             )?;
         
         // Send the email
-        if let Some(transport) = &self.transport {
-            transport.send(message).await?;
-        } else {
-            return Err(anyhow::anyhow!("SMTP transport not initialized"));
-        }
+        transport.send(message).await?;
         
         Ok(())
     }
+}
+
+#[async_trait]
+impl Destination for EmailDestination {
+    fn get_type(&self) -> &str {
+        "email"
+    }
     
-    async fn send_batch(&mut self, data: Vec<Value>) -> Result<()> {
-        // For email, we'll just send a summary message
-        if data.is_empty() {
-            return Ok(());
-        }
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+    
+    async fn send(&self, data: Value) -> Result<()> {
+        // Format the subject
+        let subject = self.format_template(&self.config.subject_template, &data);
+        
+        // Format the body
+        let body = if let Some(template) = &self.config.body_template {
+            self.format_template(template, &data)
+        } else {
+            self.create_default_body(&data)
+        };
         
-        // Use the first item for the message template
-        let template_data = &data[0];
+        self.send_message(subject, body).await
+    }
+    
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        // A single record gets the usual email
+        match data.as_slice() {
+            [] => return Ok(()),
+            [record] => return self.send(record.clone()).await,
+            _ => {},
+        }
         
-        // Add count information
-        let mut enhanced_data = template_data.clone();
-        if let Some(obj) = enhanced_data.as_object_mut() {
+        // Use the first item for the subject template
+        let mut subject_data = data[0].clone();
+        if let Some(obj) = subject_data.as_object_mut() {
             obj.insert("batch_count".to_string(), json!(data.len()));
         }
         
-        // Send the summary
-        self.send(enhanced_data).await
+        let subject = self.format_template(&self.config.subject_template, &subject_data);
+        let body = self.create_digest_body(&data);
+        
+        self.send_message(subject, body).await
     }
     
-    async fn check_availability(&mut self) -> Result<bool> {
-        // Initialize transport if not already initialized
-        if self.transport.is_none() {
-            if let Err(e) = self.init_transport().await {
+    async fn check_availability(&self) -> Result<bool> {
+        // Just verify we have a valid transport
+        match self.transport() {
+            Ok(_) => Ok(true),
+            Err(e) => {
                 log::error!("Failed to initialize SMTP transport: {}", e);
-                return Ok(false);
+                Ok(false)
             }
         }
-        
-        // Just verify we have a valid transport
-        Ok(self.transport.is_some())
     }
 } 
\ No newline at end of file
@@ -1,152 +1,676 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::fs::{self, File};
-use std::io::Write;
-use chrono::Utc;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use tokio::task::JoinHandle;
 
 use crate::router::Destination;
+use crate::router::destinations::formats::{self, CsvColumns, CsvOptions, ParquetOptions, SourceSchema};
 
 /// Configuration for the file destination
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileDestinationConfig {
-    /// Path to save files
+    /// Directory to save files in, or a file path ending in the format extension
     pub path: String,
-    /// Format of the output files (json, csv, jsonl)
+    /// Format of the output files (json, csv, jsonl, parquet)
+    #[serde(default = "default_format")]
     pub format: String,
     /// Template for the filename
+    #[serde(default = "default_filename_template")]
     pub filename_template: String,
-    /// Maximum size of a file in MB
+    /// Maximum size of a file in MB before it is rotated (0 for no limit)
+    #[serde(default = "default_max_file_size_mb")]
     pub max_file_size_mb: u64,
     /// Whether to rotate files
+    #[serde(default)]
     pub rotate_files: bool,
+    /// Rotation period (none, hourly, daily, weekly, monthly)
+    #[serde(default = "default_rotation")]
+    pub rotation: String,
+    /// Append to existing files instead of replacing them on every write
+    #[serde(default = "default_append")]
+    pub append: bool,
+    /// Gzip files once they have been rotated
+    #[serde(default)]
+    pub compress: bool,
+    /// CSV serialization options
+    #[serde(default)]
+    pub csv: CsvOptions,
+    /// Parquet serialization options
+    #[serde(default)]
+    pub parquet: ParquetOptions,
 }
 
-/// Destination that writes data to files
+fn default_format() -> String {
+    "json".to_string()
+}
+
+fn default_filename_template() -> String {
+    "{{connector_id}}_{{date}}".to_string()
+}
+
+fn default_max_file_size_mb() -> u64 {
+    100
+}
+
+fn default_rotation() -> String {
+    "daily".to_string()
+}
+
+fn default_append() -> bool {
+    true
+}
+
+/// Content to append to a file
+enum Chunk {
+    /// Pre-serialized JSON or JSON Lines
+    Bytes(Vec<u8>),
+    /// Flattened CSV rows, encoded against the file's header
+    Csv(Vec<Map<String, Value>>),
+}
+
+/// A file the destination is currently writing to
+#[derive(Debug)]
+struct ActiveFile {
+    /// Rotation period the file was started in
+    period: String,
+    /// Current size in bytes
+    size: u64,
+    /// Columns of the CSV header at the top of the file
+    csv_header: Option<Vec<String>>,
+}
+
+/// Destination that writes data to files.
+///
+/// Writes run on the blocking thread pool, and rotated files are gzipped in
+/// the background; [`Destination::flush`] waits for pending compressions.
 pub struct FileDestination {
     /// Unique identifier
     pub id: String,
-    /// Configuration for the file destination
-    pub config: FileDestinationConfig,
+    /// State of the files being written, shared with the blocking writes
+    writer: Arc<FileWriter>,
 }
 
 impl FileDestination {
     /// Create a new file destination
     pub fn new(id: String, config: FileDestinationConfig) -> Self {
-        Self { id, config }
+        Self {
+            id,
+            writer: Arc::new(FileWriter {
+                config,
+                csv_columns: CsvColumns::default(),
+                source_schema: RwLock::new(None),
+                part_sequence: AtomicU64::new(0),
+                active_files: Mutex::new(HashMap::new()),
+                compressions: Mutex::new(Vec::new()),
+            }),
+        }
     }
-    
+
+    /// Write records on the blocking thread pool, so file I/O and
+    /// serialization don't stall the runtime
+    async fn write(&self, data: Vec<Value>) -> Result<()> {
+        let writer = Arc::clone(&self.writer);
+        tokio::task::spawn_blocking(move || writer.write(&data)).await?
+    }
+}
+
+/// Writes the files of a [`FileDestination`]
+struct FileWriter {
+    /// Configuration for the file destination
+    config: FileDestinationConfig,
+    /// CSV column order shared by all files written by this destination
+    csv_columns: CsvColumns,
+    /// Schema reported by the source connector, if any
+    source_schema: RwLock<Option<SourceSchema>>,
+    /// Sequence number for Parquet part files
+    part_sequence: AtomicU64,
+    /// Files currently being written, by path
+    active_files: Mutex<HashMap<PathBuf, ActiveFile>>,
+    /// Background compressions of rotated files
+    compressions: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl FileWriter {
+
+    /// Whether `path` names a single output file rather than a directory
+    fn is_file_path(&self) -> bool {
+        self.config.path.ends_with(&format!(".{}", self.config.format))
+    }
+
+    /// Directory the output files are written to
+    fn output_dir(&self) -> PathBuf {
+        let path = Path::new(&self.config.path);
+        if self.is_file_path() {
+            path.parent().map(Path::to_path_buf).unwrap_or_default()
+        } else {
+            path.to_path_buf()
+        }
+    }
+
     /// Generate a filename based on the template
     fn generate_filename(&self, data: &Value) -> String {
+        if self.is_file_path() {
+            return Path::new(&self.config.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+
         let mut filename = self.config.filename_template.clone();
-        
+
         // Replace {{date}} with the current date
         let now = Utc::now();
         filename = filename.replace("{{date}}", &now.format("%Y-%m-%d").to_string());
-        
+
         // Replace {{connector_id}} with the connector ID if present
         if let Some(connector_id) = data.get("connector_id").and_then(|v| v.as_str()) {
             filename = filename.replace("{{connector_id}}", connector_id);
         }
-        
+
         // Add extension if not present
         if !filename.ends_with(&format!(".{}", self.config.format)) {
             filename = format!("{}.{}", filename, self.config.format);
         }
-        
+
         filename
     }
-    
+
     /// Get the full path for a file
     fn get_file_path(&self, filename: &str) -> PathBuf {
-        let base_path = Path::new(&self.config.path);
-        base_path.join(filename)
+        self.output_dir().join(filename)
     }
-}
 
-#[async_trait]
-impl Destination for FileDestination {
-    fn get_type(&self) -> &str {
-        "file"
+    /// Key of the rotation period a point in time falls in
+    fn period_key(&self, time: DateTime<Utc>) -> String {
+        if !self.config.rotate_files {
+            return String::new();
+        }
+
+        match self.config.rotation.as_str() {
+            "hourly" => time.format("%Y%m%d%H").to_string(),
+            "daily" => time.format("%Y%m%d").to_string(),
+            "weekly" => time.format("%G-W%V").to_string(),
+            "monthly" => time.format("%Y%m").to_string(),
+            _ => String::new(),
+        }
     }
-    
-    fn get_id(&self) -> &str {
-        &self.id
+
+    /// Size limit in bytes, if size-based rotation is enabled
+    fn max_file_size(&self) -> Option<u64> {
+        if self.config.rotate_files && self.config.max_file_size_mb > 0 {
+            Some(self.config.max_file_size_mb * 1024 * 1024)
+        } else {
+            None
+        }
     }
-    
-    async fn send(&self, data: Value) -> Result<()> {
-        // Create the output directory if it doesn't exist
-        fs::create_dir_all(&self.config.path)?;
-        
-        // Generate the filename
-        let filename = self.generate_filename(&data);
-        let file_path = self.get_file_path(&filename);
-        
-        // Serialize the data based on the format
-        let content = match self.config.format.as_str() {
-            "json" => serde_json::to_string_pretty(&data)?,
-            "jsonl" => {
-                let mut content = serde_json::to_string(&data)?;
-                content.push('\n');
-                content
-            },
-            "csv" => {
-                // In a real implementation, this would convert the JSON to CSV
-                // For now, just convert to string
-                format!("{}\n", serde_json::to_string(&data)?)
-            },
-            _ => return Err(anyhow::anyhow!("Unsupported format: {}", self.config.format)),
+
+    /// Pick up the state of a file this destination hasn't written to yet.
+    ///
+    /// A file left behind by an earlier run keeps its size, the period of its
+    /// last modification and its CSV header, so appending resumes where it
+    /// stopped and rotation still happens on time.
+    fn open_existing(&self, path: &Path) -> ActiveFile {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) if self.config.append && metadata.len() > 0 => metadata,
+            _ => {
+                return ActiveFile {
+                    period: self.period_key(Utc::now()),
+                    size: 0,
+                    csv_header: None,
+                };
+            }
         };
-        
-        // Write to the file
-        let mut file = File::create(file_path)?;
-        file.write_all(content.as_bytes())?;
-        
+
+        let modified = metadata.modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+
+        let csv_header = if self.config.format == "csv" && self.config.csv.include_header {
+            match read_csv_header(path, &self.config.csv) {
+                Ok(header) => Some(header),
+                Err(e) => {
+                    tracing::warn!("Failed to read CSV header of {}: {}", path.display(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Some(header) = &csv_header {
+            self.csv_columns.seed(header);
+        }
+
+        ActiveFile {
+            period: self.period_key(modified),
+            size: metadata.len(),
+            csv_header,
+        }
+    }
+
+    /// Move a file aside as the next sequence-numbered file, gzipping it if configured
+    fn roll(&self, path: &Path, state: &mut ActiveFile) -> Result<()> {
+        let rolled = next_rolled_path(path)?;
+        fs::rename(path, &rolled)?;
+        tracing::info!("Rotated {} to {}", path.display(), rolled.display());
+
+        if self.config.compress {
+            self.compress_later(rolled);
+        }
+
+        state.size = 0;
+        state.csv_header = None;
+
         Ok(())
     }
-    
-    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+
+    /// Forget files from earlier periods, gzipping them if configured.
+    ///
+    /// Filenames that contain the date change with the period, so the old
+    /// file is closed here rather than rolled.
+    fn close_stale_files(&self, active: &mut HashMap<PathBuf, ActiveFile>, current: &Path, period: &str) {
+        let stale: Vec<PathBuf> = active.iter()
+            .filter(|(path, file)| path.as_path() != current && file.period != period)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in stale {
+            active.remove(&path);
+
+            if self.config.compress && path.exists() {
+                self.compress_later(path);
+            }
+        }
+    }
+
+    /// Gzip a file on the blocking thread pool without waiting for it
+    fn compress_later(&self, path: PathBuf) {
+        let compression = tokio::task::spawn_blocking(move || {
+            if let Err(e) = compress_file(&path) {
+                tracing::warn!("Failed to compress {}: {}", path.display(), e);
+            }
+        });
+
+        if let Ok(mut compressions) = self.compressions.lock() {
+            compressions.retain(|compression| !compression.is_finished());
+            compressions.push(compression);
+        }
+    }
+
+    /// Wait for the compressions started so far
+    async fn wait_for_compressions(&self) -> Result<()> {
+        let compressions = match self.compressions.lock() {
+            Ok(mut compressions) => std::mem::take(&mut *compressions),
+            Err(_) => return Err(anyhow::anyhow!("File state lock poisoned")),
+        };
+
+        for compression in compressions {
+            compression.await?;
+        }
+
+        Ok(())
+    }
+
+    /// Serialize a chunk for a file with the given header.
+    ///
+    /// Returns the bytes to write and the CSV header of the file afterwards.
+    fn render(&self, chunk: &Chunk, header: Option<&[String]>) -> Result<(Vec<u8>, Option<Vec<String>>)> {
+        match chunk {
+            Chunk::Bytes(bytes) => Ok((bytes.clone(), None)),
+            Chunk::Csv(rows) => match header {
+                Some(header) => {
+                    let content = formats::encode_csv(&self.config.csv, header, rows, false)?;
+                    Ok((content, Some(header.to_vec())))
+                },
+                None => {
+                    let columns = self.csv_columns.current();
+                    let content = formats::encode_csv(&self.config.csv, &columns, rows, true)?;
+                    Ok((content, Some(columns)))
+                },
+            },
+        }
+    }
+
+    /// Write a chunk to a file, rotating the file first when needed
+    fn write_chunk(&self, path: &Path, chunk: Chunk) -> Result<()> {
+        let mut active = self.active_files.lock()
+            .map_err(|_| anyhow::anyhow!("File state lock poisoned"))?;
+
+        let period = self.period_key(Utc::now());
+        self.close_stale_files(&mut active, path, &period);
+
+        let state = active.entry(path.to_path_buf())
+            .or_insert_with(|| self.open_existing(path));
+
+        // Without append, every write replaces the file
+        if !self.config.append {
+            if let Chunk::Csv(rows) = &chunk {
+                self.csv_columns.extend(rows);
+            }
+            let (content, header) = self.render(&chunk, None)?;
+            File::create(path)?.write_all(&content)?;
+            state.size = content.len() as u64;
+            state.csv_header = header;
+            return Ok(());
+        }
+
+        // Roll over when the period the file was started in has ended
+        if state.size > 0 && state.period != period {
+            self.roll(path, state)?;
+        }
+        state.period = period;
+
+        if let Chunk::Csv(rows) = &chunk {
+            // New columns can't be added to a header that has already been written
+            if let Some(header) = &state.csv_header {
+                let new_columns = columns_not_in(rows, header);
+                if !new_columns.is_empty() {
+                    if !self.config.rotate_files {
+                        return Err(anyhow::anyhow!(
+                            "Records have columns {:?} not in the header of {}; enable rotate_files to start a new file when columns change",
+                            new_columns, path.display()
+                        ));
+                    }
+                    self.roll(path, state)?;
+                }
+            }
+
+            self.csv_columns.extend(rows);
+        }
+
+        let (mut content, mut header) = self.render(&chunk, state.csv_header.as_deref())?;
+
+        // Roll over when the write would push the file past the size limit
+        if let Some(max_size) = self.max_file_size() {
+            if state.size > 0 && state.size + content.len() as u64 > max_size {
+                self.roll(path, state)?;
+                (content, header) = self.render(&chunk, None)?;
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(&content)?;
+
+        state.size += content.len() as u64;
+        state.csv_header = header;
+
+        Ok(())
+    }
+
+    /// Write records to their files, grouped so each file gets a single write
+    fn write(&self, data: &[Value]) -> Result<()> {
         // Create the output directory if it doesn't exist
-        fs::create_dir_all(&self.config.path)?;
-        
-        // In a real implementation, this would handle batching and file rotation
-        // For now, just write each item to a file
+        fs::create_dir_all(self.output_dir())?;
+
+        // A Parquet batch is written as a single part file
+        if self.config.format == "parquet" {
+            let records: Vec<&Value> = data.iter()
+                .flat_map(formats::records_of)
+                .collect();
+            return self.write_parquet(&records);
+        }
+
+        let mut files: Vec<(PathBuf, Vec<&Value>)> = Vec::new();
         for item in data {
-            self.send(item).await?;
+            let file_path = self.get_file_path(&self.generate_filename(item));
+            match files.iter_mut().find(|(path, _)| *path == file_path) {
+                Some((_, items)) => items.push(item),
+                None => files.push((file_path, vec![item])),
+            }
         }
-        
+
+        for (file_path, items) in files {
+            let chunk = match self.config.format.as_str() {
+                "csv" => {
+                    let records: Vec<&Value> = items.iter()
+                        .flat_map(|item| formats::records_of(item))
+                        .collect();
+                    Chunk::Csv(self.csv_rows(&records))
+                },
+                _ => {
+                    let mut content = Vec::new();
+                    for item in items {
+                        content.extend(self.serialize_json(item)?);
+                    }
+                    Chunk::Bytes(content)
+                },
+            };
+
+            self.write_chunk(&file_path, chunk)?;
+        }
+
         Ok(())
     }
-    
-    async fn check_availability(&self) -> Result<bool> {
-        // Check if the directory exists or can be created
-        if let Err(e) = fs::create_dir_all(&self.config.path) {
+
+    /// Whether the output directory exists or can be created, and is writable
+    fn is_writable(&self) -> bool {
+        if let Err(e) = fs::create_dir_all(self.output_dir()) {
             tracing::error!("Failed to create directory {}: {}", self.config.path, e);
-            return Ok(false);
+            return false;
         }
-        
+
         // Check if we can write to the directory
         let test_file = self.get_file_path("test.tmp");
         match File::create(&test_file) {
             Ok(mut file) => {
                 // Write some data to ensure we can actually write
                 if file.write_all(b"test").is_err() {
-                    return Ok(false);
+                    return false;
                 }
-                
+
                 // Clean up
                 if let Err(e) = fs::remove_file(&test_file) {
                     tracing::warn!("Failed to remove test file: {}", e);
                 }
-                
-                Ok(true)
+
+                true
             },
             Err(e) => {
                 tracing::error!("Failed to create test file: {}", e);
-                Ok(false)
+                false
             }
         }
     }
-} 
\ No newline at end of file
+
+    /// Serialize a record for the JSON formats.
+    ///
+    /// Appended JSON is written one record per line, so the file stays
+    /// readable as NDJSON however many writes it collects.
+    fn serialize_json(&self, data: &Value) -> Result<Vec<u8>> {
+        let mut content = match self.config.format.as_str() {
+            "json" if self.config.append => serde_json::to_vec(data)?,
+            "json" => serde_json::to_vec_pretty(data)?,
+            "jsonl" => serde_json::to_vec(data)?,
+            _ => return Err(anyhow::anyhow!("Unsupported format: {}", self.config.format)),
+        };
+        content.push(b'\n');
+
+        Ok(content)
+    }
+
+    /// Flatten records into CSV rows
+    fn csv_rows(&self, records: &[&Value]) -> Vec<Map<String, Value>> {
+        records.iter()
+            .map(|record| formats::flatten_record(record, &self.config.csv.flatten_separator))
+            .collect()
+    }
+
+    /// Write records to a new Parquet part file.
+    ///
+    /// Parquet files can't be appended to, so every call produces a new file
+    /// named after the template with a timestamp and sequence suffix.
+    fn write_parquet(&self, records: &[&Value]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let content = {
+            let schema = self.source_schema.read()
+                .map_err(|_| anyhow::anyhow!("Source schema lock poisoned"))?;
+            formats::records_to_parquet(&self.config.parquet, records, schema.as_ref())?
+        };
+
+        let filename = self.generate_filename(records[0]);
+        let stem = filename.trim_end_matches(".parquet");
+        let sequence = self.part_sequence.fetch_add(1, Ordering::SeqCst);
+        let part_name = format!(
+            "{}-{}-{:04}.parquet",
+            stem,
+            Utc::now().format("%Y%m%dT%H%M%S%3f"),
+            sequence
+        );
+
+        let mut file = File::create(self.get_file_path(&part_name))?;
+        file.write_all(&content)?;
+
+        Ok(())
+    }
+}
+
+/// Read the header row of an existing CSV file
+fn read_csv_header(path: &Path, options: &CsvOptions) -> Result<Vec<String>> {
+    let mut line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut line)?;
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(options.delimiter as u8)
+        .has_headers(false)
+        .from_reader(line.as_bytes());
+
+    let record = reader.records().next()
+        .ok_or_else(|| anyhow::anyhow!("File has no header row"))??;
+
+    Ok(record.iter().map(str::to_string).collect())
+}
+
+/// Columns of CSV rows that a file's header doesn't have, in first-seen order
+fn columns_not_in(rows: &[Map<String, Value>], header: &[String]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+
+    for column in rows.iter().flat_map(|row| row.keys()) {
+        if !header.contains(column) && !columns.contains(column) {
+            columns.push(column.clone());
+        }
+    }
+
+    columns
+}
+
+/// Path for the next rolled file: `{stem}.{n}.{ext}` with `n` one above the highest in use
+fn next_rolled_path(path: &Path) -> Result<PathBuf> {
+    let stem = path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dir = path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+
+    let prefix = format!("{}.", stem);
+    let suffix = format!(".{}", extension);
+
+    let mut highest = 0;
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let name = name.strip_suffix(".gz").unwrap_or(&name);
+
+        let sequence = name.strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(&suffix))
+            .and_then(|n| n.parse::<u64>().ok());
+
+        if let Some(sequence) = sequence {
+            highest = highest.max(sequence);
+        }
+    }
+
+    Ok(dir.join(format!("{}{}{}", prefix, highest + 1, suffix)))
+}
+
+/// Path for the gzipped copy of a file: `{path}.gz`, or `{stem}-{n}.{ext}.gz`
+/// with the first `n` not in use if an earlier copy already has that name
+fn compressed_path(path: &Path) -> PathBuf {
+    let mut gz_name = path.as_os_str().to_owned();
+    gz_name.push(".gz");
+    let gz_path = PathBuf::from(gz_name);
+
+    if !gz_path.exists() {
+        return gz_path;
+    }
+
+    let stem = path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path.extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| path.with_file_name(format!("{}-{}{}.gz", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(gz_path)
+}
+
+/// Gzip a file without replacing an earlier gzipped copy, and remove the original
+fn compress_file(path: &Path) -> Result<()> {
+    let gz_path = compressed_path(path);
+
+    let mut input = File::open(path)?;
+    let output = OpenOptions::new().write(true).create_new(true).open(&gz_path)?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+
+    fs::remove_file(path)?;
+    tracing::debug!("Compressed {} to {}", path.display(), gz_path.display());
+
+    Ok(())
+}
+
+#[async_trait]
+impl Destination for FileDestination {
+    fn get_type(&self) -> &str {
+        "file"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.write(vec![data]).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        self.write(data).await
+    }
+
+    fn set_source_metadata(&self, metadata: &Value) {
+        if let Ok(mut schema) = self.writer.source_schema.write() {
+            *schema = SourceSchema::from_metadata(metadata);
+        }
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.writer.wait_for_compressions().await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        let writer = Arc::clone(&self.writer);
+        Ok(tokio::task::spawn_blocking(move || writer.is_writable()).await?)
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// CSV serialization options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvOptions {
+    /// Field delimiter (a single ASCII character)
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Quoting style (necessary, always, non_numeric, never)
+    #[serde(default = "default_quote_style")]
+    pub quote_style: String,
+    /// Whether to write a header row
+    #[serde(default = "default_include_header")]
+    pub include_header: bool,
+    /// Separator used when flattening nested objects into column names
+    #[serde(default = "default_flatten_separator")]
+    pub flatten_separator: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: default_delimiter(),
+            quote_style: default_quote_style(),
+            include_header: default_include_header(),
+            flatten_separator: default_flatten_separator(),
+        }
+    }
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_quote_style() -> String {
+    "necessary".to_string()
+}
+
+fn default_include_header() -> bool {
+    true
+}
+
+fn default_flatten_separator() -> String {
+    ".".to_string()
+}
+
+impl CsvOptions {
+    /// Build a CSV writer for these options
+    fn writer(&self) -> Result<::csv::Writer<Vec<u8>>> {
+        if !self.delimiter.is_ascii() {
+            return Err(anyhow!("CSV delimiter must be an ASCII character, got '{}'", self.delimiter));
+        }
+
+        let quote_style = match self.quote_style.as_str() {
+            "necessary" => ::csv::QuoteStyle::Necessary,
+            "always" => ::csv::QuoteStyle::Always,
+            "non_numeric" => ::csv::QuoteStyle::NonNumeric,
+            "never" => ::csv::QuoteStyle::Never,
+            other => return Err(anyhow!("Unsupported CSV quote style: {}", other)),
+        };
+
+        Ok(::csv::WriterBuilder::new()
+            .delimiter(self.delimiter as u8)
+            .quote_style(quote_style)
+            .has_headers(false)
+            .from_writer(Vec::new()))
+    }
+}
+
+/// Column order of a CSV output, kept stable across batches.
+///
+/// The first batch fixes the order of the columns it contains (sorted by
+/// name); columns seen later are appended at the end, so existing columns
+/// never move.
+#[derive(Debug, Default)]
+pub struct CsvColumns {
+    columns: Mutex<Vec<String>>,
+}
+
+impl CsvColumns {
+    /// Add columns read from an existing header, keeping any already known order
+    pub fn seed(&self, header: &[String]) {
+        let mut columns = match self.columns.lock() {
+            Ok(columns) => columns,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        for column in header {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+    }
+
+    /// Add the keys of the given rows and return the resulting column order
+    pub fn extend(&self, rows: &[Map<String, Value>]) -> Vec<String> {
+        let mut columns = match self.columns.lock() {
+            Ok(columns) => columns,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let new_keys: BTreeSet<&String> = rows.iter()
+            .flat_map(|row| row.keys())
+            .filter(|key| !columns.contains(key))
+            .collect();
+
+        columns.extend(new_keys.into_iter().cloned());
+        columns.clone()
+    }
+
+    /// Current column order
+    pub fn current(&self) -> Vec<String> {
+        match self.columns.lock() {
+            Ok(columns) => columns.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+/// Convert a flattened value to a CSV cell
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::Bool(b)) => b.to_string(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Encode flattened rows as CSV using the given column order
+pub fn encode_csv(
+    options: &CsvOptions,
+    columns: &[String],
+    rows: &[Map<String, Value>],
+    include_header: bool,
+) -> Result<Vec<u8>> {
+    let mut writer = options.writer()?;
+
+    if include_header && options.include_header {
+        writer.write_record(columns)?;
+    }
+
+    for row in rows {
+        writer.write_record(columns.iter().map(|column| cell(row.get(column))))?;
+    }
+
+    writer.into_inner()
+        .map_err(|e| anyhow!("Failed to flush CSV writer: {}", e.error()))
+}
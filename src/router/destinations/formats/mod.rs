@@ -0,0 +1,59 @@
+//! Output formats shared by the file-based destinations
+
+mod csv;
+mod parquet;
+mod schema;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::csv::{CsvColumns, CsvOptions, encode_csv};
+pub use self::parquet::{ParquetOptions, records_to_parquet};
+pub use self::schema::{FieldType, SourceSchema, infer_types, parse_timestamp};
+
+use serde_json::{Map, Value};
+
+/// Turn a payload into the list of records it contains.
+///
+/// Connectors such as GA4 deliver an array of rows, so an array payload
+/// yields one record per element while any other value is a single record.
+pub fn records_of(data: &Value) -> Vec<&Value> {
+    match data {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    }
+}
+
+/// Flatten nested objects into a single level, joining keys with `separator`.
+///
+/// Arrays are kept as values; scalar records are stored under a `value` key.
+pub fn flatten_record(data: &Value, separator: &str) -> Map<String, Value> {
+    let mut flat = Map::new();
+
+    match data {
+        Value::Object(obj) => flatten_into(&mut flat, None, obj, separator),
+        other => {
+            flat.insert("value".to_string(), other.clone());
+        },
+    }
+
+    flat
+}
+
+fn flatten_into(flat: &mut Map<String, Value>, prefix: Option<&str>, obj: &Map<String, Value>, separator: &str) {
+    for (key, value) in obj {
+        let name = match prefix {
+            Some(prefix) => format!("{}{}{}", prefix, separator, key),
+            None => key.clone(),
+        };
+
+        match value {
+            Value::Object(inner) if !inner.is_empty() => {
+                flatten_into(flat, Some(&name), inner, separator);
+            },
+            other => {
+                flat.insert(name, other.clone());
+            },
+        }
+    }
+}
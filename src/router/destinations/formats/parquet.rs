@@ -0,0 +1,230 @@
+use anyhow::{Result, anyhow};
+use arrow::array::{
+    ArrayRef, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder,
+    TimestampMicrosecondBuilder,
+};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::schema::{FieldType, SourceSchema, infer_types, parse_timestamp};
+
+/// Parquet serialization options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParquetOptions {
+    /// Compression codec (snappy, gzip, zstd, none)
+    #[serde(default = "default_compression")]
+    pub compression: String,
+    /// Maximum number of rows per row group
+    #[serde(default = "default_row_group_size")]
+    pub row_group_size: usize,
+    /// Separator used when flattening nested objects into column names
+    #[serde(default = "default_flatten_separator")]
+    pub flatten_separator: String,
+}
+
+impl Default for ParquetOptions {
+    fn default() -> Self {
+        Self {
+            compression: default_compression(),
+            row_group_size: default_row_group_size(),
+            flatten_separator: default_flatten_separator(),
+        }
+    }
+}
+
+fn default_compression() -> String {
+    "snappy".to_string()
+}
+
+fn default_row_group_size() -> usize {
+    10_000
+}
+
+fn default_flatten_separator() -> String {
+    ".".to_string()
+}
+
+impl ParquetOptions {
+    /// Reject a compression codec Parquet files can't be written with
+    pub fn validate(&self) -> Result<()> {
+        self.writer_properties().map(|_| ())
+    }
+
+    /// Writer properties for these options
+    fn writer_properties(&self) -> Result<WriterProperties> {
+        let compression = match self.compression.to_lowercase().as_str() {
+            "snappy" => Compression::SNAPPY,
+            "gzip" => Compression::GZIP(GzipLevel::default()),
+            "zstd" => Compression::ZSTD(ZstdLevel::default()),
+            "none" | "uncompressed" => Compression::UNCOMPRESSED,
+            other => return Err(anyhow!("Unsupported Parquet compression: {}", other)),
+        };
+
+        Ok(WriterProperties::builder()
+            .set_compression(compression)
+            .set_max_row_group_size(self.row_group_size.max(1))
+            .build())
+    }
+}
+
+/// Arrow type used to store a field type
+fn arrow_type(field_type: FieldType) -> DataType {
+    match field_type {
+        FieldType::Boolean => DataType::Boolean,
+        FieldType::Integer => DataType::Int64,
+        FieldType::Float => DataType::Float64,
+        FieldType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        FieldType::Text | FieldType::Json => DataType::Utf8,
+    }
+}
+
+/// Error for a value that doesn't fit its column
+fn type_error(column: &str, field_type: FieldType, value: &Value) -> anyhow::Error {
+    anyhow!("Column {} expects {} values, found {}", column, field_type.as_str(), value)
+}
+
+/// Build the Arrow array for one column
+fn build_column(column: &str, field_type: FieldType, rows: &[Map<String, Value>]) -> Result<ArrayRef> {
+    let values = rows.iter().map(|row| row.get(column).filter(|v| !v.is_null()));
+
+    let array: ArrayRef = match field_type {
+        FieldType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(Value::Bool(b)) => builder.append_value(*b),
+                    Some(Value::String(s)) if s == "true" || s == "false" => builder.append_value(s == "true"),
+                    Some(other) => return Err(type_error(column, field_type, other)),
+                }
+            }
+            Arc::new(builder.finish())
+        },
+        FieldType::Integer => {
+            let mut builder = Int64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(v) => {
+                        let int = v.as_i64()
+                            .or_else(|| v.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))
+                            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+                            .ok_or_else(|| type_error(column, field_type, v))?;
+                        builder.append_value(int);
+                    },
+                }
+            }
+            Arc::new(builder.finish())
+        },
+        FieldType::Float => {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(v) => {
+                        let float = v.as_f64()
+                            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+                            .ok_or_else(|| type_error(column, field_type, v))?;
+                        builder.append_value(float);
+                    },
+                }
+            }
+            Arc::new(builder.finish())
+        },
+        FieldType::Timestamp => {
+            let mut builder = TimestampMicrosecondBuilder::with_capacity(rows.len())
+                .with_timezone("UTC");
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(v) => {
+                        let micros = parse_timestamp(v)
+                            .map(|dt| dt.timestamp_micros())
+                            .ok_or_else(|| type_error(column, field_type, v))?;
+                        builder.append_value(micros);
+                    },
+                }
+            }
+            Arc::new(builder.finish())
+        },
+        FieldType::Text | FieldType::Json => {
+            let mut builder = StringBuilder::with_capacity(rows.len(), rows.len() * 16);
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(Value::String(s)) => builder.append_value(s),
+                    Some(other) => builder.append_value(other.to_string()),
+                }
+            }
+            Arc::new(builder.finish())
+        },
+    };
+
+    Ok(array)
+}
+
+/// Encode flattened rows as a Parquet file using the given column types
+fn encode_parquet(
+    options: &ParquetOptions,
+    columns: &[(String, FieldType)],
+    rows: &[Map<String, Value>],
+) -> Result<Vec<u8>> {
+    let fields: Vec<Field> = columns.iter()
+        .map(|(name, field_type)| Field::new(name, arrow_type(*field_type), true))
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+
+    let arrays = columns.iter()
+        .map(|(name, field_type)| build_column(name, *field_type, rows))
+        .collect::<Result<Vec<_>>>()?;
+
+    let batch = RecordBatch::try_new(Arc::clone(&schema), arrays)?;
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, Some(options.writer_properties()?))?;
+        writer.write(&batch)?;
+        writer.close()?;
+    }
+
+    Ok(buffer)
+}
+
+/// Encode records as Parquet, inferring the schema from the records.
+///
+/// When the source reported a schema, the records are validated against it
+/// and the reported types take precedence over the inferred ones.
+pub fn records_to_parquet(
+    options: &ParquetOptions,
+    records: &[&Value],
+    source_schema: Option<&SourceSchema>,
+) -> Result<Vec<u8>> {
+    let rows: Vec<Map<String, Value>> = records.iter()
+        .map(|record| super::flatten_record(record, &options.flatten_separator))
+        .collect();
+
+    let columns: Vec<String> = rows.iter()
+        .flat_map(|row| row.keys().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let inferred = infer_types(&columns, &rows);
+
+    let resolved = match source_schema {
+        Some(schema) => {
+            schema.validate(&inferred)?;
+            schema.resolve(&inferred)
+        },
+        None => inferred,
+    };
+
+    encode_parquet(options, &resolved, &rows)
+}
@@ -0,0 +1,209 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Logical type of a field in a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Boolean,
+    Integer,
+    Float,
+    Text,
+    Timestamp,
+    Json,
+}
+
+impl FieldType {
+    /// Type of a single JSON value (`None` for null)
+    pub fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(FieldType::Boolean),
+            Value::Number(n) if n.is_i64() || n.is_u64() => Some(FieldType::Integer),
+            Value::Number(_) => Some(FieldType::Float),
+            Value::String(_) => Some(FieldType::Text),
+            Value::Array(_) | Value::Object(_) => Some(FieldType::Json),
+        }
+    }
+
+    /// Widen two types into one that can hold values of both
+    pub fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (FieldType::Integer, FieldType::Float) | (FieldType::Float, FieldType::Integer) => FieldType::Float,
+            (FieldType::Json, _) | (_, FieldType::Json) => FieldType::Json,
+            _ => FieldType::Text,
+        }
+    }
+
+    /// Parse a type name as reported by connector metadata
+    /// (GA4 `TYPE_INTEGER`, BigQuery `FLOAT64`, HubSpot `number`, ...)
+    pub fn from_type_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let name = name.trim_start_matches("type_");
+
+        match name {
+            "bool" | "boolean" | "booleancheckbox" => Some(FieldType::Boolean),
+            "int" | "int64" | "integer" | "bigint" | "smallint" => Some(FieldType::Integer),
+            "float" | "float64" | "double" | "real" | "numeric" | "bignumeric" | "decimal"
+            | "number" | "currency" | "seconds" | "milliseconds" | "minutes" | "hours"
+            | "standard" | "feet" | "miles" | "meters" | "kilometers" => Some(FieldType::Float),
+            "string" | "text" | "enumeration" | "phone_number" | "date" => Some(FieldType::Text),
+            "timestamp" | "datetime" => Some(FieldType::Timestamp),
+            "json" | "jsonb" | "record" | "struct" | "object" | "array" => Some(FieldType::Json),
+            _ => None,
+        }
+    }
+
+    /// Name used in error messages and configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::Boolean => "boolean",
+            FieldType::Integer => "integer",
+            FieldType::Float => "float",
+            FieldType::Text => "text",
+            FieldType::Timestamp => "timestamp",
+            FieldType::Json => "json",
+        }
+    }
+
+    /// Whether data of type `actual` can be stored in a field of this type
+    pub fn accepts(&self, actual: FieldType) -> bool {
+        match (self, actual) {
+            (a, b) if *a == b => true,
+            (FieldType::Float, FieldType::Integer) => true,
+            (FieldType::Timestamp, FieldType::Text) => true,
+            (FieldType::Text, _) | (FieldType::Json, _) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Parse a timestamp from RFC 3339, `YYYY-MM-DD HH:MM:SS` or fractional Unix seconds
+pub fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|dt| dt.with_timezone(&Utc))
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+                    .map(|dt| dt.and_utc())
+            })
+            .ok(),
+        Value::Number(n) => n.as_f64().and_then(|secs| {
+            let micros = (secs * 1_000_000.0) as i64;
+            DateTime::from_timestamp(
+                micros.div_euclid(1_000_000),
+                (micros.rem_euclid(1_000_000) * 1_000) as u32,
+            )
+        }),
+        _ => None,
+    }
+}
+
+/// Infer the type of every column from a set of flattened rows.
+///
+/// Columns that only ever hold nulls default to text.
+pub fn infer_types(columns: &[String], rows: &[Map<String, Value>]) -> Vec<(String, FieldType)> {
+    columns.iter()
+        .map(|column| {
+            let field_type = rows.iter()
+                .filter_map(|row| row.get(column).and_then(FieldType::of))
+                .reduce(FieldType::merge)
+                .unwrap_or(FieldType::Text);
+            (column.clone(), field_type)
+        })
+        .collect()
+}
+
+/// Field types reported by a connector's `get_metadata`
+#[derive(Debug, Clone, Default)]
+pub struct SourceSchema {
+    fields: BTreeMap<String, FieldType>,
+}
+
+impl SourceSchema {
+    /// Build a schema from connector metadata.
+    ///
+    /// Understands GA4 `dimensions`/`metrics` lists, BigQuery `schema.fields`
+    /// and generic `fields`, `columns` or `properties` lists of
+    /// `{"name": ..., "type": ...}` objects. Returns `None` when the metadata
+    /// doesn't describe any fields.
+    pub fn from_metadata(metadata: &Value) -> Option<Self> {
+        let mut fields = BTreeMap::new();
+
+        // GA4 dimensions are always strings
+        if let Some(dimensions) = metadata.get("dimensions").and_then(Value::as_array) {
+            for dimension in dimensions {
+                if let Some(name) = dimension.get("name").and_then(Value::as_str) {
+                    fields.insert(name.to_string(), FieldType::Text);
+                }
+            }
+        }
+
+        let lists = [
+            metadata.get("metrics"),
+            metadata.get("fields"),
+            metadata.get("columns"),
+            metadata.get("properties"),
+            metadata.get("schema").and_then(|schema| schema.get("fields")),
+        ];
+
+        for list in lists.iter().flatten().filter_map(|list| list.as_array()) {
+            for field in list {
+                let name = match field.get("name").and_then(Value::as_str) {
+                    Some(name) => name,
+                    None => continue,
+                };
+
+                let field_type = field.get("type")
+                    .and_then(Value::as_str)
+                    .and_then(FieldType::from_type_name);
+
+                if let Some(field_type) = field_type {
+                    fields.insert(name.to_string(), field_type);
+                }
+            }
+        }
+
+        if fields.is_empty() {
+            None
+        } else {
+            Some(Self { fields })
+        }
+    }
+
+    /// Type the source reports for a field
+    pub fn field_type(&self, name: &str) -> Option<FieldType> {
+        self.fields.get(name).copied()
+    }
+
+    /// Resolve the column types for a batch: reported types win over inferred ones
+    pub fn resolve(&self, inferred: &[(String, FieldType)]) -> Vec<(String, FieldType)> {
+        inferred.iter()
+            .map(|(name, field_type)| {
+                (name.clone(), self.field_type(name).unwrap_or(*field_type))
+            })
+            .collect()
+    }
+
+    /// Check inferred column types against the reported schema
+    pub fn validate(&self, inferred: &[(String, FieldType)]) -> Result<()> {
+        let mismatches: Vec<String> = inferred.iter()
+            .filter_map(|(name, actual)| {
+                self.field_type(name)
+                    .filter(|expected| !expected.accepts(*actual))
+                    .map(|expected| format!(
+                        "{} (expected {}, found {})",
+                        name, expected.as_str(), actual.as_str()
+                    ))
+            })
+            .collect();
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Records don't match the source schema: {}", mismatches.join(", ")))
+        }
+    }
+}
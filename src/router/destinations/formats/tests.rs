@@ -0,0 +1,138 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use super::super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flatten_record() {
+        let record = json!({
+            "date": "2024-01-01",
+            "geo": {"country": "US", "city": {"name": "Austin"}},
+            "tags": ["a", "b"]
+        });
+
+        let flat = flatten_record(&record, ".");
+        assert_eq!(flat.get("date"), Some(&json!("2024-01-01")));
+        assert_eq!(flat.get("geo.country"), Some(&json!("US")));
+        assert_eq!(flat.get("geo.city.name"), Some(&json!("Austin")));
+        assert_eq!(flat.get("tags"), Some(&json!(["a", "b"])));
+    }
+
+    #[test]
+    fn test_csv_columns_are_stable_across_batches() {
+        let columns = CsvColumns::default();
+
+        let first = vec![flatten_record(&json!({"b": 1, "a": 2}), ".")];
+        assert_eq!(columns.extend(&first), vec!["a", "b"]);
+
+        // New columns are appended, existing ones keep their position
+        let second = vec![flatten_record(&json!({"c": 3, "a": 4}), ".")];
+        assert_eq!(columns.extend(&second), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_encode_csv_quotes_and_missing_values() {
+        let options = CsvOptions::default();
+        let rows = vec![
+            flatten_record(&json!({"name": "Smith, J", "visits": 3}), "."),
+            flatten_record(&json!({"name": "say \"hi\""}), "."),
+        ];
+        let columns = vec!["name".to_string(), "visits".to_string()];
+
+        let csv = encode_csv(&options, &columns, &rows, true).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "name,visits\n\"Smith, J\",3\n\"say \"\"hi\"\"\",\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_csv_custom_delimiter() {
+        let options = CsvOptions {
+            delimiter: ';',
+            quote_style: "always".to_string(),
+            ..CsvOptions::default()
+        };
+        let rows = vec![flatten_record(&json!({"a": 1, "b": "x"}), ".")];
+        let columns = vec!["a".to_string(), "b".to_string()];
+
+        let csv = encode_csv(&options, &columns, &rows, false).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "\"1\";\"x\"\n");
+    }
+
+    #[test]
+    fn test_parquet_round_trip_with_mixed_and_missing_fields() {
+        use arrow::array::{Array, BooleanArray, Float64Array, Int64Array, StringArray};
+        use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let records = [
+            json!({"id": 1, "name": "a", "geo": {"country": "US"}, "score": 1.5, "active": true}),
+            json!({"id": 2, "score": 2, "tags": ["x"], "code": 7}),
+            json!({"id": 3, "name": null, "code": "B"}),
+        ];
+        let records: Vec<&serde_json::Value> = records.iter().collect();
+
+        let content = records_to_parquet(&ParquetOptions::default(), &records, None).unwrap();
+        let batches: Vec<_> = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(content))
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+        let names: Vec<String> = batch.schema().fields().iter().map(|field| field.name().clone()).collect();
+        assert_eq!(names, vec!["active", "code", "geo.country", "id", "name", "score", "tags"]);
+        assert!(batch.schema().fields().iter().all(|field| field.is_nullable()));
+
+        let id = column("id");
+        let id = id.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(id.iter().collect::<Vec<_>>(), vec![Some(1), Some(2), Some(3)]);
+
+        // Integers and floats widen to floats; missing fields are null
+        let score = column("score");
+        let score = score.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(score.iter().collect::<Vec<_>>(), vec![Some(1.5), Some(2.0), None]);
+
+        let active = column("active");
+        let active = active.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert_eq!(active.iter().collect::<Vec<_>>(), vec![Some(true), None, None]);
+
+        // Numbers and strings in one column widen to text, arrays are stored as JSON
+        let code = column("code");
+        let code = code.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(code.iter().collect::<Vec<_>>(), vec![None, Some("7"), Some("B")]);
+
+        let tags = column("tags");
+        let tags = tags.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(tags.iter().collect::<Vec<_>>(), vec![None, Some("[\"x\"]"), None]);
+
+        let name = column("name");
+        assert_eq!(name.null_count(), 2);
+        let country = column("geo.country");
+        let country = country.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(country.value(0), "US");
+    }
+
+    #[test]
+    fn test_parquet_rejects_unsupported_compression() {
+        let options = ParquetOptions {
+            compression: "lzma".to_string(),
+            ..ParquetOptions::default()
+        };
+
+        let error = options.validate().unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported Parquet compression: lzma");
+
+        let record = json!({"id": 1});
+        assert!(records_to_parquet(&options, &[&record], None).is_err());
+
+        for compression in ["snappy", "GZIP", "zstd", "none"] {
+            let options = ParquetOptions { compression: compression.to_string(), ..ParquetOptions::default() };
+            assert!(options.validate().is_ok(), "{}", compression);
+        }
+    }
+}
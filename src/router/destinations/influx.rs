@@ -0,0 +1,310 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::router::Destination;
+use super::{formats::parse_timestamp, lookup};
+
+/// Precision of written timestamps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InfluxPrecision {
+    /// Nanoseconds
+    #[default]
+    Ns,
+    /// Microseconds
+    Us,
+    /// Milliseconds
+    Ms,
+    /// Seconds
+    S,
+}
+
+impl InfluxPrecision {
+    fn as_str(&self) -> &'static str {
+        match self {
+            InfluxPrecision::Ns => "ns",
+            InfluxPrecision::Us => "us",
+            InfluxPrecision::Ms => "ms",
+            InfluxPrecision::S => "s",
+        }
+    }
+
+    /// Convert a time to an integer timestamp of this precision
+    fn timestamp(&self, time: DateTime<Utc>) -> i64 {
+        match self {
+            InfluxPrecision::Ns => time.timestamp_nanos_opt().unwrap_or_default(),
+            InfluxPrecision::Us => time.timestamp_micros(),
+            InfluxPrecision::Ms => time.timestamp_millis(),
+            InfluxPrecision::S => time.timestamp(),
+        }
+    }
+}
+
+/// Configuration for the InfluxDB destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfluxDestinationConfig {
+    /// Base URL of the InfluxDB v2 (or compatible) server
+    pub url: String,
+    /// Organization written to
+    #[serde(default)]
+    pub org: Option<String>,
+    /// Bucket written to
+    pub bucket: String,
+    /// API token
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Measurement name
+    pub measurement: String,
+    /// Record field whose value overrides the measurement name
+    #[serde(default)]
+    pub measurement_field: Option<String>,
+    /// Fields written as tags (dot-separated paths)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Numeric fields written as fields (all numeric top-level fields if empty)
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Field holding the time (RFC 3339 or Unix seconds); the write time if not set
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+    /// Timestamp precision (ns, us, ms or s)
+    #[serde(default)]
+    pub precision: InfluxPrecision,
+    /// Write integral values as integers instead of floats
+    #[serde(default)]
+    pub integers: bool,
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+/// Destination that writes records to InfluxDB in line protocol
+pub struct InfluxDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the InfluxDB destination
+    pub config: InfluxDestinationConfig,
+    /// HTTP client
+    client: Client,
+}
+
+impl InfluxDestination {
+    /// Create a new InfluxDB destination
+    pub fn new(id: String, config: InfluxDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// Convert a record to a line of line protocol, or `None` if it has no fields
+    fn line(&self, data: &Value, now: DateTime<Utc>) -> Option<String> {
+        let measurement = self.config.measurement_field.as_ref()
+            .and_then(|field| lookup(data, field))
+            .and_then(|value| value.as_str())
+            .filter(|name| !name.is_empty())
+            .filter(|name| writable("measurement", name))
+            .unwrap_or(&self.config.measurement);
+
+        let mut tags: Vec<(String, String)> = self.config.tags.iter()
+            .filter_map(|path| {
+                let value = tag_value(lookup(data, path)?)?;
+                (writable("tag", path) && writable("tag value", &value))
+                    .then(|| (escape_key(path), escape_key(&value)))
+            })
+            .collect();
+        // InfluxDB recommends sorting tags by key
+        tags.sort();
+
+        let fields: Vec<(String, String)> = if self.config.fields.is_empty() {
+            data.as_object()
+                .map(|obj| {
+                    obj.iter()
+                        .filter(|(key, _)| !self.config.tags.contains(*key))
+                        .filter(|(key, _)| self.config.timestamp_field.as_ref() != Some(*key))
+                        .filter(|(key, _)| writable("field", key))
+                        .filter_map(|(key, value)| Some((escape_key(key), self.field_value(value)?)))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            self.config.fields.iter()
+                .filter(|path| writable("field", path))
+                .filter_map(|path| Some((escape_key(path), self.field_value(lookup(data, path)?)?)))
+                .collect()
+        };
+
+        if fields.is_empty() {
+            return None;
+        }
+
+        let mut line = escape_measurement(measurement);
+        for (key, value) in &tags {
+            line.push_str(&format!(",{}={}", key, value));
+        }
+
+        line.push(' ');
+        line.push_str(
+            &fields.iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+
+        let time = self.config.timestamp_field.as_ref()
+            .and_then(|field| lookup(data, field))
+            .and_then(parse_timestamp)
+            .unwrap_or(now);
+        line.push_str(&format!(" {}", self.config.precision.timestamp(time)));
+
+        Some(line)
+    }
+
+    /// Format a numeric value as a field value
+    fn field_value(&self, value: &Value) -> Option<String> {
+        let number = match value {
+            Value::Number(n) => n,
+            _ => return None,
+        };
+
+        if self.config.integers {
+            if let Some(i) = number.as_i64() {
+                return Some(format!("{}i", i));
+            }
+        }
+
+        let float = number.as_f64()?;
+        float.is_finite().then(|| format!("{}", float))
+    }
+
+    fn write_url(&self) -> String {
+        format!("{}/api/v2/write", self.config.url.trim_end_matches('/'))
+    }
+
+    /// Write a body of line protocol
+    async fn write(&self, body: String) -> Result<()> {
+        let mut query = vec![
+            ("bucket", self.config.bucket.clone()),
+            ("precision", self.config.precision.as_str().to_string()),
+        ];
+        if let Some(org) = &self.config.org {
+            query.push(("org", org.clone()));
+        }
+
+        let mut request = self.client.post(self.write_url())
+            .query(&query)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .timeout(Duration::from_secs(self.config.timeout))
+            .body(body);
+
+        if let Some(token) = &self.config.token {
+            request = request.header("Authorization", format!("Token {}", token));
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("InfluxDB write error ({}): {}", status, error_text));
+        }
+
+        Ok(())
+    }
+}
+
+/// Tag value of a record value; InfluxDB doesn't allow empty tag values
+fn tag_value(value: &Value) -> Option<String> {
+    let value = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+
+    (!value.is_empty()).then_some(value)
+}
+
+/// Escape a measurement name (commas and spaces)
+fn escape_measurement(name: &str) -> String {
+    escape(name, &[',', ' '])
+}
+
+/// Escape a tag key, tag value or field key (commas, equals signs and spaces)
+fn escape_key(key: &str) -> String {
+    escape(key, &[',', '=', ' '])
+}
+
+/// Whether a name or tag value can be written; line protocol has no way
+/// to escape line breaks, so text containing them is left out with a warning
+fn writable(what: &str, text: &str) -> bool {
+    let writable = !text.contains(['\n', '\r']);
+    if !writable {
+        tracing::warn!("Skipping InfluxDB {} {:?}: line protocol can't contain line breaks", what, text);
+    }
+    writable
+}
+
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[async_trait]
+impl Destination for InfluxDestination {
+    fn get_type(&self) -> &str {
+        "influx"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.send_batch(vec![data]).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        let now = Utc::now();
+        let lines: Vec<String> = data.iter().filter_map(|record| self.line(record, now)).collect();
+
+        if lines.len() < data.len() {
+            tracing::debug!("Skipped {} records without numeric fields", data.len() - lines.len());
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        // One write per batch
+        self.write(lines.join("\n")).await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        let url = format!("{}/health", self.config.url.trim_end_matches('/'));
+        match self.client.get(&url).timeout(Duration::from_secs(self.config.timeout)).send().await {
+            Ok(response) => Ok(response.status().is_success()),
+            Err(e) => {
+                tracing::error!("InfluxDB is unavailable: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::future::join_all;
+use once_cell::sync::OnceCell;
+use rdkafka::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::router::{Destination, PartialDelivery};
+use super::{lookup, render_template, value_to_string};
+
+/// Most message errors quoted in a failed delivery's error message
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Compression codec of produced message batches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KafkaCompression {
+    /// No compression
+    #[default]
+    None,
+    /// gzip
+    Gzip,
+    /// Snappy
+    Snappy,
+    /// LZ4
+    Lz4,
+    /// Zstandard
+    Zstd,
+}
+
+impl KafkaCompression {
+    fn as_str(&self) -> &'static str {
+        match self {
+            KafkaCompression::None => "none",
+            KafkaCompression::Gzip => "gzip",
+            KafkaCompression::Snappy => "snappy",
+            KafkaCompression::Lz4 => "lz4",
+            KafkaCompression::Zstd => "zstd",
+        }
+    }
+}
+
+/// Configuration for the Kafka destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KafkaDestinationConfig {
+    /// Bootstrap brokers, e.g. "kafka-1:9092,kafka-2:9092"
+    pub brokers: String,
+    /// Topic template; `{{field}}` placeholders are replaced with record fields
+    pub topic: String,
+    /// Field holding the message key (messages have no key if not set)
+    #[serde(default)]
+    pub key_field: Option<String>,
+    /// Message headers taken from the record (header name to field path)
+    #[serde(default = "default_headers")]
+    pub headers: HashMap<String, String>,
+    /// Produce a batch as one message holding a JSON array, per topic
+    #[serde(default)]
+    pub message_per_batch: bool,
+    /// Compression codec
+    #[serde(default)]
+    pub compression: KafkaCompression,
+    /// Use the idempotent producer (no duplicates or reordering on retries)
+    #[serde(default = "default_idempotent")]
+    pub idempotent: bool,
+    /// Time to wait for more messages before sending a batch to the broker, in milliseconds
+    #[serde(default = "default_linger_ms")]
+    pub linger_ms: u64,
+    /// Time to wait for a message to be acknowledged, in milliseconds
+    #[serde(default = "default_delivery_timeout_ms")]
+    pub delivery_timeout_ms: u64,
+    /// Additional librdkafka producer properties, e.g. security settings
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+fn default_headers() -> HashMap<String, String> {
+    HashMap::from([
+        ("collection_id".to_string(), "collection_id".to_string()),
+        ("connector_id".to_string(), "connector_id".to_string()),
+    ])
+}
+
+fn default_idempotent() -> bool {
+    true
+}
+
+fn default_linger_ms() -> u64 {
+    5
+}
+
+fn default_delivery_timeout_ms() -> u64 {
+    30_000
+}
+
+/// A message to produce
+pub(crate) struct OutgoingMessage {
+    pub(crate) topic: String,
+    pub(crate) key: Option<String>,
+    pub(crate) headers: OwnedHeaders,
+    pub(crate) payload: String,
+    /// Positions in the batch of the records the message holds
+    pub(crate) records: Vec<usize>,
+}
+
+/// Destination that produces records to Kafka topics
+pub struct KafkaDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the Kafka destination
+    pub config: KafkaDestinationConfig,
+    /// Producer, created on first use
+    producer: OnceCell<FutureProducer>,
+}
+
+impl KafkaDestination {
+    /// Create a new Kafka destination
+    pub fn new(id: String, config: KafkaDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            producer: OnceCell::new(),
+        }
+    }
+
+    /// Producer properties from the configuration
+    pub(crate) fn client_config(&self) -> ClientConfig {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &self.config.brokers)
+            .set("client.id", format!("muxly-{}", self.id))
+            .set("compression.type", self.config.compression.as_str())
+            .set("linger.ms", self.config.linger_ms.to_string())
+            .set("message.timeout.ms", self.config.delivery_timeout_ms.to_string());
+
+        if self.config.idempotent {
+            // The idempotent producer requires acknowledgement by all in-sync replicas
+            client_config
+                .set("enable.idempotence", "true")
+                .set("acks", "all");
+        }
+
+        // Explicit properties win over the settings above
+        for (key, value) in &self.config.properties {
+            client_config.set(key, value);
+        }
+
+        client_config
+    }
+
+    fn producer(&self) -> Result<&FutureProducer> {
+        self.producer.get_or_try_init(|| {
+            self.client_config().create::<FutureProducer>()
+                .map_err(|e| anyhow!("Failed to create Kafka producer: {}", e))
+        })
+    }
+
+    /// Topic for a record, or `None` if a placeholder field is missing
+    pub(crate) fn topic(&self, data: &Value) -> Option<String> {
+        render_template(&self.config.topic, data).map(|topic| sanitize_topic(&topic))
+    }
+
+    pub(crate) fn key(&self, data: &Value) -> Option<String> {
+        self.config.key_field.as_ref()
+            .and_then(|field| lookup(data, field))
+            .filter(|value| !value.is_null())
+            .map(value_to_string)
+    }
+
+    pub(crate) fn headers(&self, data: &Value) -> OwnedHeaders {
+        let mut names: Vec<&String> = self.config.headers.keys().collect();
+        names.sort();
+
+        names.into_iter().fold(OwnedHeaders::new(), |headers, name| {
+            match lookup(data, &self.config.headers[name]).filter(|value| !value.is_null()) {
+                Some(value) => headers.insert(Header {
+                    key: name,
+                    value: Some(&value_to_string(value)),
+                }),
+                None => headers,
+            }
+        })
+    }
+
+    /// Messages for a batch of records, and the positions of the records
+    /// missing a field of the topic template, which can't be sent
+    pub(crate) fn messages(&self, data: &[Value]) -> Result<(Vec<OutgoingMessage>, Vec<usize>)> {
+        let mut topics: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut missing = Vec::new();
+        for (position, record) in data.iter().enumerate() {
+            let topic = match self.topic(record) {
+                Some(topic) => topic,
+                None => {
+                    missing.push(position);
+                    continue;
+                }
+            };
+
+            match topics.iter_mut().find(|(name, _)| self.config.message_per_batch && *name == topic) {
+                Some((_, records)) => records.push(position),
+                None => topics.push((topic, vec![position])),
+            }
+        }
+
+        let mut messages = Vec::new();
+        for (topic, records) in topics {
+            // Keys and headers of the first record describe a batch message
+            let first = &data[records[0]];
+            let payload = if self.config.message_per_batch {
+                serde_json::to_string(&records.iter().map(|&position| &data[position]).collect::<Vec<_>>())?
+            } else {
+                serde_json::to_string(first)?
+            };
+
+            messages.push(OutgoingMessage {
+                key: self.key(first),
+                headers: self.headers(first),
+                topic,
+                payload,
+                records,
+            });
+        }
+
+        Ok((messages, missing))
+    }
+
+    /// Produce messages and wait for all of them to be acknowledged.
+    ///
+    /// Returns the position and error of each message that failed.
+    async fn produce(&self, messages: &[OutgoingMessage]) -> Result<Vec<(usize, String)>> {
+        if messages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let producer = self.producer()?;
+        let timeout = Timeout::After(Duration::from_millis(self.config.delivery_timeout_ms));
+
+        let deliveries = messages.iter().map(|message| {
+            let mut record = FutureRecord::to(&message.topic)
+                .payload(&message.payload)
+                .headers(message.headers.clone());
+            if let Some(key) = &message.key {
+                record = record.key(key);
+            }
+            producer.send(record, timeout)
+        });
+
+        Ok(join_all(deliveries).await
+            .into_iter()
+            .zip(messages)
+            .enumerate()
+            .filter_map(|(position, (result, message))| {
+                result.err().map(|(e, _)| (position, format!("{}: {}", message.topic, e)))
+            })
+            .collect())
+    }
+
+    /// Outcome of a delivery in which the `failed` messages weren't
+    /// acknowledged and the records at the `missing` positions had no topic.
+    ///
+    /// When only some records failed, the delivery fails with a
+    /// [`PartialDelivery`] naming them, so only those are retried or
+    /// dead-lettered.
+    pub(crate) fn outcome(&self, messages: &[OutgoingMessage], failed: Vec<(usize, String)>, missing: Vec<usize>) -> Result<()> {
+        if failed.is_empty() && missing.is_empty() {
+            return Ok(());
+        }
+
+        let mut errors = Vec::new();
+        if !missing.is_empty() {
+            errors.push(format!("{} records are missing a field of topic {}", missing.len(), self.config.topic));
+        }
+        if !failed.is_empty() {
+            let mut error = format!(
+                "{} of {} messages failed: {}",
+                failed.len(),
+                messages.len(),
+                failed.iter().take(MAX_REPORTED_ERRORS).map(|(_, e)| e.as_str()).collect::<Vec<_>>().join("; ")
+            );
+            if failed.len() > MAX_REPORTED_ERRORS {
+                error.push_str(&format!("; and {} more", failed.len() - MAX_REPORTED_ERRORS));
+            }
+            errors.push(error);
+        }
+        let message = errors.join("; ");
+
+        // Nothing was delivered, whether the brokers are down or no record
+        // had a topic
+        if failed.len() == messages.len() {
+            return Err(anyhow!(message));
+        }
+
+        let mut positions = missing;
+        positions.extend(failed.iter().flat_map(|(position, _)| messages[*position].records.iter().copied()));
+        positions.sort_unstable();
+
+        Err(PartialDelivery { failed: positions, message }.into())
+    }
+}
+
+/// Replace characters topic names can't contain
+fn sanitize_topic(part: &str) -> String {
+    part.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .collect()
+}
+
+#[async_trait]
+impl Destination for KafkaDestination {
+    fn get_type(&self) -> &str {
+        "kafka"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.send_batch(vec![data]).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        let (messages, missing) = self.messages(&data)?;
+        let failed = self.produce(&messages).await?;
+        self.outcome(&messages, failed, missing)
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        let producer = self.producer()?.clone();
+        let timeout = Duration::from_millis(self.config.delivery_timeout_ms.min(10_000));
+
+        // Fetching metadata blocks until the brokers answer
+        let result = tokio::task::spawn_blocking(move || {
+            producer.client().fetch_metadata(None, timeout).map(|_| ())
+        }).await?;
+
+        match result {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                tracing::error!("Kafka brokers are unavailable: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
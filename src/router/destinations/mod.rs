@@ -1,15 +1,91 @@
-mod database;
-mod email;
-mod file;
-mod prometheus;
-mod slack;
-mod storage;
-mod webhook;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+pub(crate) mod database;
+pub(crate) mod digest;
+pub(crate) mod email;
+pub(crate) mod file;
+pub(crate) mod formats;
+pub(crate) mod influx;
+#[cfg(feature = "kafka")]
+pub(crate) mod kafka;
+pub(crate) mod otlp;
+pub(crate) mod prometheus;
+pub(crate) mod search_index;
+pub(crate) mod slack;
+pub(crate) mod statsd;
+pub(crate) mod storage;
+pub(crate) mod webhook;
 
 pub use database::DatabaseDestination;
 pub use email::EmailDestination;
 pub use file::FileDestination;
+pub use influx::InfluxDestination;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaDestination;
+pub use otlp::OtlpDestination;
 pub use prometheus::PrometheusDestination;
+pub(crate) use prometheus::{MetricKind, MetricRegistry};
+pub use search_index::SearchIndexDestination;
 pub use slack::SlackDestination;
+pub use statsd::StatsdDestination;
 pub use storage::S3Destination;
-pub use webhook::WebhookDestination; 
\ No newline at end of file
+pub use webhook::WebhookDestination;
+#[cfg(test)]
+pub use webhook::{sign_payload, verify_signature};
+
+/// Look up a dot-separated field path in a record
+pub(crate) fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(data, |current, part| current.get(part))
+}
+
+/// Text of a field value, with strings unquoted
+pub(crate) fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Fill the `{{field}}` placeholders of a template from a record.
+///
+/// Returns `None` if a field is missing or a placeholder isn't closed.
+pub(crate) fn render_template(template: &str, record: &Value) -> Option<String> {
+    render_template_with(template, |field| lookup(record, field).map(value_to_string))
+}
+
+/// Fill the `{{name}}` placeholders of a template with `resolve`, or `None`
+/// if it has no value for one or a placeholder isn't closed
+pub(crate) fn render_template_with(
+    template: &str,
+    mut resolve: impl FnMut(&str) -> Option<String>,
+) -> Option<String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = start + rest[start..].find("}}")?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&resolve(rest[start + 2..end].trim())?);
+        rest = &rest[end + 2..];
+    }
+
+    rendered.push_str(rest);
+    Some(rendered)
+}
+
+/// Value of a `{{date}}`, `{{year}}`, `{{month}}`, `{{day}}`, `{{hour}}`,
+/// `{{minute}}` or `{{timestamp}}` placeholder
+pub(crate) fn time_placeholder(name: &str, time: DateTime<Utc>) -> Option<String> {
+    let format = match name {
+        "date" => "%Y-%m-%d",
+        "year" => "%Y",
+        "month" => "%m",
+        "day" => "%d",
+        "hour" => "%H",
+        "minute" => "%M",
+        "timestamp" => "%Y%m%d_%H%M%S",
+        _ => return None,
+    };
+    Some(time.format(format).to_string())
+}
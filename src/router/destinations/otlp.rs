@@ -0,0 +1,568 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use opentelemetry_proto::tonic::collector::logs::v1::{
+    ExportLogsServiceRequest, ExportLogsServiceResponse, logs_service_client::LogsServiceClient,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse, metrics_service_client::MetricsServiceClient,
+};
+use opentelemetry_proto::tonic::common::v1::{
+    AnyValue, ArrayValue, InstrumentationScope, KeyValue, KeyValueList, any_value,
+};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs, SeverityNumber};
+use opentelemetry_proto::tonic::metrics::v1::{
+    AggregationTemporality, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum, metric,
+    number_data_point,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tonic::metadata::{MetadataKey, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+
+use crate::router::Destination;
+use super::{formats::parse_timestamp, lookup};
+
+/// Name of the instrumentation scope exported data is attributed to
+const SCOPE_NAME: &str = "muxly.router";
+
+/// Transport used to reach the collector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtlpProtocol {
+    /// Protobuf over HTTP, to `/v1/metrics` or `/v1/logs`
+    #[default]
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+    /// gRPC
+    #[serde(rename = "grpc")]
+    Grpc,
+}
+
+/// What records are exported as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpSignal {
+    /// Data points of the configured metrics
+    #[default]
+    Metrics,
+    /// One log record per record
+    Logs,
+}
+
+/// Type of an exported metric
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpMetricKind {
+    /// Last value wins
+    #[default]
+    Gauge,
+    /// Each record adds its value (delta temporality)
+    Sum,
+}
+
+/// Configuration for the OTLP destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpDestinationConfig {
+    /// Collector endpoint (e.g. http://otel-collector:4318 for HTTP, :4317 for gRPC)
+    pub endpoint: String,
+    /// Transport: "http/protobuf" or "grpc"
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// Export records as "metrics" or "logs"
+    #[serde(default)]
+    pub signal: OtlpSignal,
+    /// Headers sent with every export (e.g. an API key)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Metrics to export, for the metrics signal
+    #[serde(default)]
+    pub metrics: Vec<OtlpMetricConfig>,
+    /// Attributes of every data point or log record (attribute name to field path)
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    /// Resource attributes (static values), e.g. service.name
+    #[serde(default)]
+    pub resource_attributes: HashMap<String, String>,
+    /// Field holding the log body (the whole record if not set)
+    #[serde(default)]
+    pub body_field: Option<String>,
+    /// Field holding the log severity (trace, debug, info, warn, error, fatal)
+    #[serde(default)]
+    pub severity_field: Option<String>,
+    /// Field holding the timestamp (RFC 3339 or Unix seconds); the send time if not set
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout_seconds: u64,
+}
+
+/// Definition of a single metric derived from records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpMetricConfig {
+    /// Metric name
+    pub name: String,
+    /// Metric type (gauge or sum)
+    #[serde(rename = "type", default)]
+    pub metric_type: OtlpMetricKind,
+    /// Field (dot-separated path) holding the value
+    pub value_field: String,
+    /// Description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Unit, e.g. "ms" or "{requests}"
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Whether a sum only goes up
+    #[serde(default = "default_monotonic")]
+    pub monotonic: bool,
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+fn default_monotonic() -> bool {
+    true
+}
+
+impl OtlpDestinationConfig {
+    /// Reject settings that can't export anything
+    pub fn validate(&self) -> Result<()> {
+        if !self.endpoint.starts_with("http://") && !self.endpoint.starts_with("https://") {
+            return Err(anyhow!("OTLP endpoint must be an http(s) URL, got {}", self.endpoint));
+        }
+
+        if self.signal == OtlpSignal::Metrics && self.metrics.is_empty() {
+            return Err(anyhow!("OTLP metrics export needs at least one metric"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Destination that exports records to an OpenTelemetry Collector as OTLP
+/// metrics or logs
+pub struct OtlpDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the OTLP destination
+    pub config: OtlpDestinationConfig,
+    /// HTTP client, for http/protobuf
+    client: Client,
+    /// gRPC channel, connected on first use
+    channel: OnceCell<Channel>,
+    /// Data points or log records the collector rejected in partially
+    /// successful exports
+    rejected: AtomicU64,
+}
+
+impl OtlpDestination {
+    /// Create a new OTLP destination
+    pub fn new(id: String, config: OtlpDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            client: Client::new(),
+            channel: OnceCell::new(),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Data points or log records the collector rejected in partially
+    /// successful exports since the destination was created
+    #[cfg(test)]
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Count and log the items a collector rejected in an export it
+    /// otherwise accepted.
+    ///
+    /// The export still succeeds: OTLP forbids retrying a partial success,
+    /// and the response doesn't say which records were rejected.
+    fn partial_success(&self, rejected: i64, items: &str, message: &str) {
+        if rejected <= 0 {
+            return;
+        }
+
+        let total = self.rejected.fetch_add(rejected as u64, Ordering::Relaxed) + rejected as u64;
+        tracing::warn!(
+            "OTLP collector rejected {} {} exported by {} ({} so far): {}",
+            rejected, items, self.id, total, message
+        );
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout_seconds.max(1))
+    }
+
+    /// Resource shared by everything exported
+    fn resource(&self) -> Resource {
+        let mut attributes: Vec<KeyValue> = self.config.resource_attributes.iter()
+            .map(|(key, value)| key_value(key, string_value(value)))
+            .collect();
+
+        if !self.config.resource_attributes.contains_key("service.name") {
+            attributes.push(key_value("service.name", string_value("muxly")));
+        }
+        attributes.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Resource {
+            attributes,
+            ..Default::default()
+        }
+    }
+
+    fn scope(&self) -> InstrumentationScope {
+        InstrumentationScope {
+            name: SCOPE_NAME.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Attributes of a record, from the configured field mapping
+    fn attributes(&self, data: &Value) -> Vec<KeyValue> {
+        let mut attributes: Vec<KeyValue> = self.config.attributes.iter()
+            .filter_map(|(name, path)| lookup(data, path).map(|value| key_value(name, any_value(value))))
+            .collect();
+
+        attributes.sort_by(|a, b| a.key.cmp(&b.key));
+        attributes
+    }
+
+    /// Timestamp of a record in Unix nanoseconds
+    fn timestamp(&self, data: &Value) -> u64 {
+        let now = || Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+
+        let field = match &self.config.timestamp_field {
+            Some(field) => field,
+            None => return now(),
+        };
+
+        let nanos = lookup(data, field)
+            .and_then(parse_timestamp)
+            .and_then(|time| time.timestamp_nanos_opt());
+
+        nanos.map_or_else(now, |nanos| nanos.max(0) as u64)
+    }
+
+    /// Build a metrics export request from records
+    fn metrics_request(&self, data: &[Value]) -> ExportMetricsServiceRequest {
+        let metrics = self.config.metrics.iter()
+            .filter_map(|config| {
+                let data_points: Vec<NumberDataPoint> = data.iter()
+                    .filter_map(|record| {
+                        let value = lookup(record, &config.value_field).and_then(number_value)?;
+                        Some(NumberDataPoint {
+                            attributes: self.attributes(record),
+                            time_unix_nano: self.timestamp(record),
+                            value: Some(value),
+                            ..Default::default()
+                        })
+                    })
+                    .collect();
+
+                if data_points.is_empty() {
+                    tracing::debug!("No record has field {}, skipping metric {}", config.value_field, config.name);
+                    return None;
+                }
+
+                let data = match config.metric_type {
+                    OtlpMetricKind::Gauge => metric::Data::Gauge(Gauge { data_points }),
+                    OtlpMetricKind::Sum => metric::Data::Sum(Sum {
+                        data_points,
+                        aggregation_temporality: AggregationTemporality::Delta as i32,
+                        is_monotonic: config.monotonic,
+                    }),
+                };
+
+                Some(Metric {
+                    name: config.name.clone(),
+                    description: config.description.clone().unwrap_or_default(),
+                    unit: config.unit.clone().unwrap_or_default(),
+                    data: Some(data),
+                })
+            })
+            .collect();
+
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(self.resource()),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(self.scope()),
+                    metrics,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Build a logs export request from records
+    fn logs_request(&self, data: &[Value]) -> ExportLogsServiceRequest {
+        let log_records = data.iter()
+            .map(|record| {
+                let body = match &self.config.body_field {
+                    Some(field) => lookup(record, field).map(any_value).unwrap_or_else(|| string_value("")),
+                    None => string_value(&record.to_string()),
+                };
+
+                let severity_text = self.config.severity_field.as_ref()
+                    .and_then(|field| lookup(record, field))
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+
+                let time_unix_nano = self.timestamp(record);
+
+                LogRecord {
+                    time_unix_nano,
+                    observed_time_unix_nano: time_unix_nano,
+                    severity_number: severity_number(&severity_text) as i32,
+                    severity_text,
+                    body: Some(body),
+                    attributes: self.attributes(record),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(self.resource()),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(self.scope()),
+                    log_records,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// URL of the HTTP export path for the configured signal
+    fn http_url(&self) -> String {
+        let path = match self.config.signal {
+            OtlpSignal::Metrics => "/v1/metrics",
+            OtlpSignal::Logs => "/v1/logs",
+        };
+
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        if endpoint.ends_with(path) {
+            endpoint.to_string()
+        } else {
+            format!("{}{}", endpoint, path)
+        }
+    }
+
+    /// Export an encoded request over HTTP; returns the encoded response
+    async fn export_http(&self, body: Vec<u8>) -> Result<Vec<u8>> {
+        let mut request = self.client.post(self.http_url())
+            .header("Content-Type", "application/x-protobuf")
+            .timeout(self.timeout())
+            .body(body);
+
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("OTLP collector error ({}): {}", status, error_text));
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// gRPC channel to the collector
+    fn channel(&self) -> Result<Channel> {
+        let channel = self.channel.get_or_try_init(|| -> Result<Channel> {
+            let endpoint = Endpoint::from_shared(self.config.endpoint.clone())?
+                .timeout(self.timeout());
+            Ok(endpoint.connect_lazy())
+        })?;
+
+        Ok(channel.clone())
+    }
+
+    /// Wrap a message in a gRPC request carrying the configured headers
+    fn grpc_request<T>(&self, message: T) -> Result<tonic::Request<T>> {
+        let mut request = tonic::Request::new(message);
+
+        for (name, value) in &self.config.headers {
+            let key = MetadataKey::from_bytes(name.to_lowercase().as_bytes())
+                .map_err(|_| anyhow!("Invalid OTLP header name: {}", name))?;
+            let value = MetadataValue::try_from(value.as_str())
+                .map_err(|_| anyhow!("Invalid value for OTLP header {}", name))?;
+            request.metadata_mut().insert(key, value);
+        }
+
+        Ok(request)
+    }
+
+    /// Export records for the configured signal and protocol
+    async fn export(&self, data: &[Value]) -> Result<()> {
+        match (self.config.signal, self.config.protocol) {
+            (OtlpSignal::Metrics, OtlpProtocol::HttpProtobuf) => {
+                let body = self.export_http(self.metrics_request(data).encode_to_vec()).await?;
+                // A body that isn't a response message is taken as full success
+                let response = ExportMetricsServiceResponse::decode(body.as_slice()).unwrap_or_default();
+                self.metrics_response(response);
+            },
+            (OtlpSignal::Logs, OtlpProtocol::HttpProtobuf) => {
+                let body = self.export_http(self.logs_request(data).encode_to_vec()).await?;
+                let response = ExportLogsServiceResponse::decode(body.as_slice()).unwrap_or_default();
+                self.logs_response(response);
+            },
+            (OtlpSignal::Metrics, OtlpProtocol::Grpc) => {
+                let request = self.grpc_request(self.metrics_request(data))?;
+                let response = MetricsServiceClient::new(self.channel()?).export(request).await
+                    .map_err(|status| anyhow!("OTLP collector error: {}", status))?;
+                self.metrics_response(response.into_inner());
+            },
+            (OtlpSignal::Logs, OtlpProtocol::Grpc) => {
+                let request = self.grpc_request(self.logs_request(data))?;
+                let response = LogsServiceClient::new(self.channel()?).export(request).await
+                    .map_err(|status| anyhow!("OTLP collector error: {}", status))?;
+                self.logs_response(response.into_inner());
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Handle the collector's answer to a metrics export
+    fn metrics_response(&self, response: ExportMetricsServiceResponse) {
+        if let Some(partial) = response.partial_success {
+            self.partial_success(partial.rejected_data_points, "data points", &partial.error_message);
+        }
+    }
+
+    /// Handle the collector's answer to a logs export
+    fn logs_response(&self, response: ExportLogsServiceResponse) {
+        if let Some(partial) = response.partial_success {
+            self.partial_success(partial.rejected_log_records, "log records", &partial.error_message);
+        }
+    }
+}
+
+/// Convert a JSON value to a data point value, keeping integers exact
+fn number_value(value: &Value) -> Option<number_data_point::Value> {
+    match value {
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(number_data_point::Value::AsInt(i)),
+            None => n.as_f64().map(number_data_point::Value::AsDouble),
+        },
+        Value::Bool(b) => Some(number_data_point::Value::AsInt(*b as i64)),
+        Value::String(s) => s.parse::<f64>().ok().map(number_data_point::Value::AsDouble),
+        _ => None,
+    }
+}
+
+fn key_value(key: &str, value: AnyValue) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(value),
+    }
+}
+
+fn string_value(value: &str) -> AnyValue {
+    AnyValue {
+        value: Some(any_value::Value::StringValue(value.to_string())),
+    }
+}
+
+/// Convert a JSON value to an OTLP attribute value
+fn any_value(value: &Value) -> AnyValue {
+    let value = match value {
+        Value::Null => None,
+        Value::Bool(b) => Some(any_value::Value::BoolValue(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(any_value::Value::IntValue(i)),
+            None => n.as_f64().map(any_value::Value::DoubleValue),
+        },
+        Value::String(s) => Some(any_value::Value::StringValue(s.clone())),
+        Value::Array(items) => Some(any_value::Value::ArrayValue(ArrayValue {
+            values: items.iter().map(any_value).collect(),
+        })),
+        Value::Object(obj) => Some(any_value::Value::KvlistValue(KeyValueList {
+            values: obj.iter().map(|(key, value)| key_value(key, any_value(value))).collect(),
+        })),
+    };
+
+    AnyValue { value }
+}
+
+/// Map a severity name to its OTLP severity number
+fn severity_number(severity: &str) -> SeverityNumber {
+    match severity.to_lowercase().as_str() {
+        "trace" => SeverityNumber::Trace,
+        "debug" => SeverityNumber::Debug,
+        "info" | "information" | "notice" => SeverityNumber::Info,
+        "warn" | "warning" => SeverityNumber::Warn,
+        "error" | "err" => SeverityNumber::Error,
+        "fatal" | "critical" | "crit" => SeverityNumber::Fatal,
+        _ => SeverityNumber::Unspecified,
+    }
+}
+
+#[async_trait]
+impl Destination for OtlpDestination {
+    fn get_type(&self) -> &str {
+        "otlp"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.export(std::slice::from_ref(&data)).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // One export request per batch
+        self.export(&data).await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        // Collectors have no standard health endpoint on the OTLP ports,
+        // so check that the endpoint can be reached at all
+        match self.config.protocol {
+            OtlpProtocol::HttpProtobuf => match self.client.get(self.http_url()).timeout(self.timeout()).send().await {
+                Ok(_) => Ok(true),
+                Err(e) => {
+                    tracing::error!("OTLP collector {} is unreachable: {}", self.config.endpoint, e);
+                    Ok(false)
+                }
+            },
+            OtlpProtocol::Grpc => {
+                let endpoint = Endpoint::from_shared(self.config.endpoint.clone())?
+                    .connect_timeout(self.timeout());
+                match endpoint.connect().await {
+                    Ok(_) => Ok(true),
+                    Err(e) => {
+                        tracing::error!("OTLP collector {} is unreachable: {}", self.config.endpoint, e);
+                        Ok(false)
+                    }
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,630 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use reqwest::{header, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::config::models::router::{default_include_labels, default_metric_type};
+use crate::router::Destination;
+use super::lookup;
+
+/// Content type of the Prometheus text exposition format
+const TEXT_FORMAT_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Default histogram buckets (same as the Prometheus client libraries)
+const DEFAULT_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Configuration for the Prometheus destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrometheusDestinationConfig {
+    /// Labels attached to metrics of numeric fields (label name to field
+    /// path), when `metrics` is empty
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Metric type used when a metric doesn't specify one (counter, gauge, histogram)
+    #[serde(default = "default_metric_type")]
+    pub default_metric_type: String,
+    /// Whether string fields become labels of the metrics of numeric
+    /// fields, alongside `labels`, when `metrics` is empty
+    #[serde(default = "default_include_labels")]
+    pub include_labels: bool,
+    /// Prefix added to every metric name
+    #[serde(default)]
+    pub metric_prefix: Option<String>,
+    /// Explicit metric definitions; when empty every numeric field becomes a metric
+    #[serde(default)]
+    pub metrics: Vec<PrometheusMetricConfig>,
+    /// Default histogram buckets
+    #[serde(default)]
+    pub buckets: Option<Vec<f64>>,
+    /// Optional Pushgateway to push metrics to after every delivery
+    #[serde(default)]
+    pub pushgateway: Option<PushgatewayConfig>,
+}
+
+/// Definition of a single metric derived from records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrometheusMetricConfig {
+    /// Metric name
+    pub name: String,
+    /// Metric type (counter, gauge, histogram)
+    #[serde(rename = "type", default)]
+    pub metric_type: Option<String>,
+    /// Help text
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Field (dot-separated path) holding the value
+    pub value_field: String,
+    /// Labels to attach (label name to field path)
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Histogram buckets for this metric
+    #[serde(default)]
+    pub buckets: Option<Vec<f64>>,
+}
+
+/// Pushgateway settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushgatewayConfig {
+    /// Base URL of the Pushgateway (e.g. http://pushgateway:9091)
+    pub url: String,
+    /// Job name used in the grouping key
+    #[serde(default = "default_job")]
+    pub job: String,
+    /// Additional grouping labels
+    #[serde(default)]
+    pub grouping: HashMap<String, String>,
+    /// Optional basic auth username
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Optional basic auth password
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_job() -> String {
+    "muxly".to_string()
+}
+
+/// Supported Prometheus metric types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "counter" => Ok(MetricKind::Counter),
+            "gauge" => Ok(MetricKind::Gauge),
+            "histogram" => Ok(MetricKind::Histogram),
+            _ => Err(anyhow!("Unsupported metric type: {}", value)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+/// Label set of a series, sorted by label name
+type LabelSet = Vec<(String, String)>;
+
+/// Current value of a single series
+#[derive(Debug, Clone)]
+enum SeriesValue {
+    Scalar(f64),
+    Histogram {
+        /// Non-cumulative bucket counts, one per upper bound
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+/// All series of one metric name
+#[derive(Debug, Clone)]
+struct MetricFamily {
+    kind: MetricKind,
+    help: String,
+    buckets: Vec<f64>,
+    series: BTreeMap<LabelSet, SeriesValue>,
+}
+
+/// A single value recorded for a metric
+#[derive(Debug, Clone)]
+pub(crate) struct Observation {
+    name: String,
+    kind: MetricKind,
+    help: String,
+    buckets: Vec<f64>,
+    labels: LabelSet,
+    value: f64,
+}
+
+/// In-memory metric registry rendered in the text exposition format
+#[derive(Debug, Default)]
+pub(crate) struct MetricRegistry {
+    families: Mutex<BTreeMap<String, MetricFamily>>,
+}
+
+impl MetricRegistry {
+    /// Record an observation for a metric
+    pub(crate) fn observe(
+        &self,
+        name: &str,
+        kind: MetricKind,
+        help: &str,
+        buckets: &[f64],
+        labels: LabelSet,
+        value: f64,
+    ) -> Result<()> {
+        self.observe_all(vec![Observation {
+            name: name.to_string(),
+            kind,
+            help: help.to_string(),
+            buckets: buckets.to_vec(),
+            labels,
+            value,
+        }])
+    }
+
+    /// Record a set of observations: all of them, or none if any is invalid
+    pub(crate) fn observe_all(&self, observations: Vec<Observation>) -> Result<()> {
+        let mut families = self.families.lock()
+            .map_err(|_| anyhow!("Metric registry lock poisoned"))?;
+
+        // Metrics first seen in this set take the kind of their first observation
+        let mut kinds: HashMap<&str, MetricKind> = HashMap::new();
+
+        for observation in &observations {
+            let name = observation.name.as_str();
+
+            if !observation.value.is_finite() {
+                return Err(anyhow!("Metric {} received a non-finite value", name));
+            }
+
+            let kind = families.get(name).map(|family| family.kind)
+                .unwrap_or_else(|| *kinds.entry(name).or_insert(observation.kind));
+            if kind != observation.kind {
+                return Err(anyhow!("Metric {} is already registered as a {}", name, kind.as_str()));
+            }
+
+            if kind == MetricKind::Counter && observation.value < 0.0 {
+                return Err(anyhow!("Counter {} cannot be decreased by {}", name, observation.value));
+            }
+        }
+
+        for observation in observations {
+            let Observation { name, kind, help, buckets, labels, value } = observation;
+
+            let family = families.entry(name).or_insert_with(|| MetricFamily {
+                kind,
+                help,
+                buckets,
+                series: BTreeMap::new(),
+            });
+
+            match kind {
+                MetricKind::Counter => {
+                    let entry = family.series.entry(labels).or_insert(SeriesValue::Scalar(0.0));
+                    if let SeriesValue::Scalar(current) = entry {
+                        *current += value;
+                    }
+                },
+                MetricKind::Gauge => {
+                    family.series.insert(labels, SeriesValue::Scalar(value));
+                },
+                MetricKind::Histogram => {
+                    let bucket_count = family.buckets.len();
+                    let entry = family.series.entry(labels).or_insert_with(|| SeriesValue::Histogram {
+                        counts: vec![0; bucket_count],
+                        sum: 0.0,
+                        count: 0,
+                    });
+                    if let SeriesValue::Histogram { counts, sum, count } = entry {
+                        if let Some(index) = family.buckets.iter().position(|upper| value <= *upper) {
+                            counts[index] += 1;
+                        }
+                        *sum += value;
+                        *count += 1;
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub(crate) fn render(&self) -> String {
+        let families = match self.families.lock() {
+            Ok(families) => families,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let mut output = String::new();
+
+        for (name, family) in families.iter() {
+            output.push_str(&format!("# HELP {} {}\n", name, escape_help(&family.help)));
+            output.push_str(&format!("# TYPE {} {}\n", name, family.kind.as_str()));
+
+            for (labels, value) in &family.series {
+                match value {
+                    SeriesValue::Scalar(v) => {
+                        output.push_str(&format!(
+                            "{}{} {}\n",
+                            name, format_labels(labels, None), format_value(*v)
+                        ));
+                    },
+                    SeriesValue::Histogram { counts, sum, count } => {
+                        let mut cumulative = 0;
+                        for (upper, bucket) in family.buckets.iter().zip(counts) {
+                            cumulative += bucket;
+                            output.push_str(&format!(
+                                "{}_bucket{} {}\n",
+                                name,
+                                format_labels(labels, Some(&format_value(*upper))),
+                                cumulative
+                            ));
+                        }
+                        output.push_str(&format!(
+                            "{}_bucket{} {}\n",
+                            name, format_labels(labels, Some("+Inf")), count
+                        ));
+                        output.push_str(&format!(
+                            "{}_sum{} {}\n",
+                            name, format_labels(labels, None), format_value(*sum)
+                        ));
+                        output.push_str(&format!(
+                            "{}_count{} {}\n",
+                            name, format_labels(labels, None), count
+                        ));
+                    },
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Format a label set, optionally adding the histogram `le` label
+fn format_labels(labels: &LabelSet, le: Option<&str>) -> String {
+    let mut parts: Vec<String> = labels.iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+        .collect();
+
+    if let Some(le) = le {
+        parts.push(format!("le=\"{}\"", le));
+    }
+
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+/// Format a sample value
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape a label value as required by the text format
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escape help text as required by the text format
+fn escape_help(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// Turn an arbitrary string into a valid metric or label name
+pub(crate) fn sanitize_name(name: &str, allow_colon: bool) -> String {
+    let mut sanitized: String = name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if sanitized.chars().next().is_none_or(|c| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+
+    sanitized
+}
+
+/// Convert a JSON value to a metric value
+fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// Convert a JSON value to a label value
+fn label_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Destination that exposes routed records as Prometheus metrics
+pub struct PrometheusDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the Prometheus destination
+    pub config: PrometheusDestinationConfig,
+    /// Collected metrics
+    registry: MetricRegistry,
+    /// HTTP client for the Pushgateway
+    client: Client,
+}
+
+impl PrometheusDestination {
+    /// Create a new Prometheus destination
+    pub fn new(id: String, config: PrometheusDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            registry: MetricRegistry::default(),
+            client: Client::new(),
+        }
+    }
+
+    /// Render the current metrics in the text exposition format
+    pub fn render(&self) -> String {
+        self.registry.render()
+    }
+
+    /// Name of a metric with the configured prefix applied
+    fn metric_name(&self, name: &str) -> String {
+        let prefix = self.config.metric_prefix.as_deref().unwrap_or("");
+        sanitize_name(&format!("{}{}", prefix, name), true)
+    }
+
+    /// Buckets to use for a histogram
+    fn buckets_for(&self, metric: Option<&PrometheusMetricConfig>) -> Vec<f64> {
+        let mut buckets = metric
+            .and_then(|m| m.buckets.clone())
+            .or_else(|| self.config.buckets.clone())
+            .unwrap_or_else(|| DEFAULT_BUCKETS.to_vec());
+        buckets.retain(|b| b.is_finite());
+        buckets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        buckets.dedup();
+        buckets
+    }
+
+    /// Labels of a record, from a map of label name to field path
+    fn labels(&self, data: &Value, fields: &HashMap<String, String>) -> LabelSet {
+        let mut labels: LabelSet = fields.iter()
+            .filter_map(|(label, path)| {
+                lookup(data, path)
+                    .and_then(label_value)
+                    .map(|v| (sanitize_name(label, false), v))
+            })
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Record a batch of data items as metrics.
+    ///
+    /// Every item is checked before any is recorded, so a rejected batch
+    /// that is sent again isn't counted twice.
+    fn record(&self, data: &[Value]) -> Result<()> {
+        let mut observations = Vec::new();
+        for item in data {
+            if self.config.metrics.is_empty() {
+                self.observe_implicit(item, &mut observations)?;
+            } else {
+                self.observe_explicit(item, &mut observations)?;
+            }
+        }
+
+        self.registry.observe_all(observations)
+    }
+
+    /// Observe every numeric top-level field as a metric
+    fn observe_implicit(&self, data: &Value, observations: &mut Vec<Observation>) -> Result<()> {
+        let obj = data.as_object()
+            .ok_or_else(|| anyhow!("Prometheus destination expects JSON objects"))?;
+
+        let kind = MetricKind::parse(&self.config.default_metric_type)?;
+        let mut labels = self.labels(data, &self.config.labels);
+        let buckets = self.buckets_for(None);
+
+        // String fields label the record's metrics, unless a configured
+        // label already has the name
+        if self.config.include_labels {
+            for (key, value) in obj {
+                if let Value::String(s) = value {
+                    let name = sanitize_name(key, false);
+                    if !labels.iter().any(|(label, _)| *label == name) {
+                        labels.push((name, s.clone()));
+                    }
+                }
+            }
+            labels.sort();
+        }
+
+        for (key, value) in obj {
+            let number = match value {
+                Value::Number(n) => n.as_f64(),
+                _ => None,
+            };
+
+            if let Some(number) = number {
+                observations.push(Observation {
+                    name: self.metric_name(key),
+                    kind,
+                    help: format!("Value of field {} routed by Muxly", key),
+                    buckets: buckets.clone(),
+                    labels: labels.clone(),
+                    value: number,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Observe the configured metric definitions
+    fn observe_explicit(&self, data: &Value, observations: &mut Vec<Observation>) -> Result<()> {
+        for metric in &self.config.metrics {
+            let value = match lookup(data, &metric.value_field).and_then(numeric_value) {
+                Some(value) => value,
+                None => {
+                    tracing::debug!(
+                        "Field {} missing or not numeric, skipping metric {}",
+                        metric.value_field, metric.name
+                    );
+                    continue;
+                }
+            };
+
+            let kind_str = metric.metric_type.as_deref().unwrap_or(&self.config.default_metric_type);
+            let kind = MetricKind::parse(kind_str)?;
+
+            observations.push(Observation {
+                name: self.metric_name(&metric.name),
+                kind,
+                help: metric.description.clone()
+                    .unwrap_or_else(|| format!("Value of field {} routed by Muxly", metric.value_field)),
+                buckets: self.buckets_for(Some(metric)),
+                labels: self.labels(data, &metric.labels),
+                value,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Push the current metrics after recording a delivery.
+    ///
+    /// The records are already counted, so a failed push is logged rather
+    /// than failing the delivery, which would count them again when it is
+    /// retried. The next push sends the totals, including these records.
+    async fn push_recorded(&self) {
+        if let Err(e) = self.push().await {
+            tracing::warn!("Failed to push metrics of destination {} to the Pushgateway: {}", self.id, e);
+        }
+    }
+
+    /// Push the current metrics to the Pushgateway, if configured
+    async fn push(&self) -> Result<()> {
+        let gateway = match &self.config.pushgateway {
+            Some(gateway) => gateway,
+            None => return Ok(()),
+        };
+
+        let mut url = format!(
+            "{}/metrics/job/{}",
+            gateway.url.trim_end_matches('/'),
+            urlencode(&gateway.job)
+        );
+
+        let mut grouping: Vec<_> = gateway.grouping.iter().collect();
+        grouping.sort();
+        for (name, value) in grouping {
+            url.push_str(&format!("/{}/{}", sanitize_name(name, false), urlencode(value)));
+        }
+
+        let mut request = self.client.put(&url)
+            .header(header::CONTENT_TYPE, TEXT_FORMAT_CONTENT_TYPE)
+            .body(self.render());
+
+        if let Some(username) = &gateway.username {
+            request = request.basic_auth(username, gateway.password.as_ref());
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Pushgateway error ({}): {}", status, error_text));
+        }
+
+        Ok(())
+    }
+}
+
+/// Percent-encode a path segment for the Pushgateway grouping key
+fn urlencode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+#[async_trait]
+impl Destination for PrometheusDestination {
+    fn get_type(&self) -> &str {
+        "prometheus"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    fn scrape(&self) -> Option<String> {
+        Some(self.render())
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.record(std::slice::from_ref(&data))?;
+        self.push_recorded().await;
+        Ok(())
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.record(&data)?;
+
+        // Push once per batch rather than once per record
+        self.push_recorded().await;
+        Ok(())
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        let gateway = match &self.config.pushgateway {
+            Some(gateway) => gateway,
+            None => return Ok(true),
+        };
+
+        let url = format!("{}/-/healthy", gateway.url.trim_end_matches('/'));
+        match self.client.get(&url).send().await {
+            Ok(response) => Ok(response.status().is_success()),
+            Err(e) => {
+                tracing::error!("Pushgateway health check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
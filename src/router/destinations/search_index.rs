@@ -0,0 +1,282 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::router::{Destination, PartialDelivery};
+use super::{formats::parse_timestamp, lookup, render_template_with, time_placeholder, value_to_string};
+
+/// Most item errors quoted in a failed delivery's error message
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Bulk action used to write documents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkAction {
+    /// Create or replace the document
+    #[default]
+    Index,
+    /// Only create the document; an existing ID counts as already delivered
+    Create,
+}
+
+impl BulkAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BulkAction::Index => "index",
+            BulkAction::Create => "create",
+        }
+    }
+}
+
+/// Configuration for the Elasticsearch/OpenSearch destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchIndexDestinationConfig {
+    /// Base URL of the cluster
+    pub url: String,
+    /// Index name template
+    #[serde(default = "default_index")]
+    pub index: String,
+    /// Field holding the document ID; IDs are generated by the cluster if not set
+    #[serde(default)]
+    pub id_field: Option<String>,
+    /// Bulk action (index or create)
+    #[serde(default)]
+    pub action: BulkAction,
+    /// Field holding the time used for `{{date}}` in the index name; the send time if not set
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+    /// Ingest pipeline to run documents through
+    #[serde(default)]
+    pub pipeline: Option<String>,
+    /// Username for basic authentication
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password for basic authentication
+    #[serde(default)]
+    pub password: Option<String>,
+    /// API key (base64-encoded `id:key`), sent as `Authorization: ApiKey ...`
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_index() -> String {
+    "muxly-{{connector_id}}-{{date}}".to_string()
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+/// Destination that indexes records in Elasticsearch or OpenSearch through the `_bulk` API
+pub struct SearchIndexDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the search index destination
+    pub config: SearchIndexDestinationConfig,
+    /// HTTP client
+    client: Client,
+}
+
+impl SearchIndexDestination {
+    /// Create a new search index destination
+    pub fn new(id: String, config: SearchIndexDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// Index name for a record.
+    ///
+    /// Supports `{{date}}`, `{{year}}`, `{{month}}`, `{{day}}`, `{{hour}}`,
+    /// `{{minute}}` and `{{timestamp}}` (from `timestamp_field` or the send
+    /// time) and any record field, e.g.
+    /// `{{connector_id}}`. Missing fields are rendered as `unknown`.
+    fn index_name(&self, data: &Value, now: DateTime<Utc>) -> String {
+        let time = self.config.timestamp_field.as_ref()
+            .and_then(|field| lookup(data, field))
+            .and_then(parse_timestamp)
+            .unwrap_or(now);
+
+        let name = render_template_with(&self.config.index, |name| {
+            time_placeholder(name, time).or_else(|| match lookup(data, name) {
+                Some(Value::Null) | None => Some("unknown".to_string()),
+                Some(value) => Some(value_to_string(value)),
+            })
+        });
+
+        sanitize_index(&name.unwrap_or_else(|| self.config.index.clone()))
+    }
+
+    /// Build the NDJSON body of a bulk request
+    fn bulk_body(&self, data: &[Value]) -> Result<String> {
+        let now = Utc::now();
+        let mut body = String::new();
+
+        for record in data {
+            let mut meta = json!({ "_index": self.index_name(record, now) });
+
+            if let Some(id_field) = &self.config.id_field {
+                let id = match lookup(record, id_field) {
+                    Some(Value::String(s)) if !s.is_empty() => s.clone(),
+                    Some(Value::Number(n)) => n.to_string(),
+                    _ => return Err(anyhow!("Record has no document ID in field {}", id_field)),
+                };
+                meta["_id"] = Value::String(id);
+            }
+
+            let mut action = serde_json::Map::new();
+            action.insert(self.config.action.as_str().to_string(), meta);
+            body.push_str(&Value::Object(action).to_string());
+            body.push('\n');
+            body.push_str(&serde_json::to_string(record)?);
+            body.push('\n');
+        }
+
+        Ok(body)
+    }
+
+    /// Send a bulk request and check every item of the response.
+    ///
+    /// Rejected documents fail the delivery with a [`PartialDelivery`], so
+    /// only they are retried or dead-lettered.
+    async fn bulk(&self, data: &[Value]) -> Result<()> {
+        let mut request = self.client.post(format!("{}/_bulk", self.config.url.trim_end_matches('/')))
+            .header("Content-Type", "application/x-ndjson")
+            .timeout(Duration::from_secs(self.config.timeout))
+            .body(self.bulk_body(data)?);
+
+        if let Some(pipeline) = &self.config.pipeline {
+            request = request.query(&[("pipeline", pipeline)]);
+        }
+
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("Authorization", format!("ApiKey {}", api_key));
+        } else if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.as_ref());
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Bulk request failed ({}): {}", status, error_text));
+        }
+
+        let body: Value = response.json().await?;
+        let failed = self.failed_items(&body);
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = format!(
+            "{} of {} documents failed: {}",
+            failed.len(),
+            data.len(),
+            failed.iter().take(MAX_REPORTED_ERRORS).map(|(_, error)| error.as_str()).collect::<Vec<_>>().join("; ")
+        );
+        if failed.len() > MAX_REPORTED_ERRORS {
+            message.push_str(&format!("; and {} more", failed.len() - MAX_REPORTED_ERRORS));
+        }
+
+        Err(PartialDelivery {
+            failed: failed.into_iter().map(|(position, _)| position).collect(),
+            message,
+        }.into())
+    }
+
+    /// Positions and errors of the documents a bulk response rejected.
+    ///
+    /// The `_bulk` API answers 200 even when some documents were rejected,
+    /// flagging them with `errors: true` and a per-item status.
+    fn failed_items(&self, body: &Value) -> Vec<(usize, String)> {
+        if !body.get("errors").and_then(|errors| errors.as_bool()).unwrap_or(false) {
+            return Vec::new();
+        }
+
+        let items = body.get("items").and_then(|items| items.as_array()).map(Vec::as_slice).unwrap_or_default();
+
+        items.iter()
+            .enumerate()
+            .filter_map(|(position, item)| {
+                // Each item is keyed by its action
+                let result = item.as_object()?.values().next()?;
+                let status = result.get("status").and_then(|s| s.as_u64()).unwrap_or(0);
+
+                // Creating a document that already exists means it was delivered before
+                if status == 409 && self.config.action == BulkAction::Create {
+                    return None;
+                }
+
+                let error = result.get("error")?;
+                let kind = error.get("type").and_then(|t| t.as_str()).unwrap_or("error");
+                let reason = error.get("reason").and_then(|r| r.as_str()).unwrap_or("unknown reason");
+                Some((position, format!("record {} ({}): {}: {}", position, status, kind, reason)))
+            })
+            .collect()
+    }
+}
+
+/// Make a rendered name a valid index name: lowercase, without the
+/// characters indices can't contain
+fn sanitize_index(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if matches!(c, '\\' | '/' | '*' | '?' | '"' | '<' | '>' | '|' | ' ' | ',' | '#' | ':') { '_' } else { c })
+        .collect::<String>()
+        .trim_start_matches(['-', '_', '+'])
+        .to_string()
+}
+
+#[async_trait]
+impl Destination for SearchIndexDestination {
+    fn get_type(&self) -> &str {
+        "search_index"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.bulk(std::slice::from_ref(&data)).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // One bulk request per batch
+        self.bulk(&data).await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        let mut request = self.client.get(format!("{}/_cluster/health", self.config.url.trim_end_matches('/')))
+            .timeout(Duration::from_secs(self.config.timeout));
+
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("Authorization", format!("ApiKey {}", api_key));
+        } else if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.as_ref());
+        }
+
+        match request.send().await {
+            Ok(response) => Ok(response.status().is_success()),
+            Err(e) => {
+                tracing::error!("Search cluster is unavailable: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ use reqwest::Client;
 use std::collections::HashMap;
 
 use crate::router::Destination;
+use super::digest::{Digest, DigestSettings};
 
 /// Configuration for the Slack destination
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +27,9 @@ pub struct SlackDestinationConfig {
     pub include_data: bool,
     /// Color for attachment
     pub color: Option<String>,
+    /// How batches are summarized
+    #[serde(default)]
+    pub digest: DigestSettings,
 }
 
 /// Destination that sends notifications to Slack
@@ -145,26 +149,13 @@ impl SlackDestination {
             "ts": chrono::Utc::now().timestamp()
         })]
     }
-}
-
-#[async_trait]
-impl Destination for SlackDestination {
-    fn get_type(&self) -> &str {
-        "slack"
-    }
-    
-    fn get_id(&self) -> &str {
-        &self.id
-    }
     
-    async fn send(&self, data: Value) -> Result<()> {
-        // Format the message
-        let text = self.format_message(&data);
-        
+    /// Post a message to the webhook
+    async fn post(&self, text: String, attachments: Vec<Value>) -> Result<()> {
         // Create payload
         let mut payload = json!({
             "text": text,
-            "attachments": self.create_attachments(&data),
+            "attachments": attachments,
         });
         
         // Add optional fields
@@ -197,24 +188,53 @@ impl Destination for SlackDestination {
         
         Ok(())
     }
+}
+
+#[async_trait]
+impl Destination for SlackDestination {
+    fn get_type(&self) -> &str {
+        "slack"
+    }
+    
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+    
+    async fn send(&self, data: Value) -> Result<()> {
+        // Format the message
+        let text = self.format_message(&data);
+        
+        self.post(text, self.create_attachments(&data)).await
+    }
     
     async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
-        // For Slack, we'll just send a summary message
-        if data.is_empty() {
-            return Ok(());
+        // A single record gets the usual message
+        match data.as_slice() {
+            [] => return Ok(()),
+            [record] => return self.send(record.clone()).await,
+            _ => {},
         }
         
         // Use the first item for the message template
-        let template_data = &data[0];
-        
-        // Add count information
-        let mut enhanced_data = template_data.clone();
-        if let Some(obj) = enhanced_data.as_object_mut() {
+        let mut header_data = data[0].clone();
+        if let Some(obj) = header_data.as_object_mut() {
             obj.insert("batch_count".to_string(), json!(data.len()));
         }
         
-        // Send the summary
-        self.send(enhanced_data).await
+        // Summarize the batch in a table
+        let digest = Digest::new(&data, &self.config.digest);
+        let mut text = format!(
+            "{}\n*{} records*\n```\n{}\n```",
+            self.format_message(&header_data),
+            digest.total,
+            digest.to_text()
+        );
+        
+        if digest.remaining() > 0 {
+            text.push_str(&format!("\n_...and {} more_", digest.remaining()));
+        }
+        
+        self.post(text, Vec::new()).await
     }
     
     async fn check_availability(&self) -> Result<bool> {
@@ -0,0 +1,314 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::net::UdpSocket;
+
+use crate::router::Destination;
+use super::{lookup, render_template, value_to_string};
+
+/// Type of a StatsD metric
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsdMetricType {
+    /// Incremented by the value (1 if there is no value field)
+    #[default]
+    Counter,
+    /// Set to the value
+    Gauge,
+    /// Timing in milliseconds
+    Timer,
+    /// Counts unique values
+    Set,
+}
+
+impl StatsdMetricType {
+    fn suffix(&self) -> &'static str {
+        match self {
+            StatsdMetricType::Counter => "c",
+            StatsdMetricType::Gauge => "g",
+            StatsdMetricType::Timer => "ms",
+            StatsdMetricType::Set => "s",
+        }
+    }
+}
+
+/// Configuration for the StatsD destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsdDestinationConfig {
+    /// Address of the StatsD server
+    #[serde(default = "default_address")]
+    pub address: String,
+    /// Prefix added to every metric name, e.g. "muxly."
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Metrics to emit for each record
+    pub metrics: Vec<StatsdMetricConfig>,
+    /// Append DogStatsD tags (`|#tag:value`)
+    #[serde(default)]
+    pub dogstatsd: bool,
+    /// Tags taken from the record (tag name to field path), for DogStatsD
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    /// Tags sent with every metric, e.g. "env:production", for DogStatsD
+    #[serde(default)]
+    pub constant_tags: Vec<String>,
+    /// Largest UDP packet to send; metrics are packed into packets up to this size
+    #[serde(default = "default_max_packet_size")]
+    pub max_packet_size: usize,
+}
+
+/// Definition of a single metric derived from records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsdMetricConfig {
+    /// Metric name template; `{{field}}` placeholders are replaced with record fields
+    pub name: String,
+    /// Metric type (counter, gauge, timer or set)
+    #[serde(rename = "type", default)]
+    pub metric_type: StatsdMetricType,
+    /// Field (dot-separated path) holding the value; counters count 1 per record if not set
+    #[serde(default)]
+    pub value_field: Option<String>,
+}
+
+fn default_address() -> String {
+    "127.0.0.1:8125".to_string()
+}
+
+fn default_max_packet_size() -> usize {
+    // Fits in a single Ethernet frame
+    1432
+}
+
+/// Destination that emits StatsD (or DogStatsD) metrics over UDP
+pub struct StatsdDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the StatsD destination
+    pub config: StatsdDestinationConfig,
+    /// Socket connected to the server, created on first send and again
+    /// after a send fails
+    socket: Mutex<Option<Arc<UdpSocket>>>,
+}
+
+impl StatsdDestination {
+    /// Create a new StatsD destination
+    pub fn new(id: String, config: StatsdDestinationConfig) -> Self {
+        Self {
+            id,
+            config,
+            socket: Mutex::new(None),
+        }
+    }
+
+    async fn socket(&self) -> Result<Arc<UdpSocket>> {
+        if let Some(socket) = self.lock_socket().as_ref() {
+            return Ok(Arc::clone(socket));
+        }
+
+        let server = tokio::net::lookup_host(&self.config.address).await
+            .map_err(|e| anyhow!("Failed to resolve StatsD server {}: {}", self.config.address, e))?
+            .next()
+            .ok_or_else(|| anyhow!("StatsD server {} has no addresses", self.config.address))?;
+
+        // Bind to the server's address family so IPv6 servers can be reached
+        let local: SocketAddr = if server.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }.parse()?;
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(server).await
+            .map_err(|e| anyhow!("Failed to connect to StatsD server {}: {}", self.config.address, e))?;
+
+        let socket = Arc::new(socket);
+        *self.lock_socket() = Some(Arc::clone(&socket));
+        Ok(socket)
+    }
+
+    fn lock_socket(&self) -> MutexGuard<'_, Option<Arc<UdpSocket>>> {
+        match self.socket.lock() {
+            Ok(socket) => socket,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Lines for all configured metrics of a record
+    fn lines(&self, data: &Value) -> Vec<String> {
+        let tags = self.tags(data);
+        let mut lines = Vec::new();
+
+        for metric in &self.config.metrics {
+            let name = match render_name(&metric.name, data) {
+                Some(name) => format!("{}{}", self.config.prefix.as_deref().unwrap_or(""), name),
+                None => {
+                    tracing::debug!("Record is missing a field of metric name {}", metric.name);
+                    continue;
+                }
+            };
+
+            let value = match (&metric.value_field, metric.metric_type) {
+                (None, StatsdMetricType::Counter) => "1".to_string(),
+                (None, _) => {
+                    tracing::warn!("StatsD metric {} needs a value_field", metric.name);
+                    continue;
+                },
+                (Some(field), metric_type) => match lookup(data, field).and_then(|value| format_value(value, metric_type)) {
+                    Some(value) => value,
+                    None => continue,
+                },
+            };
+
+            // A signed gauge value is a relative change, so a negative value
+            // is set by zeroing the gauge first
+            if metric.metric_type == StatsdMetricType::Gauge && value.starts_with('-') {
+                lines.push(format!("{}:0|g{}", name, tags));
+            }
+
+            lines.push(format!("{}:{}|{}{}", name, value, metric.metric_type.suffix(), tags));
+        }
+
+        lines
+    }
+
+    /// DogStatsD tag suffix for a record (empty unless enabled)
+    fn tags(&self, data: &Value) -> String {
+        if !self.config.dogstatsd {
+            return String::new();
+        }
+
+        let mut record_tags: Vec<String> = self.config.tags.iter()
+            .filter_map(|(tag, path)| {
+                lookup(data, path).map(|value| format!("{}:{}", sanitize_tag(tag), sanitize_tag(&value_to_string(value))))
+            })
+            .collect();
+        record_tags.sort();
+
+        let tags: Vec<String> = self.config.constant_tags.iter()
+            .map(|tag| sanitize_tag(tag))
+            .chain(record_tags)
+            .collect();
+
+        if tags.is_empty() {
+            String::new()
+        } else {
+            format!("|#{}", tags.join(","))
+        }
+    }
+
+    /// Pack lines into newline-separated packets no larger than the limit
+    fn packets(&self, lines: Vec<String>) -> Vec<String> {
+        let mut packets = Vec::new();
+        let mut packet = String::new();
+
+        for line in lines {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > self.config.max_packet_size {
+                packets.push(std::mem::take(&mut packet));
+            }
+
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+
+        if !packet.is_empty() {
+            packets.push(packet);
+        }
+
+        packets
+    }
+
+    async fn emit(&self, data: &[Value]) -> Result<()> {
+        let lines: Vec<String> = data.iter().flat_map(|record| self.lines(record)).collect();
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let socket = self.socket().await?;
+        for packet in self.packets(lines) {
+            if let Err(e) = socket.send(packet.as_bytes()).await {
+                // Resolve the server again on the next send, in case it moved
+                self.lock_socket().take();
+                return Err(anyhow!("Failed to send to StatsD server {}: {}", self.config.address, e));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Render a metric name template, or `None` if a placeholder field is missing
+fn render_name(template: &str, data: &Value) -> Option<String> {
+    render_template(template, data).map(|name| sanitize_name(&name))
+}
+
+/// Replace characters that would break the StatsD line format in a name
+fn sanitize_name(part: &str) -> String {
+    part.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .collect()
+}
+
+/// Replace characters that would break the DogStatsD tag list
+fn sanitize_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| if matches!(c, ',' | '|' | '#' | '\n') { '_' } else { c })
+        .collect()
+}
+
+/// Format a record value for a metric type
+fn format_value(value: &Value, metric_type: StatsdMetricType) -> Option<String> {
+    if metric_type == StatsdMetricType::Set {
+        return match value {
+            Value::Null | Value::Array(_) | Value::Object(_) => None,
+            other => Some(sanitize_tag(&value_to_string(other)).replace(':', "_")),
+        };
+    }
+
+    let number = match value {
+        Value::Number(n) => n.as_f64()?,
+        Value::Bool(b) => *b as i64 as f64,
+        Value::String(s) => s.parse::<f64>().ok()?,
+        _ => return None,
+    };
+
+    if !number.is_finite() {
+        return None;
+    }
+
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        Some(format!("{}", number as i64))
+    } else {
+        Some(format!("{}", number))
+    }
+}
+
+#[async_trait]
+impl Destination for StatsdDestination {
+    fn get_type(&self) -> &str {
+        "statsd"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.emit(std::slice::from_ref(&data)).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        self.emit(&data).await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        // UDP is connectionless; being able to resolve the server is all that can be checked
+        match self.socket().await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::error!("StatsD server is unavailable: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
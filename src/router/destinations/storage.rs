@@ -2,12 +2,19 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use aws_sdk_s3::{Client, config::Region};
-use aws_types::Credentials;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::Client;
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
 use bytes::Bytes;
 use chrono::Utc;
+use std::sync::RwLock;
+use tokio::sync::OnceCell;
 
 use crate::router::Destination;
+use crate::router::destinations::formats::{self, CsvColumns, CsvOptions, ParquetOptions, SourceSchema};
+use super::{lookup, render_template_with, time_placeholder, value_to_string};
 
 /// Configuration for the S3 storage destination
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +22,7 @@ pub struct S3DestinationConfig {
     /// S3 bucket name
     pub bucket: String,
     /// Key prefix (folder) in the bucket
+    #[serde(default)]
     pub key_prefix: String,
     /// AWS region
     pub region: String,
@@ -22,12 +30,46 @@ pub struct S3DestinationConfig {
     pub format: String,
     /// Template for the key (filename)
     pub key_template: String,
-    /// AWS credentials
-    pub credentials: S3Credentials,
+    /// Static AWS credentials (optional; the default credential chain is used otherwise)
+    #[serde(default)]
+    pub credentials: Option<S3Credentials>,
+    /// Use the default credential chain (environment, profile, instance or task role)
+    /// even when static credentials are configured
+    #[serde(default)]
+    pub use_instance_profile: bool,
+    /// Custom endpoint for S3-compatible stores such as MinIO (optional)
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+    /// Use path-style addressing (defaults to true when `endpoint_url` is set)
+    #[serde(default)]
+    pub force_path_style: Option<bool>,
+    /// Objects larger than this many MB are uploaded in parts
+    #[serde(default = "default_multipart_threshold_mb")]
+    pub multipart_threshold_mb: u64,
+    /// Size of each part of a multipart upload in MB (at least 5)
+    #[serde(default = "default_multipart_part_size_mb")]
+    pub multipart_part_size_mb: u64,
     /// Content type to use
     pub content_type: Option<String>,
+    /// CSV serialization options
+    #[serde(default)]
+    pub csv: CsvOptions,
+    /// Parquet serialization options
+    #[serde(default)]
+    pub parquet: ParquetOptions,
 }
 
+fn default_multipart_threshold_mb() -> u64 {
+    16
+}
+
+fn default_multipart_part_size_mb() -> u64 {
+    8
+}
+
+/// Smallest part size S3 accepts for all but the last part
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
 /// S3 authentication credentials
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct S3Credentials {
@@ -45,8 +87,12 @@ pub struct S3Destination {
     pub id: String,
     /// Configuration for the S3 destination
     pub config: S3DestinationConfig,
-    /// S3 client
-    client: Option<Client>,
+    /// S3 client, created on first use
+    client: OnceCell<Client>,
+    /// CSV column order shared by all objects written by this destination
+    csv_columns: CsvColumns,
+    /// Schema reported by the source connector, if any
+    source_schema: RwLock<Option<SourceSchema>>,
 }
 
 impl S3Destination {
@@ -55,45 +101,92 @@ impl S3Destination {
         Self { 
             id, 
             config,
-            client: None,
+            client: OnceCell::new(),
+            csv_columns: CsvColumns::default(),
+            source_schema: RwLock::new(None),
         }
     }
     
-    /// Initialize the S3 client
-    pub async fn initialize(&mut self) -> Result<()> {
-        let region = Region::new(self.config.region.clone());
+    /// Get the S3 client, building it on first use
+    async fn client(&self) -> Result<&Client> {
+        self.client.get_or_try_init(|| self.build_client()).await
+    }
+    
+    /// Build the S3 client from the configuration.
+    ///
+    /// Static credentials are used when configured, unless
+    /// `use_instance_profile` is set; otherwise the default credential chain
+    /// resolves them from the environment, shared config files or the
+    /// instance/task role.
+    async fn build_client(&self) -> Result<Client> {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(self.config.region.clone()));
+        
+        match &self.config.credentials {
+            Some(credentials) if !self.config.use_instance_profile => {
+                loader = loader.credentials_provider(Credentials::new(
+                    &credentials.access_key_id,
+                    &credentials.secret_access_key,
+                    credentials.session_token.clone(),
+                    None,
+                    "S3Destination",
+                ));
+            },
+            _ => {
+                tracing::debug!("Using the default AWS credential chain for bucket {}", self.config.bucket);
+            },
+        }
         
-        let credentials = Credentials::new(
-            &self.config.credentials.access_key_id,
-            &self.config.credentials.secret_access_key,
-            self.config.credentials.session_token.as_deref(),
-            None,
-            "S3Destination",
-        );
+        let shared_config = loader.load().await;
+        let mut builder = aws_sdk_s3::config::Builder::from(&shared_config);
         
-        let config = aws_sdk_s3::Config::builder()
-            .region(region)
-            .credentials_provider(credentials)
-            .build();
+        if let Some(endpoint_url) = &self.config.endpoint_url {
+            builder = builder.endpoint_url(endpoint_url);
+        }
         
-        let client = Client::from_conf(config);
-        self.client = Some(client);
+        let path_style = self.config.force_path_style
+            .unwrap_or(self.config.endpoint_url.is_some());
+        builder = builder.force_path_style(path_style);
         
-        Ok(())
+        Ok(Client::from_conf(builder.build()))
     }
     
-    /// Generate a key (filename) based on the template
+    /// Generate a key (filename) based on the template.
+    ///
+    /// Supports `{{date}}`, `{{year}}`, `{{month}}`, `{{day}}`, `{{hour}}`,
+    /// `{{minute}}`, `{{timestamp}}`, `{{uuid}}` and any record field, e.g.
+    /// `{{connector_id}}`, so Hive-style partitions like
+    /// `dt={{date}}/hour={{hour}}` can be built. Missing fields are rendered
+    /// as `unknown`. Templates without `{{uuid}}` get a random suffix, so
+    /// objects written in the same second don't overwrite each other.
     fn generate_key(&self, data: &Value) -> String {
-        let mut key = self.config.key_template.clone();
-        
-        // Replace {{date}} with the current date
+        self.render_key(data, "")
+    }
+    
+    /// Generate the key for a batch: the regular key with `_batch` before the suffix and extension
+    fn generate_batch_key(&self, data: &Value) -> String {
+        self.render_key(data, "_batch")
+    }
+    
+    /// Render the key template, adding `label`, the unique suffix and the extension
+    fn render_key(&self, data: &Value, label: &str) -> String {
         let now = Utc::now();
-        key = key.replace("{{date}}", &now.format("%Y-%m-%d").to_string());
-        key = key.replace("{{timestamp}}", &now.format("%Y%m%d_%H%M%S").to_string());
+        let mut unique = false;
+        let rendered = render_template_with(&self.config.key_template, |name| match name {
+            "uuid" => {
+                unique = true;
+                Some(uuid::Uuid::new_v4().to_string())
+            },
+            name => time_placeholder(name, now).or_else(|| match lookup(data, name) {
+                Some(Value::Null) | None => Some("unknown".to_string()),
+                Some(value) => Some(value_to_string(value)),
+            }),
+        }).unwrap_or_else(|| self.config.key_template.clone());
         
-        // Replace {{connector_id}} with the connector ID if present
-        if let Some(connector_id) = data.get("connector_id").and_then(|v| v.as_str()) {
-            key = key.replace("{{connector_id}}", connector_id);
+        let extension = format!(".{}", self.config.format);
+        let mut key = format!("{}{}", rendered.strip_suffix(&extension).unwrap_or(&rendered), label);
+        if !unique {
+            key = format!("{}-{}", key, uuid::Uuid::new_v4().simple());
         }
         
         // Add prefix if not included in template
@@ -105,12 +198,114 @@ impl S3Destination {
             }
         }
         
-        // Add extension if not present
-        if !key.ends_with(&format!(".{}", self.config.format)) {
-            key = format!("{}.{}", key, self.config.format);
+        format!("{}{}", key, extension)
+    }
+    
+    /// Size above which objects are uploaded in parts
+    fn multipart_threshold(&self) -> usize {
+        (self.config.multipart_threshold_mb as usize * 1024 * 1024).max(MIN_PART_SIZE)
+    }
+    
+    /// Size of each part of a multipart upload
+    fn part_size(&self) -> usize {
+        (self.config.multipart_part_size_mb as usize * 1024 * 1024).max(MIN_PART_SIZE)
+    }
+    
+    /// Upload an object, in parts if it is larger than the multipart threshold
+    async fn upload(&self, key: &str, body: Bytes) -> Result<()> {
+        let client = self.client().await?;
+        
+        if body.len() > self.multipart_threshold() {
+            return self.upload_multipart(client, key, body).await;
         }
         
-        key
+        client.put_object()
+            .bucket(&self.config.bucket)
+            .key(key)
+            .body(ByteStream::from(body))
+            .content_type(self.get_content_type())
+            .send()
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Upload an object with a multipart upload, aborting it on failure
+    async fn upload_multipart(&self, client: &Client, key: &str, body: Bytes) -> Result<()> {
+        let upload = client.create_multipart_upload()
+            .bucket(&self.config.bucket)
+            .key(key)
+            .content_type(self.get_content_type())
+            .send()
+            .await?;
+        
+        let upload_id = upload.upload_id()
+            .ok_or_else(|| anyhow::anyhow!("S3 returned no upload ID for {}", key))?
+            .to_string();
+        
+        let parts = match self.upload_parts(client, key, &upload_id, body).await {
+            Ok(parts) => parts,
+            Err(e) => {
+                // Abort so the uploaded parts don't linger in the bucket
+                if let Err(abort_error) = client.abort_multipart_upload()
+                    .bucket(&self.config.bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await
+                {
+                    tracing::warn!("Failed to abort multipart upload of {}: {}", key, abort_error);
+                }
+                return Err(e);
+            }
+        };
+        
+        client.complete_multipart_upload()
+            .bucket(&self.config.bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build()
+            )
+            .send()
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Upload the parts of a multipart upload in order
+    async fn upload_parts(&self, client: &Client, key: &str, upload_id: &str, body: Bytes) -> Result<Vec<CompletedPart>> {
+        let part_size = self.part_size();
+        let mut parts = Vec::new();
+        let mut offset = 0;
+        
+        while offset < body.len() {
+            let end = (offset + part_size).min(body.len());
+            let part_number = parts.len() as i32 + 1;
+            
+            let output = client.upload_part()
+                .bucket(&self.config.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(body.slice(offset..end)))
+                .send()
+                .await?;
+            
+            parts.push(
+                CompletedPart::builder()
+                    .set_e_tag(output.e_tag().map(str::to_string))
+                    .part_number(part_number)
+                    .build()
+            );
+            
+            offset = end;
+        }
+        
+        tracing::debug!("Uploaded {} in {} parts", key, parts.len());
+        Ok(parts)
     }
     
     /// Determine content type based on format
@@ -119,7 +314,7 @@ impl S3Destination {
             match self.config.format.as_str() {
                 "json" => "application/json".to_string(),
                 "csv" => "text/csv".to_string(),
-                "parquet" => "application/octet-stream".to_string(),
+                "parquet" => "application/vnd.apache.parquet".to_string(),
                 _ => "application/octet-stream".to_string(),
             }
         })
@@ -132,22 +327,31 @@ impl S3Destination {
                 let json = serde_json::to_string_pretty(data)?;
                 Ok(Bytes::from(json))
             },
-            "csv" => {
-                // In a real implementation, this would convert the JSON to CSV
-                // For now, just convert to string
-                let json = serde_json::to_string(data)?;
-                Ok(Bytes::from(json))
-            },
-            "parquet" => {
-                // In a real implementation, this would convert the JSON to Parquet
-                // For now, just convert to string
-                let json = serde_json::to_string(data)?;
-                Ok(Bytes::from(json))
-            },
+            "csv" => self.serialize_csv(&formats::records_of(data)),
+            "parquet" => self.serialize_parquet(&formats::records_of(data)),
             _ => Err(anyhow::anyhow!("Unsupported format: {}", self.config.format)),
         }
     }
     
+    /// Serialize records as Parquet, validated against the source schema
+    fn serialize_parquet(&self, records: &[&Value]) -> Result<Bytes> {
+        let schema = self.source_schema.read()
+            .map_err(|_| anyhow::anyhow!("Source schema lock poisoned"))?;
+        let parquet = formats::records_to_parquet(&self.config.parquet, records, schema.as_ref())?;
+        Ok(Bytes::from(parquet))
+    }
+    
+    /// Serialize records as CSV, with a header row
+    fn serialize_csv(&self, records: &[&Value]) -> Result<Bytes> {
+        let rows: Vec<_> = records.iter()
+            .map(|record| formats::flatten_record(record, &self.config.csv.flatten_separator))
+            .collect();
+        
+        let columns = self.csv_columns.extend(&rows);
+        let csv = formats::encode_csv(&self.config.csv, &columns, &rows, true)?;
+        Ok(Bytes::from(csv))
+    }
+    
     /// Serialize batch data
     fn serialize_batch(&self, data: &[Value]) -> Result<Bytes> {
         match self.config.format.as_str() {
@@ -157,20 +361,16 @@ impl S3Destination {
                 Ok(Bytes::from(json))
             },
             "csv" => {
-                // In a real implementation, this would convert the JSON to CSV
-                // For now, just convert to JSONL (JSON Lines)
-                let mut result = Vec::new();
-                for item in data {
-                    let line = serde_json::to_string(item)?;
-                    result.push(line);
-                }
-                Ok(Bytes::from(result.join("\n")))
+                let records: Vec<&Value> = data.iter()
+                    .flat_map(formats::records_of)
+                    .collect();
+                self.serialize_csv(&records)
             },
             "parquet" => {
-                // In a real implementation, this would convert the JSON to Parquet
-                // For now, just convert to string
-                let json = serde_json::to_string(data)?;
-                Ok(Bytes::from(json))
+                let records: Vec<&Value> = data.iter()
+                    .flat_map(formats::records_of)
+                    .collect();
+                self.serialize_parquet(&records)
             },
             _ => Err(anyhow::anyhow!("Unsupported format: {}", self.config.format)),
         }
@@ -188,26 +388,14 @@ impl Destination for S3Destination {
     }
     
     async fn send(&self, data: Value) -> Result<()> {
-        if let Some(client) = &self.client {
-            // Generate key
-            let key = self.generate_key(&data);
-            
-            // Serialize data
-            let body = self.serialize_data(&data)?;
-            
-            // Upload to S3
-            client.put_object()
-                .bucket(&self.config.bucket)
-                .key(&key)
-                .body(body.into())
-                .content_type(self.get_content_type())
-                .send()
-                .await?;
-        } else {
-            return Err(anyhow::anyhow!("S3 client not initialized"));
-        }
+        // Generate key
+        let key = self.generate_key(&data);
         
-        Ok(())
+        // Serialize data
+        let body = self.serialize_data(&data)?;
+        
+        // Upload to S3
+        self.upload(&key, body).await
     }
     
     async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
@@ -215,47 +403,44 @@ impl Destination for S3Destination {
             return Ok(());
         }
         
-        if let Some(client) = &self.client {
-            // Use the first item to generate a key with a batch indicator
-            let mut key = self.generate_key(&data[0]);
-            key = key.replace(".", "_batch.");
-            
-            // Serialize batch data
-            let body = self.serialize_batch(&data)?;
-            
-            // Upload to S3
-            client.put_object()
-                .bucket(&self.config.bucket)
-                .key(&key)
-                .body(body.into())
-                .content_type(self.get_content_type())
-                .send()
-                .await?;
-        } else {
-            return Err(anyhow::anyhow!("S3 client not initialized"));
-        }
+        // Use the first item to generate a key with a batch indicator
+        let key = self.generate_batch_key(&data[0]);
         
-        Ok(())
+        // Serialize batch data
+        let body = self.serialize_batch(&data)?;
+        
+        // Upload to S3
+        self.upload(&key, body).await
+    }
+    
+    fn set_source_metadata(&self, metadata: &Value) {
+        if let Ok(mut schema) = self.source_schema.write() {
+            *schema = SourceSchema::from_metadata(metadata);
+        }
     }
     
     async fn check_availability(&self) -> Result<bool> {
-        if let Some(client) = &self.client {
-            // Check if we can list the bucket
-            match client.list_objects_v2()
-                .bucket(&self.config.bucket)
-                .prefix(&self.config.key_prefix)
-                .max_keys(1)
-                .send()
-                .await
-            {
-                Ok(_) => Ok(true),
-                Err(e) => {
-                    tracing::error!("S3 connection check failed: {}", e);
-                    Ok(false)
-                }
+        let client = match self.client().await {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Failed to create S3 client: {}", e);
+                return Ok(false);
+            }
+        };
+        
+        // Check if we can list the bucket
+        match client.list_objects_v2()
+            .bucket(&self.config.bucket)
+            .prefix(&self.config.key_prefix)
+            .max_keys(1)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                tracing::error!("S3 connection check failed: {}", e);
+                Ok(false)
             }
-        } else {
-            Ok(false)
         }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,349 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::config::models::router::{default_max_concurrency, default_timeout, WebhookEndpoint};
+use crate::router::{Destination, PartialDelivery};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the payload signature
+pub const SIGNATURE_HEADER: &str = "X-Muxly-Signature";
+/// Header carrying the Unix timestamp that was signed
+pub const TIMESTAMP_HEADER: &str = "X-Muxly-Timestamp";
+/// Header carrying the event type of the payload
+pub const EVENT_HEADER: &str = "X-Muxly-Event";
+/// Header carrying a unique delivery ID
+pub const DELIVERY_HEADER: &str = "X-Muxly-Delivery";
+
+/// Most records whose accepting endpoints are remembered while other
+/// endpoints fail them
+const MAX_PENDING_RECORDS: usize = 10_000;
+
+/// How a batch of records is posted to an endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookBatchFormat {
+    /// One request per record, with the record as the body, as for a
+    /// single record
+    #[default]
+    Single,
+    /// One request per endpoint, with the records it accepts as a JSON array
+    Array,
+}
+
+/// Configuration for the webhook destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDestinationConfig {
+    /// Endpoints to deliver to
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Additional headers sent with every request
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Maximum number of concurrent requests
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Record field holding the event type used for `event_types` filtering
+    #[serde(default = "default_event_type_field")]
+    pub event_type_field: String,
+    /// How batches are posted
+    #[serde(default)]
+    pub batch_format: WebhookBatchFormat,
+}
+
+fn default_event_type_field() -> String {
+    "event_type".to_string()
+}
+
+/// Compute the signature for a payload.
+///
+/// The signature is the hex-encoded HMAC-SHA256 of `"{timestamp}.{body}"`
+/// keyed with the endpoint secret, prefixed with `sha256=`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Verify a signature produced by [`sign_payload`]
+#[cfg(test)]
+pub fn verify_signature(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let hex_signature = match signature.strip_prefix("sha256=") {
+        Some(hex_signature) => hex_signature,
+        None => return false,
+    };
+
+    let expected = match hex::decode(hex_signature) {
+        Ok(expected) => expected,
+        Err(_) => return false,
+    };
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    // Constant-time comparison
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Destination that delivers records to HTTP endpoints.
+///
+/// A record counts as delivered once every endpoint that accepts it has
+/// answered with a success. When only some of them did, the endpoints that
+/// did are remembered in memory, so a retry of the record only posts to the
+/// others.
+pub struct WebhookDestination {
+    /// Unique identifier
+    pub id: String,
+    /// Configuration for the webhook destination
+    pub config: WebhookDestinationConfig,
+    /// HTTP client
+    client: Client,
+    /// Limits the number of in-flight requests
+    semaphore: Semaphore,
+    /// Endpoints that accepted records other endpoints failed, by digest
+    /// of the record
+    pending: Mutex<HashMap<String, PendingRecord>>,
+}
+
+/// Endpoint URLs that accepted a record that isn't fully delivered yet
+struct PendingRecord {
+    endpoints: HashSet<String>,
+    since: Instant,
+}
+
+impl WebhookDestination {
+    /// Create a new webhook destination
+    pub fn new(id: String, config: WebhookDestinationConfig) -> Self {
+        let permits = config.max_concurrency.max(1);
+
+        Self {
+            id,
+            config,
+            client: Client::new(),
+            semaphore: Semaphore::new(permits),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, PendingRecord>> {
+        match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Digest identifying a record across retries
+    fn digest(record: &Value) -> String {
+        hex::encode(Sha256::digest(record.to_string().as_bytes()))
+    }
+
+    /// Get the event type of a record
+    fn event_type<'a>(&self, data: &'a Value) -> Option<&'a str> {
+        data.get(&self.config.event_type_field).and_then(Value::as_str)
+    }
+
+    /// Check whether an endpoint accepts a record
+    fn accepts(endpoint: &WebhookEndpoint, event_type: Option<&str>) -> bool {
+        if endpoint.event_types.is_empty() {
+            return true;
+        }
+
+        match event_type {
+            Some(event_type) => endpoint.event_types.iter()
+                .any(|t| t == "*" || t == event_type),
+            None => false,
+        }
+    }
+
+    /// Deliver a payload to a single endpoint
+    async fn deliver(&self, endpoint: &WebhookEndpoint, payload: &Value, event_type: Option<&str>) -> Result<()> {
+        let _permit = self.semaphore.acquire().await
+            .map_err(|e| anyhow!("Webhook destination closed: {}", e))?;
+
+        let method = Method::from_bytes(endpoint.method.to_uppercase().as_bytes())
+            .map_err(|_| anyhow!("Invalid HTTP method: {}", endpoint.method))?;
+
+        let body = serde_json::to_vec(payload)?;
+        let timestamp = Utc::now().timestamp();
+
+        let mut request = self.client.request(method, &endpoint.url)
+            .timeout(Duration::from_secs(self.config.timeout))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(DELIVERY_HEADER, uuid::Uuid::new_v4().to_string());
+
+        for (name, value) in &self.config.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        if let Some(event_type) = event_type {
+            request = request.header(EVENT_HEADER, event_type);
+        }
+
+        if let Some(authorization) = &endpoint.authorization {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization.as_str());
+        }
+
+        if let Some(secret) = &endpoint.secret {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, timestamp, &body));
+        }
+
+        let response = request.body(body).send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("returned {}: {}", status, error_text));
+        }
+
+        Ok(())
+    }
+
+    /// Post records to every endpoint that accepts them and hasn't yet.
+    ///
+    /// Fails with a [`PartialDelivery`] naming the records some endpoint
+    /// failed, or with a plain error if every request failed. The error
+    /// lists each failed endpoint.
+    async fn post(&self, data: &[Value], format: WebhookBatchFormat) -> Result<()> {
+        let digests: Vec<String> = data.iter().map(Self::digest).collect();
+        let mut accepted: Vec<HashSet<String>> = {
+            let pending = self.lock();
+            digests.iter()
+                .map(|digest| pending.get(digest).map(|record| record.endpoints.clone()).unwrap_or_default())
+                .collect()
+        };
+
+        // The positions of the records each request carries
+        let mut requests: Vec<(&WebhookEndpoint, Vec<usize>)> = Vec::new();
+        for endpoint in &self.config.endpoints {
+            let due: Vec<usize> = (0..data.len())
+                .filter(|&i| Self::accepts(endpoint, self.event_type(&data[i])) && !accepted[i].contains(&endpoint.url))
+                .collect();
+
+            match format {
+                _ if due.is_empty() => {},
+                WebhookBatchFormat::Single => requests.extend(due.into_iter().map(|i| (endpoint, vec![i]))),
+                WebhookBatchFormat::Array => requests.push((endpoint, due)),
+            }
+        }
+
+        let results = join_all(requests.iter().map(|(endpoint, records)| async move {
+            match format {
+                WebhookBatchFormat::Single => {
+                    let record = &data[records[0]];
+                    self.deliver(endpoint, record, self.event_type(record)).await
+                },
+                WebhookBatchFormat::Array => {
+                    let payload = Value::Array(records.iter().map(|&i| data[i].clone()).collect());
+                    self.deliver(endpoint, &payload, None).await
+                },
+            }
+        })).await;
+
+        let mut failed = BTreeSet::new();
+        let mut errors = Vec::new();
+        for ((endpoint, records), result) in requests.iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    for &i in records {
+                        accepted[i].insert(endpoint.url.clone());
+                    }
+                },
+                Err(e) => {
+                    failed.extend(records.iter().copied());
+                    errors.push(format!("{}: {}", endpoint.url, e));
+                },
+            }
+        }
+
+        self.remember(digests, accepted, &failed);
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let message = format!("Webhook delivery failed for {}", errors.join("; "));
+        if errors.len() == requests.len() {
+            return Err(anyhow!(message));
+        }
+
+        Err(PartialDelivery { failed: failed.into_iter().collect(), message }.into())
+    }
+
+    /// Remember the endpoints that accepted each failed record, and forget
+    /// the records that are now fully delivered
+    fn remember(&self, digests: Vec<String>, accepted: Vec<HashSet<String>>, failed: &BTreeSet<usize>) {
+        let mut pending = self.lock();
+
+        for (i, (digest, endpoints)) in digests.into_iter().zip(accepted).enumerate() {
+            if !failed.contains(&i) || endpoints.is_empty() {
+                pending.remove(&digest);
+                continue;
+            }
+
+            let since = pending.get(&digest).map_or_else(Instant::now, |record| record.since);
+            pending.insert(digest, PendingRecord { endpoints, since });
+        }
+
+        // Records that are never retried would otherwise be kept forever
+        while pending.len() > MAX_PENDING_RECORDS {
+            let oldest = pending.iter()
+                .min_by_key(|(_, record)| record.since)
+                .map(|(digest, _)| digest.clone());
+            match oldest {
+                Some(digest) => pending.remove(&digest),
+                None => break,
+            };
+        }
+    }
+}
+
+#[async_trait]
+impl Destination for WebhookDestination {
+    fn get_type(&self) -> &str {
+        "webhook"
+    }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send(&self, data: Value) -> Result<()> {
+        self.post(std::slice::from_ref(&data), WebhookBatchFormat::Single).await
+    }
+
+    async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+        self.post(&data, self.config.batch_format).await
+    }
+
+    async fn check_availability(&self) -> Result<bool> {
+        for endpoint in &self.config.endpoints {
+            if url::Url::parse(&endpoint.url).is_err() {
+                tracing::error!("Invalid webhook URL: {}", endpoint.url);
+                return Ok(false);
+            }
+        }
+
+        Ok(!self.config.endpoints.is_empty())
+    }
+}
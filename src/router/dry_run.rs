@@ -0,0 +1,101 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::router::{ConditionTrace, TransformationStep, apply_transformation};
+
+/// What a route would do with a record, without sending anything
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunReport {
+    /// Route the record was run through
+    pub route_id: String,
+    /// The record as given
+    pub input: Value,
+    /// Output of each of the route's transformations
+    pub transformations: Vec<TransformationTrace>,
+    /// How the route's condition evaluated, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<ConditionTrace>,
+    /// Whether the record passed the route's transformations and condition
+    pub route_matched: bool,
+    /// How each enabled rule evaluated, in priority order
+    pub rules: Vec<RuleTrace>,
+    /// Destinations that would receive the record
+    pub destinations: Vec<String>,
+    /// Why the record wouldn't be routed at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Output of a single transformation step
+#[derive(Debug, Clone, Serialize)]
+pub struct TransformationTrace {
+    /// Type of transformation
+    pub transformation_type: String,
+    /// The record after this step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    /// Why the step failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How a routing rule evaluated
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTrace {
+    /// ID of the rule
+    pub rule_id: String,
+    /// Priority of the rule
+    pub priority: i32,
+    /// Whether the rule matched
+    pub matched: bool,
+    /// How the rule's condition evaluated, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<ConditionTrace>,
+    /// Whether the record is in the rule's sample, if the rule samples
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampled: Option<bool>,
+    /// Output of each of the rule's transformations, if it matched
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transformations: Vec<TransformationTrace>,
+    /// Destinations the rule would send the record to
+    pub destinations: Vec<String>,
+    /// Whether evaluation stops after this rule
+    pub stopped: bool,
+    /// Why the rule couldn't be evaluated or applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Apply transformations one step at a time, recording each step's output.
+///
+/// Returns the traces and the final record, or `None` if a step failed.
+pub fn trace_transformations(
+    steps: &[TransformationStep],
+    data: Value,
+) -> (Vec<TransformationTrace>, Option<Value>) {
+    let mut traces = Vec::new();
+    let mut current = data;
+
+    for step in steps {
+        match apply_transformation(step, current) {
+            Ok(output) => {
+                traces.push(TransformationTrace {
+                    transformation_type: step.transformation_type.clone(),
+                    output: Some(output.clone()),
+                    error: None,
+                });
+                current = output;
+            },
+            Err(e) => {
+                traces.push(TransformationTrace {
+                    transformation_type: step.transformation_type.clone(),
+                    output: None,
+                    error: Some(e.to_string()),
+                });
+                return (traces, None);
+            }
+        }
+    }
+
+    (traces, Some(current))
+}
@@ -0,0 +1,197 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::models::router::{GlobalRouterConfig, RouterConfig};
+use crate::router::{
+    BreakerRegistry, BreakerSettings, DeadLetterStore, DedupStore, DeliveryMetrics, DeliveryQueue, DestinationDefaults, DestinationInUse,
+    DestinationRegistry, FanOutSettings, QueueSettings, ReceiptStore, Route, RouteDefinition, RouteRegistry,
+};
+use crate::storage::{DatabasePool, DestinationRecord};
+
+/// The router's shared stores and the routes started from configuration.
+///
+/// Built once at startup; the API reaches the routes and destinations
+/// through its registries.
+pub struct RouterIntegration {
+    /// Shared destinations, referenced by ID from routes and rules
+    pub destinations: Arc<DestinationRegistry>,
+    /// Running routes, by ID
+    pub routes: Arc<RouteRegistry>,
+    /// Durable outbox that queued routes deliver through
+    pub queue: Arc<DeliveryQueue>,
+    /// Store for records that couldn't be routed
+    pub dead_letters: Arc<DeadLetterStore>,
+    /// Store for the keys of records already seen
+    pub dedup: Arc<DedupStore>,
+    /// Circuit breakers of the routes' destinations, reported by the health API
+    pub breakers: Arc<BreakerRegistry>,
+    /// Delivery metrics of the routes and the queue, served on the
+    /// configured metrics path
+    pub metrics: Arc<DeliveryMetrics>,
+    /// Store of delivery receipts of the routes and the queue
+    pub receipts: Arc<ReceiptStore>,
+    /// Global router settings
+    global: GlobalRouterConfig,
+    /// Held while a shared destination changes, so the check for routes
+    /// using it and the change itself can't interleave with another change
+    destination_changes: Mutex<()>,
+}
+
+impl RouterIntegration {
+    /// Build the router's stores and start every enabled route.
+    ///
+    /// Records a previous run left in flight are returned to the delivery
+    /// queue before any queue worker starts, so they are delivered again.
+    /// A route that fails to build is logged and skipped rather than
+    /// keeping the others from starting.
+    pub async fn start(pool: Arc<DatabasePool>, config: &RouterConfig) -> Result<Self> {
+        let destinations = Arc::new(DestinationRegistry::load(Arc::clone(&pool), DestinationDefaults::from(config)).await?);
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+        let dedup = Arc::new(DedupStore::new(Arc::clone(&pool)));
+        let metrics = Arc::new(DeliveryMetrics::new());
+        let receipts = Arc::new(ReceiptStore::new(Arc::clone(&pool)));
+
+        let queue = Arc::new(
+            DeliveryQueue::new(Arc::clone(&pool), QueueSettings::from(&config.global))
+                .with_dead_letters(Arc::clone(&dead_letters))
+                .with_metrics(Arc::clone(&metrics))
+                .with_receipts(Arc::clone(&receipts)),
+        );
+        queue.recover().await?;
+
+        let integration = Self {
+            destinations,
+            routes: Arc::new(RouteRegistry::new()),
+            queue,
+            dead_letters,
+            dedup,
+            breakers: Arc::new(BreakerRegistry::new(BreakerSettings::from(&config.global))),
+            metrics,
+            receipts,
+            global: config.global.clone(),
+            destination_changes: Mutex::new(()),
+        };
+
+        if !config.global.delivery_queue {
+            tracing::info!("Delivery queue is disabled; failed deliveries become dead letters without being retried");
+        }
+
+        if !config.global.enabled {
+            tracing::info!("Routing is disabled; not starting {} configured routes", config.routes.len());
+            return Ok(integration);
+        }
+
+        for definition in config.routes.iter().filter(|definition| definition.config.enabled) {
+            match integration.build_route(definition) {
+                Ok(route) => {
+                    integration.routes.insert(route);
+                    tracing::info!("Started route {}", definition.config.id);
+                },
+                Err(e) => tracing::error!("Failed to start route {}: {}", definition.config.id, e),
+            }
+        }
+
+        Ok(integration)
+    }
+
+    /// Build a route with the shared stores attached
+    fn build_route(&self, definition: &RouteDefinition) -> Result<Route> {
+        let mut route = Route::from_registry(definition.config.clone(), definition.rules.clone(), &self.destinations)?
+            .with_fan_out(FanOutSettings::from(&self.global))
+            .with_breakers(&self.breakers)
+            .with_dead_letters(Arc::clone(&self.dead_letters))
+            .with_dedup(Arc::clone(&self.dedup))
+            .with_metrics(Arc::clone(&self.metrics))
+            .with_receipts(Arc::clone(&self.receipts));
+
+        if self.global.delivery_queue {
+            route = route.with_queue(Arc::clone(&self.queue));
+        }
+
+        Ok(route)
+    }
+
+    /// Add or replace a shared destination, and rebuild the running routes
+    /// that use it so they deliver to the new instance.
+    ///
+    /// Disabling a destination that routes use fails with
+    /// [`DestinationInUse`] without changing anything. The routes' previous
+    /// instances are flushed once the rebuilt routes have taken over.
+    pub async fn put_destination(&self, record: DestinationRecord) -> Result<()> {
+        let _change = self.destination_changes.lock().await;
+        let users = self.routes_using(&record.id);
+        if !record.enabled && !users.is_empty() {
+            return Err(Self::in_use(&record.id, &users).into());
+        }
+
+        self.destinations.register(record).await?;
+
+        for route in users {
+            self.rebuild_route(route).await;
+        }
+
+        Ok(())
+    }
+
+    /// Remove a shared destination; returns whether it existed.
+    ///
+    /// Fails with [`DestinationInUse`] while running routes use it.
+    pub async fn remove_destination(&self, id: &str) -> Result<bool> {
+        let _change = self.destination_changes.lock().await;
+        let users = self.routes_using(id);
+        if !users.is_empty() {
+            return Err(Self::in_use(id, &users).into());
+        }
+
+        self.destinations.remove(id).await
+    }
+
+    /// Running routes that deliver to a shared destination
+    fn routes_using(&self, id: &str) -> Vec<Arc<Route>> {
+        match self.destinations.get(id) {
+            Some(destination) => self.routes.list().into_iter().filter(|route| route.uses(&destination)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn in_use(id: &str, routes: &[Arc<Route>]) -> DestinationInUse {
+        let mut routes: Vec<String> = routes.iter().map(|route| route.config.id.clone()).collect();
+        routes.sort();
+        DestinationInUse { id: id.to_string(), routes }
+    }
+
+    /// Replace a running route with one built from the same definition
+    async fn rebuild_route(&self, route: Arc<Route>) {
+        let definition = RouteDefinition {
+            config: route.config.clone(),
+            rules: route.rules.clone(),
+        };
+
+        match self.build_route(&definition) {
+            Ok(rebuilt) => {
+                self.routes.insert(rebuilt);
+                // Deliver what the previous instances are holding back
+                route.flush().await;
+                tracing::info!("Rebuilt route {}", definition.config.id);
+            },
+            Err(e) => tracing::error!("Failed to rebuild route {}, keeping it as it was: {}", definition.config.id, e),
+        }
+    }
+
+    /// Stop the delivery queue workers, send the rate limit summaries
+    /// routes are holding back and deliver the records batching
+    /// destinations are holding.
+    ///
+    /// Records being delivered from the queue at that moment are retried on
+    /// the next start.
+    pub async fn stop(&self) -> Result<()> {
+        self.queue.stop();
+
+        for route in self.routes.list() {
+            route.flush().await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::router::{Destination, PartialDelivery};
+use crate::router::destinations::{MetricKind, MetricRegistry};
+
+/// Upper bounds of the delivery latency histogram, in seconds
+const DURATION_BUCKETS: &[f64] = &[0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Counters and latency histograms of deliveries to destinations, by route
+/// and destination, served on Muxly's `/metrics` endpoint
+#[derive(Debug, Default)]
+pub struct DeliveryMetrics {
+    registry: MetricRegistry,
+}
+
+impl DeliveryMetrics {
+    /// Create an empty set of metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a delivery of `records` records to a destination.
+    ///
+    /// A partial delivery counts its accepted records as delivered and its
+    /// rejected ones as failed.
+    pub fn record(
+        &self,
+        route_id: &str,
+        destination: &dyn Destination,
+        records: usize,
+        duration: Duration,
+        result: &Result<()>,
+    ) {
+        let labels = vec![
+            ("destination".to_string(), destination.get_id().to_string()),
+            ("destination_type".to_string(), destination.get_type().to_string()),
+            ("route".to_string(), route_id.to_string()),
+        ];
+
+        let failed = PartialDelivery::failed_count(records, result);
+        let status = if result.is_ok() { "success" } else { "failure" };
+
+        let mut delivery_labels = labels.clone();
+        delivery_labels.push(("status".to_string(), status.to_string()));
+
+        let mut observations = vec![(
+            "muxly_router_deliveries_total",
+            MetricKind::Counter,
+            "Deliveries to a destination, by outcome",
+            delivery_labels,
+            1.0,
+        )];
+        if result.is_ok() || failed < records {
+            observations.push((
+                "muxly_router_records_delivered_total",
+                MetricKind::Counter,
+                "Records delivered to a destination",
+                labels.clone(),
+                (records - failed) as f64,
+            ));
+        }
+        if result.is_err() {
+            observations.push((
+                "muxly_router_records_failed_total",
+                MetricKind::Counter,
+                "Records a destination failed to accept",
+                labels.clone(),
+                failed as f64,
+            ));
+        }
+        observations.push((
+            "muxly_router_delivery_duration_seconds",
+            MetricKind::Histogram,
+            "Time taken by a destination to accept a delivery",
+            labels,
+            duration.as_secs_f64(),
+        ));
+
+        for (name, kind, help, labels, value) in observations {
+            if let Err(e) = self.registry.observe(name, kind, help, DURATION_BUCKETS, labels, value) {
+                tracing::warn!("Failed to record metric {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        self.registry.render()
+    }
+}
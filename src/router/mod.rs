@@ -1,18 +1,86 @@
+mod batching;
+mod circuit_breaker;
+mod dead_letters;
+mod dedup;
 mod destinations;
 mod destination_factory;
+mod dry_run;
+mod integration;
+mod metrics;
+mod queue;
+mod receipts;
+mod registry;
 mod route;
 mod router_factory;
 mod routing;
 
-use anyhow::Result;
+#[cfg(test)]
+mod tests;
+
+use anyhow::{Result, anyhow};
+use futures::stream::{self, StreamExt};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 
+use crate::config::models::router::GlobalRouterConfig;
+
 /// Router for sending data to destinations
 pub struct Router {
     /// List of destinations to route data to
     pub destinations: Vec<Arc<dyn Destination>>,
+    /// Durable queue that deliveries go through, if any
+    queue: Option<Arc<DeliveryQueue>>,
+    /// Stable key of each destination, by destination ID
+    destination_keys: HashMap<String, String>,
+    /// Concurrency and timeout settings for sending to destinations
+    fan_out: FanOutSettings,
+    /// Per-destination timeouts overriding the default, by destination ID
+    timeouts: HashMap<String, Duration>,
+    /// Circuit breaker of each destination, by destination ID
+    breakers: HashMap<String, Arc<CircuitBreaker>>,
+    /// Metrics that direct sends are recorded in, if any
+    metrics: Option<Arc<DeliveryMetrics>>,
+    /// Store that a receipt of every send goes to, if any
+    receipts: Option<Arc<ReceiptStore>>,
+    /// Route ID the metrics and receipts are labelled with
+    router_id: String,
+}
+
+/// Outcome of sending records to one destination
+pub struct Delivery {
+    /// The destination sent to
+    pub destination: Arc<dyn Destination>,
+    /// Whether the destination accepted the records
+    pub result: Result<()>,
+    /// ID of the delivery's receipt, if one was stored
+    pub receipt_id: Option<i64>,
+}
+
+/// Settings for sending a record to several destinations
+#[derive(Debug, Clone)]
+pub struct FanOutSettings {
+    /// Maximum number of destinations sent to at once
+    pub max_concurrency: usize,
+    /// Timeout for destinations without their own
+    pub timeout: Duration,
+}
+
+impl Default for FanOutSettings {
+    fn default() -> Self {
+        Self::from(&GlobalRouterConfig::default())
+    }
+}
+
+impl From<&GlobalRouterConfig> for FanOutSettings {
+    fn from(config: &GlobalRouterConfig) -> Self {
+        Self {
+            max_concurrency: config.max_concurrency.max(1),
+            timeout: Duration::from_secs(config.destination_timeout.max(1)),
+        }
+    }
 }
 
 /// Trait for defining a destination
@@ -30,8 +98,58 @@ pub trait Destination: Send + Sync {
     /// Send a batch of data to the destination
     async fn send_batch(&self, data: Vec<Value>) -> Result<()>;
     
+    /// Send records along with the queued delivery receipt of each, if any.
+    ///
+    /// Buffered destinations keep the receipts with the records and settle
+    /// them once the records are delivered or given up on; the default
+    /// implementation sends the records as a batch.
+    async fn send_with_receipts(&self, data: Vec<Value>, _receipt_ids: Vec<Option<i64>>) -> Result<()> {
+        self.send_batch(data).await
+    }
+    
     /// Check if the destination is available
     async fn check_availability(&self) -> Result<bool>;
+    
+    /// Provide the metadata reported by the source connector's `get_metadata`.
+    ///
+    /// Destinations that write typed output use it to validate and type
+    /// their schema; the default implementation ignores it.
+    fn set_source_metadata(&self, _metadata: &Value) {}
+    
+    /// Whether accepted records are held in memory and delivered later,
+    /// so a successful send means they were queued rather than delivered
+    fn is_buffered(&self) -> bool {
+        false
+    }
+    
+    /// Provide the store for records the destination accepted but had to
+    /// give up on, with the route and key to file them under.
+    ///
+    /// The default implementation ignores it.
+    fn set_dead_letters(&self, _store: Arc<DeadLetterStore>, _route_id: &str, _destination_key: &str) {}
+    
+    /// Provide the store for receipts of the deliveries the destination
+    /// makes on its own, with the route and key to record them under.
+    ///
+    /// The default implementation ignores it.
+    fn set_receipts(&self, _store: Arc<ReceiptStore>, _route_id: &str, _destination_key: Option<&str>) {}
+    
+    /// Provide the metrics that deliveries the destination makes on its own
+    /// are recorded in, labelled with the route ID.
+    ///
+    /// The default implementation ignores them.
+    fn set_metrics(&self, _metrics: Arc<DeliveryMetrics>, _route_id: &str) {}
+    
+    /// Deliver any records the destination is holding
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Metrics the destination exposes for scraping, in the Prometheus
+    /// text exposition format; `None` if it exposes none
+    fn scrape(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Router data structure for storing routing settings and state
@@ -46,11 +164,19 @@ pub struct RouterData {
     /// Source connector configuration
     pub source: SourceSettings,
     /// Transformations to apply
+    #[serde(default)]
     pub transformations: Vec<TransformationSettings>,
     /// Destinations to send data to
+    #[serde(default)]
     pub destinations: Vec<DestinationSettings>,
+    /// IDs of shared destinations from the registry to send data to
+    #[serde(default)]
+    pub destination_ids: Vec<String>,
     /// Condition for routing (optional)
     pub condition: Option<String>,
+    /// Deduplication settings (optional)
+    #[serde(default)]
+    pub dedup: Option<DedupSettings>,
     /// Error handling settings
     pub error_handling: Option<ErrorHandlingSettings>,
 }
@@ -68,6 +194,7 @@ pub struct SourceSettings {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformationSettings {
     /// Type of transformation
+    #[serde(alias = "type")]
     pub transformation_type: String,
     /// Parameters for the transformation
     pub params: Value,
@@ -76,10 +203,20 @@ pub struct TransformationSettings {
 /// Destination settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DestinationSettings {
+    /// Stable ID that routing rules refer to (`{destination_type}_{position}`
+    /// in the route's `destinations` if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Type of destination
     pub destination_type: String,
     /// Configuration for the destination
     pub config: Value,
+    /// Timeout in seconds for sending to this destination (overrides the global default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Collect records and deliver them in windowed batches (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchSettings>,
 }
 
 /// Error handling settings
@@ -91,10 +228,18 @@ pub struct ErrorHandlingSettings {
     pub error_destination: Option<DestinationSettings>,
 }
 
+impl ErrorHandlingSettings {
+    /// Whether an error should abort the route rather than skip the record
+    pub fn fails_on_error(&self) -> bool {
+        self.on_error.eq_ignore_ascii_case("fail")
+    }
+}
+
 /// Status of a routing operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum RoutingStatus {
     /// Successfully routed data
+    #[default]
     Success,
     /// Failed to route data
     Failure(String),
@@ -102,42 +247,481 @@ pub enum RoutingStatus {
     PartialSuccess(Vec<String>),
 }
 
+impl RoutingStatus {
+    /// Summarize the outcome of sending to `total` destinations.
+    ///
+    /// `failures` holds the ID and error of each destination that failed.
+    pub fn from_failures(total: usize, failures: Vec<(String, String)>) -> Self {
+        if failures.is_empty() {
+            RoutingStatus::Success
+        } else if failures.len() < total {
+            RoutingStatus::PartialSuccess(failures.into_iter().map(|(id, _)| id).collect())
+        } else {
+            let errors: Vec<String> = failures.into_iter()
+                .map(|(id, e)| format!("{}: {}", id, e))
+                .collect();
+            RoutingStatus::Failure(errors.join("; "))
+        }
+    }
+    
+    /// Whether every destination succeeded
+    #[cfg(test)]
+    pub fn is_success(&self) -> bool {
+        matches!(self, RoutingStatus::Success)
+    }
+}
+
+/// Error of a batch delivery in which the destination rejected only some
+/// records.
+///
+/// Destinations whose APIs report the outcome of every record return it, so
+/// that only the rejected records are retried or dead-lettered.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct PartialDelivery {
+    /// Positions in the batch of the rejected records
+    pub failed: Vec<usize>,
+    /// Description of the rejections
+    pub message: String,
+}
+
+impl PartialDelivery {
+    /// Split the items of a batch into the delivered and the rejected ones
+    pub fn split<T>(&self, items: Vec<T>) -> (Vec<T>, Vec<T>) {
+        let (failed, delivered): (Vec<_>, Vec<_>) = items.into_iter()
+            .enumerate()
+            .partition(|(position, _)| self.failed.contains(position));
+        
+        (
+            delivered.into_iter().map(|(_, item)| item).collect(),
+            failed.into_iter().map(|(_, item)| item).collect(),
+        )
+    }
+    
+    /// Number of the `total` records of a delivery that failed, given its result
+    pub fn failed_count(total: usize, result: &Result<()>) -> usize {
+        match result {
+            Ok(()) => 0,
+            Err(e) => match e.downcast_ref::<PartialDelivery>() {
+                Some(partial) => partial.failed.len().min(total),
+                None => total,
+            },
+        }
+    }
+}
+
 impl Router {
     /// Create a new router
     pub fn new(destinations: Vec<Arc<dyn Destination>>) -> Self {
-        Self { destinations }
+        Self {
+            destinations,
+            queue: None,
+            destination_keys: HashMap::new(),
+            fan_out: FanOutSettings::default(),
+            timeouts: HashMap::new(),
+            breakers: HashMap::new(),
+            metrics: None,
+            receipts: None,
+            router_id: String::new(),
+        }
     }
     
-    /// Route data to all enabled destinations
-    pub async fn route(&self, data: Value) -> Result<()> {
-        for destination in &self.destinations {
-            if let Err(e) = destination.send(data.clone()).await {
-                // Log error but continue routing to other destinations
-                tracing::error!(
-                    "Failed to send data to destination {}: {}",
-                    destination.get_id(),
-                    e
+    /// Set the concurrency limit and default timeout for sending
+    pub fn with_fan_out(mut self, settings: FanOutSettings) -> Self {
+        self.fan_out = settings;
+        self
+    }
+    
+    /// Apply the per-destination timeouts of the settings the destinations
+    /// were created from, in the same order
+    pub fn with_timeouts(mut self, settings: &[DestinationSettings]) -> Self {
+        for (destination, settings) in self.destinations.iter().zip(settings) {
+            if let Some(timeout) = settings.timeout {
+                self.timeouts.insert(
+                    destination.get_id().to_string(),
+                    Duration::from_secs(timeout.max(1)),
                 );
             }
         }
         
-        Ok(())
+        self
     }
     
-    /// Route a batch of data to all enabled destinations
-    pub async fn route_batch(&self, data: Vec<Value>) -> Result<()> {
+    /// Key each destination as `{router_id}/{destination_id}`.
+    ///
+    /// The keys name the destination in the delivery queue and in dead
+    /// letters, so they stay the same across restarts and when the
+    /// destination list is reordered, as long as the destination IDs do.
+    pub fn with_keys(mut self, router_id: &str) -> Self {
+        for destination in &self.destinations {
+            self.destination_keys.insert(
+                destination.get_id().to_string(),
+                format!("{}/{}", router_id, destination.get_id()),
+            );
+        }
+        
+        self
+    }
+    
+    /// Guard each destination with a circuit breaker from the registry.
+    ///
+    /// Breakers are keyed by destination key, so they are shared by every
+    /// router built from the same route. Call this before
+    /// [`Router::with_queue`] so the queue workers use the breakers too.
+    pub fn with_breakers(mut self, registry: &BreakerRegistry, router_id: &str) -> Self {
+        if self.destination_keys.is_empty() {
+            self = self.with_keys(router_id);
+        }
+        
+        for destination in &self.destinations {
+            if let Some(key) = self.destination_keys.get(destination.get_id()) {
+                let breaker = registry.breaker(key, destination.as_ref());
+                self.breakers.insert(destination.get_id().to_string(), breaker);
+            }
+        }
+        
+        self
+    }
+    
+    /// Record direct sends in delivery metrics, labelled with the router ID.
+    ///
+    /// Queued deliveries are recorded by the queue (see
+    /// [`DeliveryQueue::with_metrics`]), and records a buffered destination
+    /// holds by the destination once it sends them on.
+    pub fn with_metrics(mut self, metrics: Arc<DeliveryMetrics>, router_id: &str) -> Self {
+        for destination in &self.destinations {
+            destination.set_metrics(Arc::clone(&metrics), router_id);
+        }
+        
+        self.metrics = Some(metrics);
+        self.router_id = router_id.to_string();
+        self
+    }
+    
+    /// Store a receipt of every send, labelled with the router ID.
+    ///
+    /// Records that are queued, or held by a buffered destination, get a
+    /// `queued` receipt, which is settled with the outcome of the batch
+    /// they are delivered in. Call this after [`Router::with_keys`] (or
+    /// [`Router::with_breakers`]) so receipts name the destination keys.
+    pub fn with_receipts(mut self, store: Arc<ReceiptStore>, router_id: &str) -> Self {
+        for destination in &self.destinations {
+            let key = self.destination_keys.get(destination.get_id()).map(String::as_str);
+            destination.set_receipts(Arc::clone(&store), router_id, key);
+        }
+        
+        self.receipts = Some(store);
+        self.router_id = router_id.to_string();
+        self
+    }
+    
+    /// Deliver through a durable queue instead of sending directly.
+    ///
+    /// Each destination is queued under its key (see [`Router::with_keys`])
+    /// and a worker is started to deliver its queue, within the
+    /// destination's timeout. Call this after [`Router::with_fan_out`] and
+    /// [`Router::with_timeouts`] so the workers use those timeouts.
+    pub fn with_queue(mut self, queue: Arc<DeliveryQueue>, router_id: &str) -> Self {
+        if self.destination_keys.is_empty() {
+            self = self.with_keys(router_id);
+        }
+        
+        for destination in &self.destinations {
+            if let Some(key) = self.destination_keys.get(destination.get_id()) {
+                let breaker = self.breakers.get(destination.get_id()).cloned();
+                queue.start(key.clone(), Arc::clone(destination), breaker, self.timeout(destination));
+            }
+        }
+        
+        self.queue = Some(queue);
+        self.router_id = router_id.to_string();
+        self
+    }
+    
+    /// File records that destinations give up on after accepting them in
+    /// the dead letter store, under each destination's key
+    pub fn with_dead_letters(self, store: &Arc<DeadLetterStore>, router_id: &str) -> Self {
         for destination in &self.destinations {
-            if let Err(e) = destination.send_batch(data.clone()).await {
-                // Log error but continue routing to other destinations
+            if let Some(key) = self.destination_keys.get(destination.get_id()) {
+                destination.set_dead_letters(Arc::clone(store), router_id, key);
+            }
+        }
+        
+        self
+    }
+    
+    /// Deliver the records every destination is holding
+    pub async fn flush(&self) {
+        for destination in &self.destinations {
+            if let Err(e) = destination.flush().await {
+                tracing::error!("Failed to flush destination {}: {}", destination.get_id(), e);
+            }
+        }
+    }
+    
+    /// Whether deliveries go through a durable queue
+    #[cfg(test)]
+    pub fn is_queued(&self) -> bool {
+        self.queue.is_some()
+    }
+    
+    /// Get the stable key of a destination, if keys were assigned
+    pub fn destination_key(&self, destination: &Arc<dyn Destination>) -> Option<&str> {
+        self.destination_keys.get(destination.get_id()).map(String::as_str)
+    }
+    
+    /// Send records to a single destination, through the queue if there is
+    /// one, and store a receipt of the send.
+    ///
+    /// Queued records keep their origin for dead letters. Direct sends fail
+    /// if the destination doesn't finish within its timeout, or without
+    /// trying when its circuit breaker is open.
+    pub async fn deliver(
+        &self,
+        destination: &Arc<dyn Destination>,
+        data: Vec<Value>,
+        origin: Option<DeliveryOrigin<'_>>,
+    ) -> Delivery {
+        let records = data.len();
+        let started = Instant::now();
+        
+        // Records that are held get their receipt before they are handed
+        // over, so whatever sends them on can settle it
+        let held = self.queue.is_some() || destination.is_buffered();
+        let queued = if held {
+            self.receipt(destination, records, origin, Duration::ZERO, &Ok(())).await
+        } else {
+            None
+        };
+        
+        let result = self.send(destination, data, origin, queued).await;
+        let duration = started.elapsed();
+        
+        let receipt_id = if held {
+            if let (Some(id), Err(_)) = (queued, &result) {
+                self.settle(id, destination, records, duration, &result).await;
+            }
+            queued
+        } else {
+            self.receipt(destination, records, origin, duration, &result).await
+        };
+        
+        Delivery { destination: Arc::clone(destination), result, receipt_id }
+    }
+    
+    /// The receipt of a send; `queued` for records that are held, if it
+    /// succeeded
+    fn new_receipt(
+        &self,
+        destination: &Arc<dyn Destination>,
+        records: usize,
+        duration: Duration,
+        result: &Result<()>,
+    ) -> DeliveryReceipt {
+        let status = match result {
+            Ok(()) if self.queue.is_some() || destination.is_buffered() => ReceiptStatus::Queued,
+            Ok(()) => ReceiptStatus::Delivered,
+            Err(_) => ReceiptStatus::Failed,
+        };
+        
+        let mut receipt = DeliveryReceipt::new(&self.router_id, destination.get_id(), records, status, duration);
+        if let Some(key) = self.destination_key(destination) {
+            receipt = receipt.with_destination_key(key);
+        }
+        if let Err(e) = result {
+            receipt = receipt.with_error(e);
+        }
+        
+        receipt
+    }
+    
+    /// Store the receipt of a send; returns its ID, or `None` if there is
+    /// no receipt store or storing failed
+    async fn receipt(
+        &self,
+        destination: &Arc<dyn Destination>,
+        records: usize,
+        origin: Option<DeliveryOrigin<'_>>,
+        duration: Duration,
+        result: &Result<()>,
+    ) -> Option<i64> {
+        let store = self.receipts.as_ref()?;
+        
+        let mut receipt = self.new_receipt(destination, records, duration, result);
+        if let Some(origin) = origin {
+            receipt = receipt.with_rule(origin.rule_id);
+        }
+        
+        match store.record(&receipt).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::error!("Failed to record delivery receipt for route {}: {}", self.router_id, e);
+                None
+            }
+        }
+    }
+    
+    /// Settle the queued receipt of records that couldn't be handed over
+    async fn settle(
+        &self,
+        id: i64,
+        destination: &Arc<dyn Destination>,
+        records: usize,
+        duration: Duration,
+        result: &Result<()>,
+    ) {
+        let store = match &self.receipts {
+            Some(store) => store,
+            None => return,
+        };
+        
+        let outcome = self.new_receipt(destination, records, duration, result);
+        if let Err(e) = store.settle(&[id], &outcome).await {
+            tracing::error!("Failed to settle delivery receipt for route {}: {}", self.router_id, e);
+        }
+    }
+    
+    /// Hand records to the queue, or send them straight to the destination
+    async fn send(
+        &self,
+        destination: &Arc<dyn Destination>,
+        data: Vec<Value>,
+        origin: Option<DeliveryOrigin<'_>>,
+        receipt_id: Option<i64>,
+    ) -> Result<()> {
+        if let (Some(queue), Some(key)) = (&self.queue, self.destination_keys.get(destination.get_id())) {
+            return queue.enqueue_with_receipt(&self.router_id, key, &data, origin, receipt_id).await;
+        }
+        
+        let breaker = self.breakers.get(destination.get_id());
+        
+        let _permit = match breaker {
+            Some(breaker) => match breaker.allow(destination.as_ref(), self.timeout(destination)).await {
+                Some(permit) => Some(permit),
+                None => return Err(anyhow!("Circuit breaker for destination {} is open", destination.get_id())),
+            },
+            None => None,
+        };
+        
+        let records = data.len();
+        let started = Instant::now();
+        let result = self.send_with_timeout(destination, data, receipt_id).await;
+        
+        // A buffered destination records the records it accepted once it
+        // has sent them on
+        if !destination.is_buffered() || result.is_err() {
+            if let Some(metrics) = &self.metrics {
+                metrics.record(&self.router_id, destination.as_ref(), records, started.elapsed(), &result);
+            }
+        }
+        
+        // A destination that rejected some records still answered
+        if let Some(breaker) = breaker {
+            match &result {
+                Err(e) if e.downcast_ref::<PartialDelivery>().is_none() => breaker.record_failure(&e.to_string()),
+                _ => breaker.record_success(),
+            }
+        }
+        
+        result
+    }
+    
+    /// Timeout for sending to a destination: its own, or the default
+    fn timeout(&self, destination: &Arc<dyn Destination>) -> Duration {
+        self.timeouts.get(destination.get_id())
+            .copied()
+            .unwrap_or(self.fan_out.timeout)
+    }
+    
+    /// Send records straight to a destination, within its timeout.
+    ///
+    /// A buffered destination is handed the records' queued receipt.
+    async fn send_with_timeout(
+        &self,
+        destination: &Arc<dyn Destination>,
+        data: Vec<Value>,
+        receipt_id: Option<i64>,
+    ) -> Result<()> {
+        let timeout = self.timeout(destination);
+        
+        let send = async {
+            if destination.is_buffered() {
+                let receipt_ids = vec![receipt_id; data.len()];
+                return destination.send_with_receipts(data, receipt_ids).await;
+            }
+            
+            match data.len() {
+                1 => destination.send(data.into_iter().next().unwrap_or_default()).await,
+                _ => destination.send_batch(data).await,
+            }
+        };
+        
+        match tokio::time::timeout(timeout, send).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!(
+                "Sending to destination {} timed out after {}s",
+                destination.get_id(),
+                timeout.as_secs()
+            )),
+        }
+    }
+    
+    /// Send records to several destinations concurrently.
+    ///
+    /// At most `max_concurrency` destinations are sent to at once, each
+    /// with its own timeout, so a slow destination doesn't hold up the
+    /// others. Returns each destination's delivery, in the given order.
+    pub async fn fan_out(
+        &self,
+        destinations: &[Arc<dyn Destination>],
+        data: &[Value],
+        origin: Option<DeliveryOrigin<'_>>,
+    ) -> Vec<Delivery> {
+        // Futures are lazy, so building them up front still caps concurrency,
+        // and keeps the closure out of the stream type so the result is Send
+        let deliveries: Vec<_> = destinations.iter()
+            .map(|destination| self.deliver(destination, data.to_vec(), origin))
+            .collect();
+        
+        stream::iter(deliveries)
+            .buffered(self.fan_out.max_concurrency)
+            .collect()
+            .await
+    }
+    
+    /// Deliver to every destination and summarize the outcome
+    #[cfg(test)]
+    async fn deliver_all(&self, data: Vec<Value>) -> RoutingStatus {
+        let mut failures = Vec::new();
+        
+        for delivery in self.fan_out(&self.destinations, &data, None).await {
+            if let Err(e) = delivery.result {
                 tracing::error!(
-                    "Failed to send batch to destination {}: {}",
-                    destination.get_id(),
+                    "Failed to send data to destination {}: {}",
+                    delivery.destination.get_id(),
                     e
                 );
+                failures.push((delivery.destination.get_id().to_string(), e.to_string()));
             }
         }
         
-        Ok(())
+        RoutingStatus::from_failures(self.destinations.len(), failures)
+    }
+    
+    /// Route data to all enabled destinations
+    #[cfg(test)]
+    pub async fn route(&self, data: Value) -> RoutingStatus {
+        self.deliver_all(vec![data]).await
+    }
+    
+    /// Route a batch of data to all enabled destinations
+    #[cfg(test)]
+    pub async fn route_batch(&self, data: Vec<Value>) -> RoutingStatus {
+        if data.is_empty() {
+            return RoutingStatus::Success;
+        }
+        
+        self.deliver_all(data).await
     }
 }
 
@@ -146,20 +730,55 @@ pub use destinations::{
     DatabaseDestination,
     EmailDestination,
     FileDestination,
+    InfluxDestination,
+    OtlpDestination,
     PrometheusDestination,
+    SearchIndexDestination,
     SlackDestination,
     S3Destination,
+    StatsdDestination,
     WebhookDestination,
 };
 
+#[cfg(feature = "kafka")]
+pub use destinations::KafkaDestination;
+
 // Re-export destination factory
-pub use destination_factory::DestinationFactory;
+pub use destination_factory::{DestinationDefaults, DestinationFactory};
+
+// Re-export delivery queue
+pub use queue::{DeliveryOrigin, DeliveryQueue, QueueSettings};
+
+// Re-export batching
+pub use batching::{BatchSettings, BatchingDestination};
+
+// Re-export circuit breakers
+pub use circuit_breaker::{BreakerRegistry, BreakerSettings, BreakerState, BreakerStatus, CircuitBreaker};
+
+// Re-export dead letters
+pub use dead_letters::{DeadLetter, DeadLetterFilter, DeadLetterStore, FailureStage, RedriveOutcome};
+
+// Re-export deduplication
+pub use dedup::{DedupSettings, DedupStore};
+
+// Re-export delivery metrics and receipts
+pub use metrics::DeliveryMetrics;
+pub use receipts::{DeliveryReceipt, ReceiptFilter, ReceiptStatus, ReceiptStore};
+
+// Re-export registries
+pub use registry::{DestinationInUse, DestinationRegistry, RouteRegistry};
+
+// Re-export dry runs
+pub use dry_run::{DryRunReport, RuleTrace, trace_transformations};
+
+// Re-export the router integration
+pub use integration::RouterIntegration;
 
 // Re-export router factory
 pub use router_factory::RouterFactory;
 
 // Re-export route functionality
-pub use route::Route;
+pub use route::{Route, RouteDefinition};
 
 // Re-export routing functionality
 pub use routing::*; 
\ No newline at end of file
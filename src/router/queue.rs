@@ -0,0 +1,674 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use rand::Rng;
+use serde_json::Value;
+use sqlx::{Row, Sqlite, Transaction};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::config::models::router::GlobalRouterConfig;
+use crate::router::{
+    CircuitBreaker, DeadLetter, DeadLetterStore, DeliveryMetrics, DeliveryReceipt, Destination, FailureStage,
+    PartialDelivery, ReceiptStatus, ReceiptStore,
+};
+use crate::storage::DatabasePool;
+
+/// Delay before the first retry
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+/// Longest delay between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Settings for the delivery queue
+#[derive(Debug, Clone)]
+pub struct QueueSettings {
+    /// Maximum number of undelivered records per destination
+    pub buffer_size: usize,
+    /// Maximum number of records per delivery
+    pub batch_size: usize,
+    /// How often each destination's queue is flushed
+    pub flush_interval: Duration,
+    /// Retries after the first attempt before a record is marked as failed
+    pub max_retries: u32,
+}
+
+impl From<&GlobalRouterConfig> for QueueSettings {
+    fn from(config: &GlobalRouterConfig) -> Self {
+        Self {
+            buffer_size: config.buffer_size.max(1),
+            batch_size: config.batch_size.max(1),
+            flush_interval: Duration::from_secs(config.flush_interval.max(1)),
+            max_retries: config.max_retries,
+        }
+    }
+}
+
+/// The rule and original record that queued records were routed from
+#[derive(Debug, Clone, Copy)]
+pub struct DeliveryOrigin<'a> {
+    /// Rule that routed the records
+    pub rule_id: &'a str,
+    /// The record before any transformation
+    pub record: &'a Value,
+}
+
+/// Where a destination key's worker delivers to
+#[derive(Clone)]
+struct QueueTarget {
+    destination: Arc<dyn Destination>,
+    breaker: Option<Arc<CircuitBreaker>>,
+    timeout: Duration,
+}
+
+/// A record claimed from the queue for delivery
+#[derive(Debug)]
+struct QueuedRecord {
+    id: i64,
+    /// Route that queued the record
+    route_id: String,
+    payload: Value,
+    attempts: u32,
+    /// The original record, if it was queued with one
+    record: Option<Value>,
+    /// Rule that routed the record, if it was queued with one
+    rule_id: Option<String>,
+    /// Queued receipt of the record, if it was queued with one
+    receipt_id: Option<i64>,
+}
+
+/// Durable per-destination outbox stored in the SQLite database.
+///
+/// Records are written to the `delivery_queue` table before delivery and
+/// removed only once the destination accepted them, so delivery is
+/// at-least-once and survives restarts. Failed deliveries are retried with
+/// exponential backoff up to `max_retries` times, then moved to the dead
+/// letter store if there is one.
+pub struct DeliveryQueue {
+    /// Database holding the queue
+    pool: Arc<DatabasePool>,
+    /// Queue settings
+    settings: QueueSettings,
+    /// Wakes a destination's worker when a full batch is waiting
+    notifiers: Mutex<HashMap<String, Arc<Notify>>>,
+    /// Running workers, by destination key
+    workers: Mutex<HashMap<String, JoinHandle<()>>>,
+    /// What each worker delivers to, by destination key
+    targets: Mutex<HashMap<String, QueueTarget>>,
+    /// Where records go once they run out of retries
+    dead_letters: Option<Arc<DeadLetterStore>>,
+    /// Metrics that deliveries are recorded in, if any
+    metrics: Option<Arc<DeliveryMetrics>>,
+    /// Store that a receipt of every delivered or failed batch goes to, if any
+    receipts: Option<Arc<ReceiptStore>>,
+}
+
+impl DeliveryQueue {
+    /// Create a new delivery queue
+    pub fn new(pool: Arc<DatabasePool>, settings: QueueSettings) -> Self {
+        Self {
+            pool,
+            settings,
+            notifiers: Mutex::new(HashMap::new()),
+            workers: Mutex::new(HashMap::new()),
+            targets: Mutex::new(HashMap::new()),
+            dead_letters: None,
+            metrics: None,
+            receipts: None,
+        }
+    }
+    
+    /// Move records that run out of retries to a dead letter store.
+    ///
+    /// Without one they stay in the queue with status `failed`.
+    pub fn with_dead_letters(mut self, store: Arc<DeadLetterStore>) -> Self {
+        self.dead_letters = Some(store);
+        self
+    }
+
+    /// Record deliveries in delivery metrics, labelled with the route that
+    /// queued the records
+    pub fn with_metrics(mut self, metrics: Arc<DeliveryMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Record a receipt for every batch delivered from the queue, or that
+    /// failed to deliver
+    pub fn with_receipts(mut self, store: Arc<ReceiptStore>) -> Self {
+        self.receipts = Some(store);
+        self
+    }
+
+    /// Return records left in flight by a previous run to the queue.
+    ///
+    /// Should be called once at startup, before any worker is started.
+    pub async fn recover(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE delivery_queue SET status = 'pending', updated_at = CURRENT_TIMESTAMP
+             WHERE status = 'in_flight'"
+        )
+        .execute(self.pool.as_ref())
+        .await?;
+
+        if result.rows_affected() > 0 {
+            tracing::info!("Recovered {} in-flight deliveries", result.rows_affected());
+        }
+
+        Ok(result.rows_affected())
+    }
+
+    /// Add records to a destination's queue.
+    ///
+    /// The origin is kept with each record, so a record that runs out of
+    /// retries becomes a dead letter with its original record and rule.
+    /// Fails without queueing anything when the destination already has
+    /// `buffer_size` undelivered records, so callers see backpressure
+    /// instead of the queue growing without bound.
+    #[cfg(test)]
+    pub async fn enqueue(
+        &self,
+        route_id: &str,
+        destination_key: &str,
+        records: &[Value],
+        origin: Option<DeliveryOrigin<'_>>,
+    ) -> Result<()> {
+        self.enqueue_with_receipt(route_id, destination_key, records, origin, None).await
+    }
+
+    /// Add records to a destination's queue under a queued delivery receipt.
+    ///
+    /// The receipt is settled once the records are delivered, or once they
+    /// run out of retries.
+    pub async fn enqueue_with_receipt(
+        &self,
+        route_id: &str,
+        destination_key: &str,
+        records: &[Value],
+        origin: Option<DeliveryOrigin<'_>>,
+        receipt_id: Option<i64>,
+    ) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let pending = match self.insert(&mut tx, route_id, destination_key, records, origin, receipt_id).await? {
+            Some(pending) => pending,
+            None => {
+                tx.rollback().await?;
+                return Err(anyhow!(
+                    "Delivery queue for {} is full ({} records, at most {} pending)",
+                    destination_key, records.len(), self.settings.buffer_size
+                ));
+            }
+        };
+
+        tx.commit().await?;
+        self.wake(destination_key, pending);
+
+        Ok(())
+    }
+
+    /// Add records to a destination's queue as part of a transaction.
+    ///
+    /// Returns the number of records pending once it commits, or `None` if
+    /// they would take the destination past `buffer_size`, in which case
+    /// the transaction must be rolled back. Call [`DeliveryQueue::wake`]
+    /// after committing.
+    pub(crate) async fn insert(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        route_id: &str,
+        destination_key: &str,
+        records: &[Value],
+        origin: Option<DeliveryOrigin<'_>>,
+        receipt_id: Option<i64>,
+    ) -> Result<Option<usize>> {
+        // Write before counting: SQLite can't wait for a busy database when
+        // a transaction that has only read so far starts writing
+        let now = Utc::now().timestamp_millis();
+        let original = origin.map(|origin| serde_json::to_string(origin.record)).transpose()?;
+        for record in records {
+            sqlx::query(
+                "INSERT INTO delivery_queue (route_id, destination_key, payload, record, rule_id, receipt_id, next_attempt_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(route_id)
+            .bind(destination_key)
+            .bind(serde_json::to_string(record)?)
+            .bind(&original)
+            .bind(origin.map(|origin| origin.rule_id))
+            .bind(receipt_id)
+            .bind(now)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        let (pending,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM delivery_queue WHERE destination_key = ? AND status != 'failed'"
+        )
+        .bind(destination_key)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let pending = pending as usize;
+        Ok((pending <= self.settings.buffer_size).then_some(pending))
+    }
+
+    /// Wake a destination's worker if `pending` records make a full batch,
+    /// rather than waiting for the interval
+    pub(crate) fn wake(&self, destination_key: &str, pending: usize) {
+        if pending >= self.settings.batch_size {
+            self.notifier(destination_key).notify_one();
+        }
+    }
+
+    /// Whether a worker is delivering a destination's queue
+    pub fn is_running(&self, destination_key: &str) -> bool {
+        match self.workers.lock() {
+            Ok(workers) => workers.contains_key(destination_key),
+            Err(poisoned) => poisoned.into_inner().contains_key(destination_key),
+        }
+    }
+
+    /// Number of records waiting to be delivered to a destination
+    #[cfg(test)]
+    pub async fn pending_count(&self, destination_key: &str) -> Result<usize> {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM delivery_queue WHERE destination_key = ? AND status != 'failed'"
+        )
+        .bind(destination_key)
+        .fetch_one(self.pool.as_ref())
+        .await?;
+
+        Ok(count as usize)
+    }
+
+    /// Start the worker delivering a destination's queue.
+    ///
+    /// Each delivery fails if the destination doesn't finish within
+    /// `timeout`. With a circuit breaker, records stay queued while the
+    /// breaker is open. If a worker is already running for the key, it
+    /// delivers to the given destination from its next flush on, so a
+    /// rebuilt route takes over its queue.
+    pub fn start(
+        self: &Arc<Self>,
+        destination_key: String,
+        destination: Arc<dyn Destination>,
+        breaker: Option<Arc<CircuitBreaker>>,
+        timeout: Duration,
+    ) {
+        let mut workers = match self.workers.lock() {
+            Ok(workers) => workers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        self.lock_targets().insert(destination_key.clone(), QueueTarget { destination, breaker, timeout });
+
+        if workers.contains_key(&destination_key) {
+            return;
+        }
+
+        let queue = Arc::clone(self);
+        let key = destination_key.clone();
+        let notify = self.notifier(&destination_key);
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(queue.settings.flush_interval);
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = notify.notified() => {},
+                }
+
+                let target = match queue.lock_targets().get(&key) {
+                    Some(target) => target.clone(),
+                    None => break,
+                };
+
+                if let Err(e) = queue.flush(&key, target.destination.as_ref(), target.breaker.as_deref(), target.timeout).await {
+                    tracing::error!("Failed to flush delivery queue for {}: {}", key, e);
+                }
+            }
+        });
+
+        workers.insert(destination_key, handle);
+    }
+
+    /// Stop all workers.
+    ///
+    /// Records being delivered at that moment stay in flight and are
+    /// returned to the queue by [`DeliveryQueue::recover`] on the next start.
+    pub fn stop(&self) {
+        let mut workers = match self.workers.lock() {
+            Ok(workers) => workers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        for (_, handle) in workers.drain() {
+            handle.abort();
+        }
+
+        self.lock_targets().clear();
+    }
+
+    fn lock_targets(&self) -> MutexGuard<'_, HashMap<String, QueueTarget>> {
+        match self.targets.lock() {
+            Ok(targets) => targets,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Deliver due records to a destination, one batch at a time.
+    ///
+    /// Returns the number of records delivered. Stops at the first failed
+    /// batch, whose records are rescheduled with backoff (only the rejected
+    /// ones if the destination accepted the rest), or as soon as the
+    /// destination's circuit breaker doesn't allow sending. A batch the
+    /// destination doesn't accept within `timeout` counts as failed.
+    ///
+    /// A buffered destination is handed the records' queued receipts and
+    /// records the delivery itself once it sends them on.
+    pub async fn flush(
+        &self,
+        destination_key: &str,
+        destination: &dyn Destination,
+        breaker: Option<&CircuitBreaker>,
+        timeout: Duration,
+    ) -> Result<usize> {
+        let mut delivered = 0;
+
+        loop {
+            let batch = self.claim(destination_key).await?;
+            if batch.is_empty() {
+                return Ok(delivered);
+            }
+
+            let _permit = match breaker {
+                Some(breaker) => match breaker.allow(destination, timeout).await {
+                    Some(permit) => Some(permit),
+                    None => {
+                        self.release(&batch).await?;
+                        return Ok(delivered);
+                    },
+                },
+                None => None,
+            };
+
+            // Every record queued under a destination key comes from its route
+            let route_id = batch[0].route_id.clone();
+            let payloads: Vec<Value> = batch.iter().map(|record| record.payload.clone()).collect();
+            let receipt_ids = batch.iter().map(|record| record.receipt_id).collect();
+            let buffered = destination.is_buffered();
+
+            let started = Instant::now();
+            let send = async {
+                if buffered {
+                    destination.send_with_receipts(payloads, receipt_ids).await
+                } else {
+                    destination.send_batch(payloads).await
+                }
+            };
+            let result = match tokio::time::timeout(timeout, send).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!(
+                    "Sending to destination {} timed out after {}s",
+                    destination.get_id(),
+                    timeout.as_secs()
+                )),
+            };
+
+            let duration = started.elapsed();
+
+            // A buffered destination records the records it accepted once it
+            // has sent them on
+            if !buffered || result.is_err() {
+                if let Some(metrics) = &self.metrics {
+                    metrics.record(&route_id, destination, batch.len(), duration, &result);
+                }
+            }
+
+            // A destination that rejected some records still answered
+            if let Some(breaker) = breaker {
+                match &result {
+                    Err(e) if e.downcast_ref::<PartialDelivery>().is_none() => breaker.record_failure(&e.to_string()),
+                    _ => breaker.record_success(),
+                }
+            }
+
+            let (accepted, rejected) = match &result {
+                Ok(()) => (batch, Vec::new()),
+                Err(e) => match e.downcast_ref::<PartialDelivery>() {
+                    Some(partial) => partial.split(batch),
+                    None => (Vec::new(), batch),
+                },
+            };
+
+            if !accepted.is_empty() {
+                if !buffered {
+                    let settled: Vec<i64> = accepted.iter().filter_map(|record| record.receipt_id).collect();
+                    self.receipt(destination_key, destination, &accepted, duration, None, &settled).await;
+                }
+                self.complete(&accepted).await?;
+                delivered += accepted.len();
+            }
+
+            if let Err(e) = result {
+                let settled: Vec<i64> = rejected.iter()
+                    .filter(|record| self.is_exhausted(record))
+                    .filter_map(|record| record.receipt_id)
+                    .collect();
+                self.receipt(destination_key, destination, &rejected, duration, Some(&e), &settled).await;
+                tracing::warn!(
+                    "Delivery of {} records to {} failed: {}",
+                    rejected.len(), destination_key, e
+                );
+                self.reschedule(destination_key, &rejected, &e.to_string()).await?;
+                return Ok(delivered);
+            }
+        }
+    }
+
+    /// Store a receipt for records of a batch delivered from the queue, or
+    /// rejected with `error`, and settle the `settled` queued receipts with
+    /// it.
+    ///
+    /// The receipt names the batch's rule when all its records came from
+    /// the same one. Failing to store it is logged, not returned, so the
+    /// delivery itself is still completed or rescheduled.
+    async fn receipt(
+        &self,
+        destination_key: &str,
+        destination: &dyn Destination,
+        batch: &[QueuedRecord],
+        duration: Duration,
+        error: Option<&anyhow::Error>,
+        settled: &[i64],
+    ) {
+        let (store, route_id) = match (&self.receipts, batch.first()) {
+            (Some(store), Some(record)) => (store, &record.route_id),
+            _ => return,
+        };
+
+        let status = match error {
+            None => ReceiptStatus::Delivered,
+            Some(_) => ReceiptStatus::Failed,
+        };
+
+        let mut receipt = DeliveryReceipt::new(
+            route_id,
+            destination.get_id(),
+            batch.len(),
+            status,
+            duration,
+        )
+        .with_destination_key(destination_key);
+        if let Some(rule_id) = batch.first().and_then(|record| record.rule_id.as_deref()) {
+            if batch.iter().all(|record| record.rule_id.as_deref() == Some(rule_id)) {
+                receipt = receipt.with_rule(rule_id);
+            }
+        }
+        if let Some(e) = error {
+            receipt = receipt.with_error(e);
+        }
+
+        if let Err(e) = store.record_batch(&receipt, settled).await {
+            tracing::error!("Failed to record delivery receipt for {}: {}", destination_key, e);
+        }
+    }
+
+    /// Whether a record's next failed attempt is its last one
+    fn is_exhausted(&self, record: &QueuedRecord) -> bool {
+        // The first attempt isn't a retry
+        record.attempts + 1 > self.settings.max_retries
+    }
+
+    /// Claim the next batch of due records by marking them in flight
+    async fn claim(&self, destination_key: &str) -> Result<Vec<QueuedRecord>> {
+        let rows = sqlx::query(
+            "UPDATE delivery_queue SET status = 'in_flight', updated_at = CURRENT_TIMESTAMP
+             WHERE id IN (
+                 SELECT id FROM delivery_queue
+                 WHERE destination_key = ? AND status = 'pending' AND next_attempt_at <= ?
+                 ORDER BY id
+                 LIMIT ?
+             )
+             RETURNING id, route_id, payload, attempts, record, rule_id, receipt_id"
+        )
+        .bind(destination_key)
+        .bind(Utc::now().timestamp_millis())
+        .bind(self.settings.batch_size as i64)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let mut records = rows.into_iter()
+            .map(|row| {
+                let payload: String = row.try_get("payload")?;
+                let record: Option<String> = row.try_get("record")?;
+                Ok(QueuedRecord {
+                    id: row.try_get("id")?,
+                    route_id: row.try_get("route_id")?,
+                    payload: serde_json::from_str(&payload)?,
+                    attempts: row.try_get::<i64, _>("attempts")? as u32,
+                    record: record.map(|r| serde_json::from_str(&r)).transpose()?,
+                    rule_id: row.try_get("rule_id")?,
+                    receipt_id: row.try_get("receipt_id")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // RETURNING doesn't guarantee order
+        records.sort_by_key(|record| record.id);
+        Ok(records)
+    }
+
+    /// Remove delivered records from the queue
+    async fn complete(&self, batch: &[QueuedRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for record in batch {
+            sqlx::query("DELETE FROM delivery_queue WHERE id = ?")
+                .bind(record.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Return claimed records to the queue without counting an attempt
+    async fn release(&self, batch: &[QueuedRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for record in batch {
+            sqlx::query(
+                "UPDATE delivery_queue SET status = 'pending', updated_at = CURRENT_TIMESTAMP WHERE id = ?"
+            )
+            .bind(record.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Schedule failed records for another attempt, or mark them failed
+    async fn reschedule(&self, destination_key: &str, batch: &[QueuedRecord], error: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let mut exhausted = Vec::new();
+
+        for record in batch {
+            let attempts = record.attempts + 1;
+            let status = if self.is_exhausted(record) { "failed" } else { "pending" };
+            let next_attempt_at = Utc::now().timestamp_millis() + backoff(attempts).as_millis() as i64;
+
+            sqlx::query(
+                "UPDATE delivery_queue
+                 SET status = ?, attempts = ?, next_attempt_at = ?, last_error = ?, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?"
+            )
+            .bind(status)
+            .bind(attempts as i64)
+            .bind(next_attempt_at)
+            .bind(error)
+            .bind(record.id)
+            .execute(&mut *tx)
+            .await?;
+
+            if status == "failed" {
+                tracing::error!("Giving up on queued record {} after {} attempts: {}", record.id, attempts, error);
+                exhausted.push((record, attempts));
+            }
+        }
+
+        tx.commit().await?;
+
+        if let Some(store) = &self.dead_letters {
+            for (record, attempts) in exhausted {
+                // Records queued without an origin were sent untransformed
+                let original = record.record.clone().unwrap_or_else(|| record.payload.clone());
+                let mut letter = DeadLetter::new(&record.route_id, FailureStage::Delivery, original, error)
+                    .with_payload(record.payload.clone())
+                    .with_destination(destination_key)
+                    .with_attempts(attempts);
+                if let Some(rule_id) = &record.rule_id {
+                    letter = letter.with_rule(rule_id);
+                }
+
+                store.publish(letter).await?;
+
+                sqlx::query("DELETE FROM delivery_queue WHERE id = ?")
+                    .bind(record.id)
+                    .execute(self.pool.as_ref())
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the notifier for a destination, creating it if needed
+    fn notifier(&self, destination_key: &str) -> Arc<Notify> {
+        let mut notifiers = match self.notifiers.lock() {
+            Ok(notifiers) => notifiers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        Arc::clone(notifiers.entry(destination_key.to_string()).or_default())
+    }
+}
+
+/// Delay before the next attempt: doubles per attempt with up to 20% jitter
+fn backoff(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    let delay = BASE_BACKOFF.saturating_mul(1 << exponent).min(MAX_BACKOFF);
+    let jitter = rand::thread_rng().gen_range(0.0..0.2);
+
+    delay.mul_f64(1.0 + jitter)
+}
@@ -0,0 +1,249 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use sqlx::sqlite::SqliteRow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::storage::DatabasePool;
+
+/// Outcome recorded in a delivery receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptStatus {
+    /// The destination accepted the batch
+    Delivered,
+    /// The batch was written to the delivery queue or a batching destination's buffer
+    Queued,
+    /// The destination rejected the batch
+    Failed,
+}
+
+impl ReceiptStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptStatus::Delivered => "delivered",
+            ReceiptStatus::Queued => "queued",
+            ReceiptStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "delivered" => ReceiptStatus::Delivered,
+            "queued" => ReceiptStatus::Queued,
+            _ => ReceiptStatus::Failed,
+        }
+    }
+}
+
+/// Record of a batch a route handed to a destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryReceipt {
+    /// ID in the receipt store (set once stored)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Route the batch was sent by
+    pub route_id: String,
+    /// Rule that sent the batch (`default` when the route has no rules)
+    pub rule_id: Option<String>,
+    /// ID of the destination
+    pub destination_id: String,
+    /// Stable key of the destination, if keys were assigned
+    pub destination_key: Option<String>,
+    /// Number of records in the batch
+    pub record_count: usize,
+    /// Outcome of the delivery
+    pub status: ReceiptStatus,
+    /// Error returned by the destination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Time taken, in milliseconds
+    pub duration_ms: u64,
+    /// Receipt of the batch that settled a queued receipt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_receipt_id: Option<i64>,
+    /// When the receipt was recorded
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeliveryReceipt {
+    /// Create a receipt for a delivery that just finished
+    pub fn new(
+        route_id: &str,
+        destination_id: &str,
+        record_count: usize,
+        status: ReceiptStatus,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            id: None,
+            route_id: route_id.to_string(),
+            rule_id: None,
+            destination_id: destination_id.to_string(),
+            destination_key: None,
+            record_count,
+            status,
+            error: None,
+            duration_ms: duration.as_millis() as u64,
+            batch_receipt_id: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Set the rule that sent the batch
+    pub fn with_rule(mut self, rule_id: &str) -> Self {
+        self.rule_id = Some(rule_id.to_string());
+        self
+    }
+
+    /// Set the destination's stable key
+    pub fn with_destination_key(mut self, key: &str) -> Self {
+        self.destination_key = Some(key.to_string());
+        self
+    }
+
+    /// Set the error returned by the destination
+    pub fn with_error(mut self, error: impl ToString) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self> {
+        let status: String = row.try_get("status")?;
+
+        Ok(Self {
+            id: Some(row.try_get("id")?),
+            route_id: row.try_get("route_id")?,
+            rule_id: row.try_get("rule_id")?,
+            destination_id: row.try_get("destination_id")?,
+            destination_key: row.try_get("destination_key")?,
+            record_count: row.try_get::<i64, _>("record_count")? as usize,
+            status: ReceiptStatus::parse(&status),
+            error: row.try_get("error")?,
+            duration_ms: row.try_get::<i64, _>("duration_ms")? as u64,
+            batch_receipt_id: row.try_get("batch_receipt_id")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// Filter for listing delivery receipts
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReceiptFilter {
+    /// Only receipts from this route
+    pub route_id: Option<String>,
+    /// Only receipts for this destination ID
+    pub destination_id: Option<String>,
+    /// Maximum number of receipts to return
+    pub limit: Option<i64>,
+    /// Number of receipts to skip
+    pub offset: Option<i64>,
+}
+
+/// Store of delivery receipts, kept in the SQLite database
+pub struct ReceiptStore {
+    /// Database holding the receipts
+    pool: Arc<DatabasePool>,
+}
+
+impl ReceiptStore {
+    /// Create a new receipt store
+    pub fn new(pool: Arc<DatabasePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Store a receipt; returns its ID
+    pub async fn record(&self, receipt: &DeliveryReceipt) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO delivery_receipts
+                (route_id, rule_id, destination_id, destination_key, record_count, status, error, duration_ms, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&receipt.route_id)
+        .bind(&receipt.rule_id)
+        .bind(&receipt.destination_id)
+        .bind(&receipt.destination_key)
+        .bind(receipt.record_count as i64)
+        .bind(receipt.status.as_str())
+        .bind(&receipt.error)
+        .bind(receipt.duration_ms as i64)
+        .bind(receipt.created_at)
+        .execute(self.pool.as_ref())
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Settle queued receipts with the outcome of the delivery that ended
+    /// them: its status, error and duration. They are linked to the
+    /// delivery's receipt if it was stored.
+    ///
+    /// Receipts that were already settled are left as they are.
+    pub async fn settle(&self, queued: &[i64], outcome: &DeliveryReceipt) -> Result<()> {
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        for id in queued {
+            sqlx::query(
+                "UPDATE delivery_receipts SET status = ?, error = ?, duration_ms = ?, batch_receipt_id = ?
+                 WHERE id = ? AND status = 'queued'"
+            )
+            .bind(outcome.status.as_str())
+            .bind(&outcome.error)
+            .bind(outcome.duration_ms as i64)
+            .bind(outcome.id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Store the receipt of a batch and settle the queued receipts of its
+    /// records with it; returns its ID
+    pub async fn record_batch(&self, receipt: &DeliveryReceipt, queued: &[i64]) -> Result<i64> {
+        let id = self.record(receipt).await?;
+
+        let mut outcome = receipt.clone();
+        outcome.id = Some(id);
+        self.settle(queued, &outcome).await?;
+
+        Ok(id)
+    }
+
+    /// List receipts, newest first
+    pub async fn list(&self, filter: &ReceiptFilter) -> Result<Vec<DeliveryReceipt>> {
+        let rows = sqlx::query(
+            "SELECT * FROM delivery_receipts
+             WHERE (?1 IS NULL OR route_id = ?1)
+               AND (?2 IS NULL OR destination_id = ?2)
+             ORDER BY id DESC
+             LIMIT ?3 OFFSET ?4"
+        )
+        .bind(&filter.route_id)
+        .bind(&filter.destination_id)
+        .bind(filter.limit.unwrap_or(100))
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        rows.iter().map(DeliveryReceipt::from_row).collect()
+    }
+
+    /// Get a receipt by ID
+    pub async fn get(&self, id: i64) -> Result<Option<DeliveryReceipt>> {
+        let row = sqlx::query("SELECT * FROM delivery_receipts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool.as_ref())
+            .await?;
+
+        row.as_ref().map(DeliveryReceipt::from_row).transpose()
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::router::{Destination, DestinationDefaults, DestinationFactory, DestinationSettings, Route};
+use crate::storage::{DatabasePool, DestinationRecord};
+
+/// Error for removing or disabling a shared destination that running
+/// routes still deliver to
+#[derive(Debug, thiserror::Error)]
+#[error("Destination {id} is used by routes {}", routes.join(", "))]
+pub struct DestinationInUse {
+    /// ID of the destination
+    pub id: String,
+    /// IDs of the routes using it
+    pub routes: Vec<String>,
+}
+
+/// A destination in the registry
+#[derive(Clone)]
+struct Registered {
+    record: DestinationRecord,
+    settings: DestinationSettings,
+    destination: Arc<dyn Destination>,
+}
+
+/// Shared destinations with stable, user-chosen IDs.
+///
+/// Destinations are defined once in the `destinations` table and the same
+/// instance is used by every route and rule that refers to it by ID.
+pub struct DestinationRegistry {
+    /// Database holding the destination definitions
+    pool: Arc<DatabasePool>,
+    /// Enabled destinations, by ID
+    destinations: RwLock<HashMap<String, Registered>>,
+    /// Router-wide defaults destinations are created with
+    defaults: DestinationDefaults,
+}
+
+impl DestinationRegistry {
+    /// Create a registry holding every enabled destination in the database,
+    /// created with the router-wide `defaults`
+    pub async fn load(pool: Arc<DatabasePool>, defaults: DestinationDefaults) -> Result<Self> {
+        let records: Vec<DestinationRecord> = sqlx::query_as(
+            "SELECT * FROM destinations WHERE enabled = true ORDER BY id"
+        )
+        .fetch_all(pool.as_ref())
+        .await?;
+
+        let mut destinations = HashMap::new();
+        for record in records {
+            // One broken definition shouldn't take the others down
+            match Self::build(&record, &defaults) {
+                Ok((settings, destination)) => {
+                    destinations.insert(record.id.clone(), Registered { record, settings, destination });
+                },
+                Err(e) => tracing::error!("Failed to load destination {}: {}", record.id, e),
+            }
+        }
+
+        tracing::info!("Loaded {} shared destinations", destinations.len());
+
+        Ok(Self {
+            pool,
+            destinations: RwLock::new(destinations),
+            defaults,
+        })
+    }
+
+    /// Router-wide defaults the registry creates destinations with; routes
+    /// create their own destinations with them too
+    pub fn defaults(&self) -> &DestinationDefaults {
+        &self.defaults
+    }
+
+    /// Create the destination a record describes, with the settings it was created from
+    fn build(record: &DestinationRecord, defaults: &DestinationDefaults) -> Result<(DestinationSettings, Arc<dyn Destination>)> {
+        let settings = DestinationSettings {
+            id: Some(record.id.clone()),
+            destination_type: record.destination_type.clone(),
+            config: record.config_json()?,
+            timeout: record.timeout.map(|timeout| timeout.max(1) as u64),
+            batch: record.batch_settings()?,
+        };
+
+        let destination = DestinationFactory::create_destination_with_defaults(&settings, defaults)?;
+        Ok((settings, destination))
+    }
+
+    /// Add or replace a destination.
+    ///
+    /// The destination is created before it is stored, so an invalid
+    /// configuration is rejected without changing the registry. Routes
+    /// already built keep the previous instance until they are rebuilt;
+    /// [`RouterIntegration::put_destination`](crate::router::RouterIntegration::put_destination)
+    /// rebuilds them.
+    pub async fn register(&self, record: DestinationRecord) -> Result<()> {
+        if record.id.trim().is_empty() {
+            return Err(anyhow!("Destination ID can't be empty"));
+        }
+
+        let (settings, destination) = Self::build(&record, &self.defaults)?;
+
+        sqlx::query(
+            "INSERT INTO destinations (id, name, destination_type, config, enabled, timeout, batch, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name,
+                destination_type = excluded.destination_type,
+                config = excluded.config,
+                enabled = excluded.enabled,
+                timeout = excluded.timeout,
+                batch = excluded.batch,
+                updated_at = excluded.updated_at"
+        )
+        .bind(&record.id)
+        .bind(&record.name)
+        .bind(&record.destination_type)
+        .bind(&record.config)
+        .bind(record.enabled)
+        .bind(record.timeout)
+        .bind(&record.batch)
+        .bind(record.created_at)
+        .bind(Utc::now())
+        .execute(self.pool.as_ref())
+        .await?;
+
+        let mut destinations = self.write();
+        if record.enabled {
+            destinations.insert(record.id.clone(), Registered { record, settings, destination });
+        } else {
+            destinations.remove(&record.id);
+        }
+
+        Ok(())
+    }
+
+    /// Remove a destination; returns whether it existed
+    pub async fn remove(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM destinations WHERE id = ?")
+            .bind(id)
+            .execute(self.pool.as_ref())
+            .await?;
+
+        self.write().remove(id);
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get an enabled destination by ID
+    pub fn get(&self, id: &str) -> Option<Arc<dyn Destination>> {
+        self.read().get(id).map(|registered| Arc::clone(&registered.destination))
+    }
+
+    /// Get an enabled destination by ID, with the settings it was created from
+    pub fn get_with_settings(&self, id: &str) -> Option<(Arc<dyn Destination>, DestinationSettings)> {
+        self.read().get(id).map(|registered| (Arc::clone(&registered.destination), registered.settings.clone()))
+    }
+
+    /// Get the stored definition of a destination, enabled or not
+    pub async fn find(&self, id: &str) -> Result<Option<DestinationRecord>> {
+        let record = sqlx::query_as("SELECT * FROM destinations WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool.as_ref())
+            .await?;
+
+        Ok(record)
+    }
+
+    /// Definitions of all enabled destinations, sorted by ID
+    pub fn list(&self) -> Vec<DestinationRecord> {
+        let mut records: Vec<_> = self.read().values().map(|registered| registered.record.clone()).collect();
+        records.sort_by(|a, b| a.id.cmp(&b.id));
+        records
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Registered>> {
+        match self.destinations.read() {
+            Ok(destinations) => destinations,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Registered>> {
+        match self.destinations.write() {
+            Ok(destinations) => destinations,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Routes that are running, by route ID
+#[derive(Default)]
+pub struct RouteRegistry {
+    routes: RwLock<HashMap<String, Arc<Route>>>,
+}
+
+impl RouteRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a route, replacing any route with the same ID
+    pub fn insert(&self, route: Route) -> Arc<Route> {
+        let route = Arc::new(route);
+        let mut routes = match self.routes.write() {
+            Ok(routes) => routes,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        routes.insert(route.config.id.clone(), Arc::clone(&route));
+        route
+    }
+
+    /// Get a route by ID
+    pub fn get(&self, id: &str) -> Option<Arc<Route>> {
+        match self.routes.read() {
+            Ok(routes) => routes.get(id).cloned(),
+            Err(poisoned) => poisoned.into_inner().get(id).cloned(),
+        }
+    }
+
+    /// All routes
+    pub fn list(&self) -> Vec<Arc<Route>> {
+        match self.routes.read() {
+            Ok(routes) => routes.values().cloned().collect(),
+            Err(poisoned) => poisoned.into_inner().values().cloned().collect(),
+        }
+    }
+}
@@ -1,13 +1,28 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::router::{
-    Destination, Router, RouterData, RoutingRule, DestinationFactory,
-    evaluate_rule, apply_transformations, RoutingResult
+    BreakerRegistry, DeadLetter, DeadLetterStore, DedupStore, DeliveryMetrics, DeliveryOrigin, Destination, DeliveryQueue, FailureStage, FanOutSettings, PartialDelivery, Router,
+    RateLimiter, ReceiptStore, RouterData, RoutingRule, RoutingStatus, DestinationFactory, DestinationRegistry, RouterFactory,
+    evaluate_condition, evaluate_rule, explain_condition, apply_transformations, RoutingResult,
+    TransformationStep, DryRunReport, RuleTrace, trace_transformations
 };
 
+/// A route as defined in the configuration: its settings and rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteDefinition {
+    /// Route settings
+    #[serde(flatten)]
+    pub config: RouterData,
+    /// Routing rules
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+}
+
 /// A route defines how data is processed and where it goes
 pub struct Route {
     /// The router configuration
@@ -16,129 +31,667 @@ pub struct Route {
     pub router: Router,
     /// The routing rules
     pub rules: Vec<RoutingRule>,
-    /// The destination map (id to destination)
+    /// The destination map (ID to destination)
     destination_map: HashMap<String, Arc<dyn Destination>>,
+    /// Destination for records that couldn't be routed
+    error_destination: Option<Arc<dyn Destination>>,
+    /// Store for records that couldn't be routed
+    dead_letters: Option<Arc<DeadLetterStore>>,
+    /// Store for the keys of records already seen
+    dedup: Option<Arc<DedupStore>>,
+    /// Rate limiters of the rules that have one, by rule ID
+    rate_limiters: HashMap<String, RateLimiter>,
 }
 
 impl Route {
-    /// Create a new route from configuration
+    /// Create a new route from configuration.
+    ///
+    /// Rules may only refer to the route's own destinations; use
+    /// [`Route::from_registry`] to refer to shared destinations.
+    #[cfg(test)]
     pub fn new(config: RouterData, rules: Vec<RoutingRule>) -> Result<Self> {
-        // Create destinations from config
-        let destinations = DestinationFactory::create_destinations(&config.destinations)?;
-        
-        // Create a map of destination ids to destinations
-        let mut destination_map = HashMap::new();
-        for (i, dest) in destinations.iter().enumerate() {
-            let id = if i < config.destinations.len() {
-                match &config.destinations[i].destination_type {
-                    dest_type => format!("{}_{}", dest_type, dest.get_id()),
-                }
-            } else {
-                dest.get_id().to_string()
-            };
-            
-            destination_map.insert(id, Arc::clone(dest));
+        Self::build(config, rules, None)
+    }
+    
+    /// Create a new route whose rules may refer to shared destinations
+    pub fn from_registry(config: RouterData, rules: Vec<RoutingRule>, registry: &DestinationRegistry) -> Result<Self> {
+        Self::build(config, rules, Some(registry))
+    }
+    
+    fn build(config: RouterData, rules: Vec<RoutingRule>, registry: Option<&DestinationRegistry>) -> Result<Self> {
+        if let Some(dedup) = &config.dedup {
+            dedup.validate()?;
         }
         
+        for rule in &rules {
+            rule.validate()?;
+        }
+        
+        let rate_limiters = rules.iter()
+            .filter_map(|rule| {
+                rule.rate_limit.as_ref().map(|limit| (rule.id.clone(), RateLimiter::new(&rule.id, limit.clone())))
+            })
+            .collect();
+        
+        // Create the route's own destinations and the shared ones it refers to
+        let (destinations, settings) = RouterFactory::create_route_destinations(&config, &rules, registry)?;
+        
+        // Rules refer to destinations by ID
+        let destination_map = destinations.iter()
+            .map(|dest| (dest.get_id().to_string(), Arc::clone(dest)))
+            .collect();
+        
         // Create the router
-        let router = Router::new(destinations);
+        let router = Router::new(destinations)
+            .with_keys(&config.id)
+            .with_timeouts(&settings);
+        
+        // Create the error destination, if configured
+        let error_destination = match config.error_handling.as_ref().and_then(|e| e.error_destination.as_ref()) {
+            Some(settings) => {
+                let defaults = registry.map(|registry| registry.defaults().clone()).unwrap_or_default();
+                Some(DestinationFactory::create_destination_with_defaults(settings, &defaults)?)
+            },
+            None => None,
+        };
         
         Ok(Self {
             config,
             router,
             rules,
             destination_map,
+            error_destination,
+            dead_letters: None,
+            dedup: None,
+            rate_limiters,
         })
     }
     
-    /// Process data through the route
+    /// Set the concurrency limit and default timeout for sending
+    pub fn with_fan_out(mut self, settings: FanOutSettings) -> Self {
+        self.router = self.router.with_fan_out(settings);
+        self
+    }
+    
+    /// Guard the destinations with circuit breakers from the registry.
+    ///
+    /// Call this before [`Route::with_queue`] so queued deliveries are
+    /// guarded too.
+    pub fn with_breakers(mut self, registry: &BreakerRegistry) -> Self {
+        self.router = self.router.with_breakers(registry, &self.config.id);
+        self
+    }
+    
+    /// Store records that couldn't be routed so they can be re-driven
+    pub fn with_dead_letters(mut self, store: Arc<DeadLetterStore>) -> Self {
+        if let Some(destination) = &self.error_destination {
+            store.register_error_destination(&self.config.id, Arc::clone(destination));
+        }
+        
+        self.router = self.router.with_dead_letters(&store, &self.config.id);
+        self.dead_letters = Some(store);
+        self
+    }
+    
+    /// Send the summaries rules' rate limits are holding back, then deliver
+    /// the records the route's destinations are holding in batches.
+    ///
+    /// Call this when a run ends and before shutting down, so nothing is
+    /// held back until the next record.
+    pub async fn flush(&self) {
+        if let Err(e) = self.flush_rate_limits().await {
+            tracing::error!("Failed to send rate limit summaries for route {}: {}", self.config.id, e);
+        }
+        
+        self.router.flush().await;
+    }
+    
+    /// Whether the route delivers to this destination instance
+    pub fn uses(&self, destination: &Arc<dyn Destination>) -> bool {
+        self.destination_map.values().any(|used| Arc::ptr_eq(used, destination))
+    }
+    
+    /// Drop duplicate records using keys kept in the store.
+    ///
+    /// Has no effect unless the route configures `dedup`.
+    pub fn with_dedup(mut self, store: Arc<DedupStore>) -> Self {
+        self.dedup = Some(store);
+        self
+    }
+    
+    /// Record a delivery receipt for every batch sent to a destination.
+    ///
+    /// Call this after [`Route::with_breakers`] so receipts name the
+    /// destination keys.
+    pub fn with_receipts(mut self, store: Arc<ReceiptStore>) -> Self {
+        self.router = self.router.with_receipts(store, &self.config.id);
+        self
+    }
+    
+    /// Record direct sends in delivery metrics, labelled with this route's ID
+    pub fn with_metrics(mut self, metrics: Arc<DeliveryMetrics>) -> Self {
+        self.router = self.router.with_metrics(metrics, &self.config.id);
+        self
+    }
+    
+    /// Deliver through a durable queue, keyed by this route's ID
+    pub fn with_queue(mut self, queue: Arc<DeliveryQueue>) -> Self {
+        self.router = self.router.with_queue(queue, &self.config.id);
+        self
+    }
+    
+    /// Pass the source connector's metadata on to every destination
+    pub fn apply_source_metadata(&self, metadata: &Value) {
+        for destination in &self.router.destinations {
+            destination.set_source_metadata(metadata);
+        }
+    }
+    
+    /// A destination rules of this route can send to, by ID
+    #[cfg(test)]
+    pub fn destination(&self, id: &str) -> Option<&Arc<dyn Destination>> {
+        self.destination_map.get(id)
+    }
+    
+    /// Every destination rules of this route can send to, in order
+    pub fn destinations(&self) -> &[Arc<dyn Destination>] {
+        &self.router.destinations
+    }
+    
+    /// Whether an error aborts the route instead of skipping the record
+    fn fails_on_error(&self) -> bool {
+        self.config.error_handling.as_ref().is_some_and(|e| e.fails_on_error())
+    }
+    
+    /// Hand a record that couldn't be routed to the dead letter store, or
+    /// straight to the error destination when there is no store
+    async fn dead_letter(&self, letter: DeadLetter) {
+        let result = match (&self.dead_letters, &self.error_destination) {
+            (Some(store), _) => store.publish(letter).await.map(|_| ()),
+            (None, Some(destination)) => match serde_json::to_value(&letter) {
+                Ok(envelope) => destination.send(envelope).await,
+                Err(e) => Err(e.into()),
+            },
+            (None, None) => return,
+        };
+        
+        if let Err(e) = result {
+            tracing::error!("Failed to dead-letter record for route {}: {}", self.config.id, e);
+        }
+    }
+    
+    /// Dead-letter a failed record, then either abort the route or skip it
+    async fn handle_failure(&self, letter: DeadLetter) -> Result<()> {
+        let error = letter.error.clone();
+        self.dead_letter(letter).await;
+        self.check_abort(&error)
+    }
+    
+    /// Fail with `error` if the route aborts on errors
+    fn check_abort(&self, error: &str) -> Result<()> {
+        if self.fails_on_error() {
+            Err(anyhow!("Route {} failed: {}", self.config.id, error))
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// A record's dedup key, if the route deduplicates and the record has one
+    fn dedup_key(&self, data: &Value) -> Option<String> {
+        self.dedup.as_ref()?;
+        
+        match self.config.dedup.as_ref()?.key(data) {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::warn!("Failed to compute dedup key for route {}: {}", self.config.id, e);
+                None
+            }
+        }
+    }
+    
+    /// Claim a record's key for the dedup window; returns `Some(false)`
+    /// if the route already delivered a record with the same key, or
+    /// `None` if the route doesn't deduplicate or the store failed.
+    ///
+    /// A failing store lets records through rather than dropping them.
+    async fn claim(&self, key: &str) -> Option<bool> {
+        let (settings, store) = match (&self.config.dedup, &self.dedup) {
+            (Some(settings), Some(store)) => (settings, store),
+            _ => return None,
+        };
+        
+        match store.claim(&self.config.id, key, Duration::from_secs(settings.window)).await {
+            Ok(claimed) => Some(claimed),
+            Err(e) => {
+                tracing::warn!("Dedup check failed for route {}, letting record through: {}", self.config.id, e);
+                None
+            }
+        }
+    }
+    
+    /// Give up the claim on the key of a record no destination accepted,
+    /// so it is let through again when it's retried
+    async fn release(&self, key: &str) {
+        if let Some(store) = &self.dedup {
+            if let Err(e) = store.release(&self.config.id, key).await {
+                tracing::warn!("Failed to release dedup key for route {}: {}", self.config.id, e);
+            }
+        }
+    }
+    
+    /// Send a record to the given destinations concurrently, dead-lettering
+    /// it for each one that fails
+    async fn fan_out(
+        &self,
+        targets: &[(String, Arc<dyn Destination>)],
+        rule_id: &str,
+        original: &Value,
+        data: &Value,
+    ) -> Result<RoutingResult> {
+        let destinations: Vec<_> = targets.iter().map(|(_, dest)| Arc::clone(dest)).collect();
+        let origin = DeliveryOrigin { rule_id, record: original };
+        let outcomes = self.router.fan_out(&destinations, std::slice::from_ref(data), Some(origin)).await;
+        
+        let mut routed_to = Vec::new();
+        let mut failures = Vec::new();
+        let mut receipt_ids = Vec::new();
+        let (mut records_delivered, mut records_failed) = (0, 0);
+        
+        for ((dest_id, _), delivery) in targets.iter().zip(outcomes) {
+            let (dest, result) = (delivery.destination, delivery.result);
+            receipt_ids.extend(delivery.receipt_id);
+            
+            let failed = PartialDelivery::failed_count(1, &result);
+            records_delivered += 1 - failed;
+            records_failed += failed;
+            
+            let e = match result {
+                Ok(()) => {
+                    routed_to.push(dest_id.clone());
+                    continue;
+                },
+                Err(e) => e,
+            };
+            
+            tracing::error!("Failed to send data to destination {}: {}", dest.get_id(), e);
+            
+            let mut letter = DeadLetter::new(&self.config.id, FailureStage::Delivery, original.clone(), &e)
+                .with_rule(rule_id)
+                .with_payload(data.clone());
+            if let Some(key) = self.router.destination_key(&dest) {
+                letter = letter.with_destination(key);
+            }
+            
+            self.dead_letter(letter).await;
+            failures.push((dest_id.clone(), e.to_string()));
+        }
+        
+        let error = (!failures.is_empty()).then(|| {
+            failures.iter()
+                .map(|(id, e)| format!("{}: {}", id, e))
+                .collect::<Vec<_>>()
+                .join("; ")
+        });
+        
+        // Every destination was tried before aborting
+        if let Some(error) = &error {
+            self.check_abort(error)?;
+        }
+        
+        let mut result = RoutingResult::new(rule_id, RoutingStatus::from_failures(targets.len(), failures))
+            .with_matched(true)
+            .with_routed_to(routed_to)
+            .with_record_counts(records_delivered, records_failed)
+            .with_receipt_ids(receipt_ids);
+        if let Some(error) = error {
+            result = result.with_error(error);
+        }
+        
+        Ok(result)
+    }
+    
+    /// The route's transformations as steps
+    fn transformation_steps(&self) -> Vec<TransformationStep> {
+        self.config.transformations.iter()
+            .map(|t| TransformationStep {
+                transformation_type: t.transformation_type.clone(),
+                params: t.params.clone(),
+            })
+            .collect()
+    }
+    
+    /// The destinations a rule routes to
+    fn rule_targets(&self, rule: &RoutingRule) -> Vec<(String, Arc<dyn Destination>)> {
+        rule.destination_ids.iter()
+            .filter_map(|id| self.destination_map.get(id).map(|dest| (id.clone(), Arc::clone(dest))))
+            .collect()
+    }
+    
+    /// Send summaries of the records rules' rate limits have held back so
+    /// far; [`Route::flush`] does this when a run ends.
+    pub async fn flush_rate_limits(&self) -> Result<Vec<RoutingResult>> {
+        let mut results = Vec::new();
+        
+        for rule in self.ordered_rules() {
+            let summary = self.rate_limiters.get(&rule.id).and_then(|limiter| limiter.take_summary(true));
+            if let Some(summary) = summary {
+                results.push(self.fan_out(&self.rule_targets(rule), &rule.id, &summary, &summary).await?);
+            }
+        }
+        
+        Ok(results)
+    }
+    
+    /// Rules in the order they are applied: enabled ones by priority
+    fn ordered_rules(&self) -> Vec<&RoutingRule> {
+        let mut rules: Vec<_> = self.rules.iter().filter(|rule| rule.enabled).collect();
+        rules.sort_by_key(|rule| rule.priority);
+        rules
+    }
+    
+    /// Run a record through the route's transformations, condition and
+    /// rules without sending it anywhere, explaining each step
+    pub fn dry_run(&self, data: Value) -> DryRunReport {
+        let mut report = DryRunReport {
+            route_id: self.config.id.clone(),
+            input: data.clone(),
+            transformations: Vec::new(),
+            condition: None,
+            route_matched: false,
+            rules: Vec::new(),
+            destinations: Vec::new(),
+            error: None,
+        };
+        
+        let (transformations, processed) = trace_transformations(&self.transformation_steps(), data);
+        report.transformations = transformations;
+        
+        let processed = match processed {
+            Some(processed) => processed,
+            None => {
+                report.error = Some("A route transformation failed".to_string());
+                return report;
+            }
+        };
+        
+        if let Some(condition) = &self.config.condition {
+            match explain_condition(condition, &processed) {
+                Ok(trace) => {
+                    let matched = trace.result && !trace.has_error();
+                    report.condition = Some(trace);
+                    if !matched {
+                        return report;
+                    }
+                },
+                Err(e) => {
+                    report.error = Some(format!("Invalid route condition: {}", e));
+                    return report;
+                }
+            }
+        }
+        
+        report.route_matched = true;
+        
+        if self.rules.is_empty() {
+            let mut destinations: Vec<_> = self.destination_map.keys().cloned().collect();
+            destinations.sort();
+            report.destinations = destinations;
+            return report;
+        }
+        
+        for rule in self.ordered_rules() {
+            let mut trace = RuleTrace {
+                rule_id: rule.id.clone(),
+                priority: rule.priority,
+                matched: true,
+                condition: None,
+                sampled: None,
+                transformations: Vec::new(),
+                destinations: Vec::new(),
+                stopped: false,
+                error: None,
+            };
+            
+            if let Some(condition) = &rule.condition {
+                match explain_condition(condition, &processed) {
+                    Ok(condition) => {
+                        trace.matched = condition.result && !condition.has_error();
+                        trace.condition = Some(condition);
+                    },
+                    Err(e) => {
+                        trace.matched = false;
+                        trace.error = Some(e.to_string());
+                    },
+                }
+            }
+            
+            if let (true, Some(sampling)) = (trace.matched, &rule.sampling) {
+                match sampling.samples(&processed) {
+                    Ok(sampled) => {
+                        trace.matched = sampled;
+                        trace.sampled = Some(sampled);
+                    },
+                    Err(e) => {
+                        trace.matched = false;
+                        trace.error = Some(e.to_string());
+                    },
+                }
+            }
+            
+            if trace.matched {
+                let (transformations, output) = trace_transformations(&rule.transformations, processed.clone());
+                trace.transformations = transformations;
+                
+                if output.is_some() {
+                    trace.destinations = rule.destination_ids.iter()
+                        .filter(|id| self.destination_map.contains_key(*id))
+                        .cloned()
+                        .collect();
+                } else {
+                    trace.error = Some("A rule transformation failed".to_string());
+                }
+                
+                trace.stopped = rule.stop_on_match;
+            }
+            
+            for destination in &trace.destinations {
+                if !report.destinations.contains(destination) {
+                    report.destinations.push(destination.clone());
+                }
+            }
+            
+            let stopped = trace.stopped;
+            report.rules.push(trace);
+            if stopped {
+                break;
+            }
+        }
+        
+        report
+    }
+    
+    /// Process data through the route.
+    ///
+    /// Records whose transformations or deliveries fail are wrapped in a
+    /// [`DeadLetter`] and sent to the dead letter store or error destination.
+    /// With `on_error: fail` the route then returns an error; otherwise the
+    /// record (or the failed destination) is skipped and the error recorded
+    /// in the results.
+    ///
+    /// Records that don't match the route's condition, checked after the
+    /// route's transformations, go nowhere. So do records with the dedup key of
+    /// a record the route delivered within the dedup window; they are counted
+    /// in `duplicates_dropped`. Rules are applied in priority order; a matching
+    /// rule with `stop_on_match` ends the evaluation.
+    ///
+    /// A rule with sampling only matches the records in its sample. Records
+    /// over a rule's rate limit are counted in `rate_limited` and not sent;
+    /// with `on_limit: summarize` a summary record is sent to the rule's
+    /// destinations once the window is over.
     pub async fn process(&self, data: Value) -> Result<Vec<RoutingResult>> {
         let mut results = Vec::new();
         
         // Apply transformations if any
-        let mut processed_data = data;
+        let mut processed_data = data.clone();
         
         if !self.config.transformations.is_empty() {
-            let transformations = self.config.transformations.iter()
-                .map(|t| crate::router::TransformationStep {
-                    transformation_type: t.transformation_type.clone(),
-                    params: t.params.clone(),
-                })
-                .collect::<Vec<_>>();
+            let transformations = self.transformation_steps();
             
-            processed_data = apply_transformations(&transformations, processed_data)?;
+            processed_data = match apply_transformations(&transformations, processed_data) {
+                Ok(transformed) => transformed,
+                Err(e) => {
+                    let letter = DeadLetter::new(&self.config.id, FailureStage::Transformation, data, &e);
+                    self.handle_failure(letter).await?;
+                    
+                    results.push(RoutingResult::new("default", RoutingStatus::Failure(e.to_string())).with_error(e));
+                    return Ok(results);
+                }
+            };
+        }
+        
+        // The route's condition gates every destination
+        if let Some(condition) = &self.config.condition {
+            let matched = match evaluate_condition(condition, &processed_data) {
+                Ok(matched) => matched,
+                Err(e) => {
+                    let letter = DeadLetter::new(&self.config.id, FailureStage::Condition, data, &e);
+                    self.handle_failure(letter).await?;
+                    
+                    results.push(RoutingResult::new("condition", RoutingStatus::Failure(e.to_string())).with_error(e));
+                    return Ok(results);
+                }
+            };
+            
+            if !matched {
+                results.push(RoutingResult::new("condition", RoutingStatus::Success));
+                return Ok(results);
+            }
+        }
+        
+        // Drop records the route has already delivered; the key is claimed
+        // before delivery, so concurrent duplicates are dropped too
+        let claimed = match self.dedup_key(&processed_data) {
+            Some(key) => match self.claim(&key).await {
+                Some(false) => {
+                    results.push(RoutingResult::new("dedup", RoutingStatus::Success).with_duplicates_dropped(1));
+                    return Ok(results);
+                },
+                Some(true) => Some(key),
+                None => None,
+            },
+            None => None,
+        };
+        
+        let routed = self.apply_rules(&data, &processed_data, &mut results).await;
+        
+        // Only a record some destination accepted (or queued) counts as
+        // seen, so one that was rate-limited, matched no rule or failed
+        // everywhere is let through again when it's retried
+        if let Some(key) = claimed {
+            if !matches!(routed, Ok(true)) {
+                self.release(&key).await;
+            }
         }
         
+        routed?;
+        Ok(results)
+    }
+    
+    /// Apply the route's rules to a record, or send it to every destination
+    /// if the route has none, adding a result for each send to `results`.
+    ///
+    /// Returns whether any destination accepted the record.
+    async fn apply_rules(&self, data: &Value, processed_data: &Value, results: &mut Vec<RoutingResult>) -> Result<bool> {
+        let mut accepted = false;
+        
         // Apply rules or use default routing if no rules
         if self.rules.is_empty() {
             // Default routing: send to all destinations
-            self.router.route(processed_data.clone()).await?;
+            let mut targets: Vec<_> = self.destination_map.iter()
+                .map(|(id, dest)| (id.clone(), Arc::clone(dest)))
+                .collect();
+            targets.sort_by(|a, b| a.0.cmp(&b.0));
             
-            // Record the result
-            results.push(RoutingResult {
-                rule_id: "default".to_string(),
-                matched: true,
-                routed_to: self.destination_map.keys().cloned().collect(),
-                error: None,
-            });
+            let result = self.fan_out(&targets, "default", data, processed_data).await?;
+            accepted |= result.records_delivered > 0;
+            results.push(result);
         } else {
-            // Apply each rule in priority order
-            let mut sorted_rules = self.rules.clone();
-            sorted_rules.sort_by_key(|r| r.priority);
-            
-            for rule in sorted_rules {
-                // Skip disabled rules
-                if !rule.enabled {
-                    continue;
-                }
-                
+            // Apply each enabled rule in priority order
+            for rule in self.ordered_rules() {
                 // Check if the rule matches
-                let matched = match evaluate_rule(&rule, &processed_data) {
+                let matched = match evaluate_rule(rule, processed_data) {
                     Ok(m) => m,
                     Err(e) => {
-                        results.push(RoutingResult {
-                            rule_id: rule.id.clone(),
-                            matched: false,
-                            routed_to: Vec::new(),
-                            error: Some(e.to_string()),
-                        });
+                        let letter = DeadLetter::new(&self.config.id, FailureStage::Condition, data.clone(), &e)
+                            .with_rule(&rule.id);
+                        self.handle_failure(letter).await?;
+                        
+                        results.push(RoutingResult::new(&rule.id, RoutingStatus::Failure(e.to_string())).with_error(e));
                         continue;
                     }
                 };
                 
                 if matched {
+                    if let Some(limiter) = self.rate_limiters.get(&rule.id) {
+                        // Summarize the previous window before counting this record
+                        if let Some(summary) = limiter.take_summary(false) {
+                            results.push(self.fan_out(&self.rule_targets(rule), &rule.id, &summary, &summary).await?);
+                        }
+                        
+                        if !limiter.admit() {
+                            results.push(
+                                RoutingResult::new(&rule.id, RoutingStatus::Success)
+                                    .with_matched(true)
+                                    .with_rate_limited(1),
+                            );
+                            
+                            if rule.stop_on_match {
+                                break;
+                            }
+                            continue;
+                        }
+                    }
+                    
                     // Apply rule-specific transformations
                     let mut rule_data = processed_data.clone();
                     
                     if !rule.transformations.is_empty() {
-                        rule_data = apply_transformations(&rule.transformations, rule_data)?;
+                        rule_data = match apply_transformations(&rule.transformations, rule_data) {
+                            Ok(transformed) => transformed,
+                            Err(e) => {
+                                let letter = DeadLetter::new(&self.config.id, FailureStage::Transformation, data.clone(), &e)
+                                    .with_rule(&rule.id);
+                                self.handle_failure(letter).await?;
+                                
+                                results.push(
+                                    RoutingResult::new(&rule.id, RoutingStatus::Failure(e.to_string()))
+                                        .with_matched(true)
+                                        .with_error(e),
+                                );
+                                
+                                if rule.stop_on_match {
+                                    break;
+                                }
+                                continue;
+                            }
+                        };
                     }
                     
                     // Route to specified destinations
-                    let mut routed_to = Vec::new();
-                    let mut error = None;
+                    let targets = self.rule_targets(rule);
                     
-                    for dest_id in &rule.destination_ids {
-                        if let Some(dest) = self.destination_map.get(dest_id) {
-                            if let Err(e) = dest.send(rule_data.clone()).await {
-                                error = Some(e.to_string());
-                            } else {
-                                routed_to.push(dest_id.clone());
-                            }
-                        }
-                    }
+                    let result = self.fan_out(&targets, &rule.id, data, &rule_data).await?;
+                    accepted |= result.records_delivered > 0;
+                    results.push(result);
                     
-                    // Record the result
-                    results.push(RoutingResult {
-                        rule_id: rule.id.clone(),
-                        matched: true,
-                        routed_to,
-                        error,
-                    });
+                    // First match wins
+                    if rule.stop_on_match {
+                        break;
+                    }
                 }
             }
         }
         
-        Ok(results)
+        Ok(accepted)
     }
-} 
\ No newline at end of file
+}
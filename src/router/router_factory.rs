@@ -1,34 +1,66 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use crate::router::{Router, RouterData, DestinationFactory};
+use crate::router::{
+    Destination, DestinationRegistry, DestinationSettings, RouterData, RoutingRule, DestinationFactory
+};
+
+/// A route's destinations, alongside the settings of each
+type RouteDestinations = (Vec<Arc<dyn Destination>>, Vec<DestinationSettings>);
 
 /// Factory for creating router instances from configuration
 pub struct RouterFactory;
 
 impl RouterFactory {
-    /// Create a new router from the provided configuration
-    pub fn create_router(config: &RouterData) -> Result<Router> {
-        // Create destinations from settings
-        let destinations = DestinationFactory::create_destinations(&config.destinations)?;
+    /// Create a route's destinations: its own destinations in order, then
+    /// the shared destinations the route or its rules refer to by ID.
+    ///
+    /// Returns the settings of each destination alongside, in the same
+    /// order. Own destinations without an ID are named after their type and
+    /// position, so they keep the same ID (and delivery queue) across
+    /// restarts, and take the registry's defaults, if any. Fails if two
+    /// destinations share an ID, or if an ID is neither one of the route's
+    /// own destinations nor in the registry.
+    pub fn create_route_destinations(
+        config: &RouterData,
+        rules: &[RoutingRule],
+        registry: Option<&DestinationRegistry>,
+    ) -> Result<RouteDestinations> {
+        let mut settings: Vec<DestinationSettings> = config.destinations.iter()
+            .enumerate()
+            .map(|(position, settings)| DestinationSettings {
+                id: Some(settings.id.clone().unwrap_or_else(|| format!("{}_{}", settings.destination_type, position))),
+                ..settings.clone()
+            })
+            .collect();
         
-        // Create the router with the destinations
-        Ok(Router::new(destinations))
-    }
-    
-    /// Create multiple routers from a list of configurations
-    pub fn create_routers(configs: &[RouterData]) -> Result<Vec<Router>> {
-        let mut routers = Vec::new();
+        let defaults = registry.map(|registry| registry.defaults().clone()).unwrap_or_default();
+        let mut destinations = DestinationFactory::create_destinations_with_defaults(&settings, &defaults)?;
         
-        for config in configs {
-            // Skip disabled routers
-            if !config.enabled {
+        let mut ids = HashSet::new();
+        for destination in &destinations {
+            if !ids.insert(destination.get_id().to_string()) {
+                return Err(anyhow!("Route {} has more than one destination {}", config.id, destination.get_id()));
+            }
+        }
+        
+        let referenced = config.destination_ids.iter()
+            .chain(rules.iter().flat_map(|rule| rule.destination_ids.iter()));
+        
+        for id in referenced {
+            if ids.contains(id) {
                 continue;
             }
             
-            routers.push(Self::create_router(config)?);
+            let (destination, shared_settings) = registry.and_then(|registry| registry.get_with_settings(id))
+                .ok_or_else(|| anyhow!("Route {} refers to unknown destination {}", config.id, id))?;
+            
+            ids.insert(id.clone());
+            destinations.push(destination);
+            settings.push(shared_settings);
         }
         
-        Ok(routers)
+        Ok((destinations, settings))
     }
 } 
\ No newline at end of file
@@ -1,4 +1,5 @@
 use anyhow::{Result, anyhow};
+use serde::Serialize;
 use serde_json::Value;
 use jsonpath_lib as jsonpath;
 
@@ -15,6 +16,68 @@ pub fn evaluate_condition(condition: &str, data: &Value) -> Result<bool> {
     evaluate_parsed_condition(&parsed, data)
 }
 
+/// Explains how a condition expression evaluates against data.
+///
+/// Sub-conditions are evaluated as [`evaluate_condition`] does: `AND` and
+/// `OR` stop at the first part that decides the result or fails to
+/// evaluate, and the parts after it are marked as skipped.
+pub fn explain_condition(condition: &str, data: &Value) -> Result<ConditionTrace> {
+    if condition.is_empty() {
+        return Ok(ConditionTrace {
+            expression: String::new(),
+            result: true,
+            actual: None,
+            error: None,
+            skipped: false,
+            children: Vec::new(),
+        });
+    }
+    
+    let parsed = parse_condition(condition)?;
+    Ok(explain_parsed_condition(&parsed, data))
+}
+
+/// How a condition, and each of its sub-conditions, evaluated
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionTrace {
+    /// The (sub-)condition, as parsed
+    pub expression: String,
+    /// Whether it held
+    pub result: bool,
+    /// Value of the field it tested, for comparisons and existence checks;
+    /// absent when the field doesn't exist
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<Value>,
+    /// Why it couldn't be evaluated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Not evaluated, because an earlier sub-condition decided the result
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+    /// Traces of its sub-conditions
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ConditionTrace>,
+}
+
+impl ConditionTrace {
+    /// Trace of a sub-condition that wasn't evaluated
+    fn skipped(condition: &Condition) -> Self {
+        Self {
+            expression: condition.describe(),
+            result: false,
+            actual: None,
+            error: None,
+            skipped: true,
+            children: Vec::new(),
+        }
+    }
+
+    /// Whether any part of the condition failed to evaluate
+    pub fn has_error(&self) -> bool {
+        self.error.is_some() || self.children.iter().any(ConditionTrace::has_error)
+    }
+}
+
 /// Parsed condition
 #[derive(Debug, Clone)]
 enum Condition {
@@ -49,6 +112,55 @@ enum ComparisonOperator {
     EndsWith,
 }
 
+impl ComparisonOperator {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ComparisonOperator::Equal => "==",
+            ComparisonOperator::NotEqual => "!=",
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::GreaterThanOrEqual => ">=",
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::LessThanOrEqual => "<=",
+            ComparisonOperator::Contains => "CONTAINS",
+            ComparisonOperator::NotContains => "NOT CONTAINS",
+            ComparisonOperator::StartsWith => "STARTS WITH",
+            ComparisonOperator::EndsWith => "ENDS WITH",
+        }
+    }
+}
+
+impl Condition {
+    /// Render the condition back into the expression syntax
+    fn describe(&self) -> String {
+        match self {
+            Condition::Comparison { field, operator, value } => {
+                let value = match value {
+                    Value::String(s) => format!("'{}'", s),
+                    other => other.to_string(),
+                };
+                format!("{} {} {}", field, operator.as_str(), value)
+            },
+            Condition::And(conditions) => {
+                conditions.iter().map(Condition::describe_operand).collect::<Vec<_>>().join(" AND ")
+            },
+            Condition::Or(conditions) => {
+                conditions.iter().map(Condition::describe_operand).collect::<Vec<_>>().join(" OR ")
+            },
+            Condition::Not(condition) => format!("NOT {}", condition.describe_operand()),
+            Condition::Exists(path) => format!("EXISTS {}", path),
+        }
+    }
+
+    /// Render the condition as part of a larger one, parenthesized if it
+    /// combines conditions itself
+    fn describe_operand(&self) -> String {
+        match self {
+            Condition::And(_) | Condition::Or(_) => format!("({})", self.describe()),
+            _ => self.describe(),
+        }
+    }
+}
+
 /// Parses a condition string into a structured condition
 fn parse_condition(condition: &str) -> Result<Condition> {
     // This is a simplified parser for demonstration
@@ -78,14 +190,14 @@ fn parse_condition(condition: &str) -> Result<Condition> {
     }
     
     // Check for NOT condition
-    if condition.starts_with("NOT ") {
-        let inner = parse_condition(&condition[4..])?;
+    if let Some(inner) = condition.strip_prefix("NOT ") {
+        let inner = parse_condition(inner)?;
         return Ok(Condition::Not(Box::new(inner)));
     }
     
     // Check for EXISTS condition
-    if condition.starts_with("EXISTS ") {
-        let path = condition[7..].trim().to_string();
+    if let Some(path) = condition.strip_prefix("EXISTS ") {
+        let path = path.trim().to_string();
         return Ok(Condition::Exists(path));
     }
     
@@ -158,68 +270,74 @@ fn parse_condition(condition: &str) -> Result<Condition> {
     Err(anyhow!("Could not parse condition: {}", condition))
 }
 
+/// Selects the value of a field (a plain name or a JSONPath) from data
+pub(crate) fn select_field(field: &str, data: &Value) -> Result<Option<Value>> {
+    let selector = if field.starts_with('$') {
+        field.to_string()
+    } else {
+        format!("$.{}", field)
+    };
+    
+    let result = jsonpath::select(data, &selector)?;
+    Ok(result.first().map(|value| (*value).clone()))
+}
+
+/// Compares a field value against a condition value
+fn compare(field_value: &Value, operator: &ComparisonOperator, value: &Value) -> Result<bool> {
+    match operator {
+        ComparisonOperator::Equal => Ok(field_value == value),
+        ComparisonOperator::NotEqual => Ok(field_value != value),
+        ComparisonOperator::GreaterThan => {
+            compare_numbers(field_value, value, |a, b| a > b)
+        },
+        ComparisonOperator::GreaterThanOrEqual => {
+            compare_numbers(field_value, value, |a, b| a >= b)
+        },
+        ComparisonOperator::LessThan => {
+            compare_numbers(field_value, value, |a, b| a < b)
+        },
+        ComparisonOperator::LessThanOrEqual => {
+            compare_numbers(field_value, value, |a, b| a <= b)
+        },
+        ComparisonOperator::Contains => {
+            if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
+                Ok(field_str.contains(value_str))
+            } else {
+                Ok(false)
+            }
+        },
+        ComparisonOperator::NotContains => {
+            if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
+                Ok(!field_str.contains(value_str))
+            } else {
+                Ok(false)
+            }
+        },
+        ComparisonOperator::StartsWith => {
+            if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
+                Ok(field_str.starts_with(value_str))
+            } else {
+                Ok(false)
+            }
+        },
+        ComparisonOperator::EndsWith => {
+            if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
+                Ok(field_str.ends_with(value_str))
+            } else {
+                Ok(false)
+            }
+        },
+    }
+}
+
 /// Evaluates a parsed condition against data
 fn evaluate_parsed_condition(condition: &Condition, data: &Value) -> Result<bool> {
     match condition {
         Condition::Comparison { field, operator, value } => {
             // Use JSONPath to extract the field value
-            let selector = if field.starts_with('$') {
-                field.clone()
-            } else {
-                format!("$.{}", field)
-            };
-            
-            let result = jsonpath::select(data, &selector)?;
-            if result.is_empty() {
-                return Ok(false); // Field doesn't exist
-            }
-            
-            let field_value = &result[0];
-            
-            // Compare values based on operator
-            match operator {
-                ComparisonOperator::Equal => Ok(field_value == value),
-                ComparisonOperator::NotEqual => Ok(field_value != value),
-                ComparisonOperator::GreaterThan => {
-                    compare_numbers(field_value, value, |a, b| a > b)
-                },
-                ComparisonOperator::GreaterThanOrEqual => {
-                    compare_numbers(field_value, value, |a, b| a >= b)
-                },
-                ComparisonOperator::LessThan => {
-                    compare_numbers(field_value, value, |a, b| a < b)
-                },
-                ComparisonOperator::LessThanOrEqual => {
-                    compare_numbers(field_value, value, |a, b| a <= b)
-                },
-                ComparisonOperator::Contains => {
-                    if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
-                        Ok(field_str.contains(value_str))
-                    } else {
-                        Ok(false)
-                    }
-                },
-                ComparisonOperator::NotContains => {
-                    if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
-                        Ok(!field_str.contains(value_str))
-                    } else {
-                        Ok(false)
-                    }
-                },
-                ComparisonOperator::StartsWith => {
-                    if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
-                        Ok(field_str.starts_with(value_str))
-                    } else {
-                        Ok(false)
-                    }
-                },
-                ComparisonOperator::EndsWith => {
-                    if let (Value::String(field_str), Value::String(value_str)) = (field_value, value) {
-                        Ok(field_str.ends_with(value_str))
-                    } else {
-                        Ok(false)
-                    }
-                },
+            match select_field(field, data)? {
+                Some(field_value) => compare(&field_value, operator, value),
+                None => Ok(false), // Field doesn't exist
             }
         },
         Condition::And(conditions) => {
@@ -242,18 +360,84 @@ fn evaluate_parsed_condition(condition: &Condition, data: &Value) -> Result<bool
             Ok(!evaluate_parsed_condition(condition, data)?)
         },
         Condition::Exists(path) => {
-            let selector = if path.starts_with('$') {
-                path.clone()
-            } else {
-                format!("$.{}", path)
-            };
-            
-            let result = jsonpath::select(data, &selector)?;
-            Ok(!result.is_empty())
+            Ok(select_field(path, data)?.is_some())
         },
     }
 }
 
+/// Explains a parsed condition against data, short-circuiting like
+/// [`evaluate_parsed_condition`]
+fn explain_parsed_condition(condition: &Condition, data: &Value) -> ConditionTrace {
+    let mut trace = ConditionTrace {
+        expression: condition.describe(),
+        result: false,
+        actual: None,
+        error: None,
+        skipped: false,
+        children: Vec::new(),
+    };
+    
+    match condition {
+        Condition::Comparison { field, operator, value } => {
+            match select_field(field, data) {
+                Ok(Some(field_value)) => {
+                    match compare(&field_value, operator, value) {
+                        Ok(result) => trace.result = result,
+                        Err(e) => trace.error = Some(e.to_string()),
+                    }
+                    trace.actual = Some(field_value);
+                },
+                // A missing field fails the comparison, with no actual value
+                Ok(None) => {},
+                Err(e) => trace.error = Some(e.to_string()),
+            }
+        },
+        Condition::And(conditions) => {
+            trace.children = explain_until(conditions, data, false);
+            trace.result = trace.children.iter().all(|child| child.result && !child.has_error());
+        },
+        Condition::Or(conditions) => {
+            trace.children = explain_until(conditions, data, true);
+            trace.result = trace.children.iter().any(|child| child.result && !child.has_error());
+        },
+        Condition::Not(condition) => {
+            let child = explain_parsed_condition(condition, data);
+            trace.result = !child.result && !child.has_error();
+            trace.children.push(child);
+        },
+        Condition::Exists(path) => {
+            match select_field(path, data) {
+                Ok(value) => {
+                    trace.result = value.is_some();
+                    trace.actual = value;
+                },
+                Err(e) => trace.error = Some(e.to_string()),
+            }
+        },
+    }
+    
+    trace
+}
+
+/// Explains sub-conditions in order until one evaluates to `decisive` or
+/// fails to evaluate; the ones after it are marked as skipped
+fn explain_until(conditions: &[Condition], data: &Value, decisive: bool) -> Vec<ConditionTrace> {
+    let mut traces = Vec::with_capacity(conditions.len());
+    
+    for condition in conditions {
+        let trace = explain_parsed_condition(condition, data);
+        let done = trace.has_error() || trace.result == decisive;
+        traces.push(trace);
+        
+        if done {
+            break;
+        }
+    }
+    
+    traces.extend(conditions[traces.len()..].iter().map(ConditionTrace::skipped));
+    traces
+}
+
 /// Helper function to compare numeric values
 fn compare_numbers<F>(a: &Value, b: &Value, compare: F) -> Result<bool>
 where
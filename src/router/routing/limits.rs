@@ -0,0 +1,186 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::conditions::select_field;
+
+/// Deterministic sampling of the records a rule matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingSettings {
+    /// Field (a name or a JSONPath) whose value decides whether a record is sampled
+    pub key: String,
+    /// Fraction of key values sampled, from 0.0 to 1.0
+    pub rate: f64,
+}
+
+impl SamplingSettings {
+    /// Check that the rate is a fraction
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.rate) {
+            return Err(anyhow!("Sampling rate must be between 0 and 1, got {}", self.rate));
+        }
+        Ok(())
+    }
+
+    /// Whether a record is in the sample.
+    ///
+    /// The key value is hashed, so every record with the same value gets the
+    /// same answer. Records without the key field are never sampled.
+    pub fn samples(&self, data: &Value) -> Result<bool> {
+        let value = match select_field(&self.key, data)? {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+
+        let hash = Sha256::digest(serde_json::to_string(&value)?.as_bytes());
+        let mut bucket = [0u8; 8];
+        bucket.copy_from_slice(&hash[..8]);
+
+        Ok((u64::from_be_bytes(bucket) as f64 / u64::MAX as f64) < self.rate)
+    }
+}
+
+/// What happens to records over a rule's rate limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitAction {
+    /// Drop them
+    #[default]
+    Drop,
+    /// Drop them, then send one summary record saying how many were dropped
+    Summarize,
+}
+
+/// Limit on how many records a rule sends per window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitSettings {
+    /// Records sent per window
+    pub max_records: u64,
+    /// Length of the window, in seconds
+    #[serde(default = "default_window")]
+    pub window: u64,
+    /// What happens to records over the limit
+    #[serde(default)]
+    pub on_limit: LimitAction,
+}
+
+fn default_window() -> u64 {
+    3600
+}
+
+impl RateLimitSettings {
+    /// Check that the limit and window are positive
+    pub fn validate(&self) -> Result<()> {
+        if self.max_records == 0 {
+            return Err(anyhow!("Rate limit max_records must be at least 1"));
+        }
+        if self.window == 0 {
+            return Err(anyhow!("Rate limit window must be at least 1 second"));
+        }
+        Ok(())
+    }
+}
+
+/// Counts for the current window of a rate limiter
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    started_at: DateTime<Utc>,
+    sent: u64,
+    suppressed: u64,
+    /// Summary of the previous window, waiting to be sent
+    pending_summary: Option<Value>,
+}
+
+/// Fixed-window rate limiter for a routing rule
+pub struct RateLimiter {
+    rule_id: String,
+    settings: RateLimitSettings,
+    window: Mutex<Window>,
+}
+
+impl RateLimiter {
+    /// Create a limiter whose first window starts now
+    pub fn new(rule_id: &str, settings: RateLimitSettings) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            settings,
+            window: Mutex::new(Window {
+                started: Instant::now(),
+                started_at: Utc::now(),
+                sent: 0,
+                suppressed: 0,
+                pending_summary: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Window> {
+        let mut window = match self.window.lock() {
+            Ok(window) => window,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        // Start a new window once the current one is over
+        if window.started.elapsed() >= Duration::from_secs(self.settings.window) {
+            if let Some(summary) = self.summary(&mut window) {
+                window.pending_summary = Some(summary);
+            }
+            window.started = Instant::now();
+            window.started_at = Utc::now();
+            window.sent = 0;
+        }
+
+        window
+    }
+
+    /// Count a record against the limit; returns whether it may be sent
+    pub fn admit(&self) -> bool {
+        let mut window = self.lock();
+
+        if window.sent < self.settings.max_records {
+            window.sent += 1;
+            true
+        } else {
+            window.suppressed += 1;
+            false
+        }
+    }
+
+    /// Take the summary of records suppressed in a finished window, if one is
+    /// due. With `flush`, records suppressed so far in the current window are
+    /// summarized too.
+    pub fn take_summary(&self, flush: bool) -> Option<Value> {
+        let mut window = self.lock();
+
+        match window.pending_summary.take() {
+            Some(summary) => Some(summary),
+            None if flush => self.summary(&mut window),
+            None => None,
+        }
+    }
+
+    /// Summarize the records suppressed so far and reset the count
+    fn summary(&self, window: &mut Window) -> Option<Value> {
+        let suppressed = std::mem::take(&mut window.suppressed);
+        if suppressed == 0 || self.settings.on_limit != LimitAction::Summarize {
+            return None;
+        }
+
+        Some(json!({
+            "rule_id": self.rule_id,
+            "suppressed": suppressed,
+            "max_records": self.settings.max_records,
+            "window_seconds": self.settings.window,
+            "window_start": window.started_at.to_rfc3339(),
+            "message": format!(
+                "{} more records matched rule {} but were not sent (limit {} per {}s)",
+                suppressed, self.rule_id, self.settings.max_records, self.settings.window
+            ),
+        }))
+    }
+}
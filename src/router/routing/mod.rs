@@ -1,13 +1,17 @@
 mod conditions;
+mod limits;
 mod transformations;
 
 pub use conditions::*;
+pub use limits::*;
 pub use transformations::*;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use serde_json::Value;
 use serde::{Deserialize, Serialize};
 
+use crate::router::RoutingStatus;
+
 /// Routing rule that determines how data should be routed
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingRule {
@@ -25,6 +29,28 @@ pub struct RoutingRule {
     pub destination_ids: Vec<String>,
     /// Transformations to apply
     pub transformations: Vec<TransformationStep>,
+    /// Skip the remaining rules once this rule matches
+    #[serde(default)]
+    pub stop_on_match: bool,
+    /// Only apply the rule to a deterministic sample of matching records (optional)
+    #[serde(default)]
+    pub sampling: Option<SamplingSettings>,
+    /// Limit how many records the rule sends per window (optional)
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitSettings>,
+}
+
+impl RoutingRule {
+    /// Check the rule's sampling and rate limit settings
+    pub fn validate(&self) -> Result<()> {
+        if let Some(sampling) = &self.sampling {
+            sampling.validate().map_err(|e| anyhow!("Rule {}: {}", self.id, e))?;
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.validate().map_err(|e| anyhow!("Rule {}: {}", self.id, e))?;
+        }
+        Ok(())
+    }
 }
 
 /// Result of evaluating a routing rule
@@ -38,17 +64,101 @@ pub struct RoutingResult {
     pub routed_to: Vec<String>,
     /// Any error that occurred
     pub error: Option<String>,
+    /// Outcome across the destinations the rule routed to
+    #[serde(default)]
+    pub status: RoutingStatus,
+    /// Records dropped as duplicates of ones already seen
+    #[serde(default)]
+    pub duplicates_dropped: usize,
+    /// Records the rule matched but didn't send because of its rate limit
+    #[serde(default)]
+    pub rate_limited: usize,
+    /// Records the destinations accepted, counted once per destination
+    #[serde(default)]
+    pub records_delivered: usize,
+    /// Records the destinations rejected, counted once per destination
+    #[serde(default)]
+    pub records_failed: usize,
+    /// IDs of the delivery receipts recorded for this result
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub receipt_ids: Vec<i64>,
 }
 
-/// Evaluates a routing rule against data
+impl RoutingResult {
+    /// Create the result of a rule that didn't match and sent nothing
+    pub fn new(rule_id: &str, status: RoutingStatus) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            matched: false,
+            routed_to: Vec::new(),
+            error: None,
+            status,
+            duplicates_dropped: 0,
+            rate_limited: 0,
+            records_delivered: 0,
+            records_failed: 0,
+            receipt_ids: Vec::new(),
+        }
+    }
+
+    /// Set whether the rule matched
+    pub fn with_matched(mut self, matched: bool) -> Self {
+        self.matched = matched;
+        self
+    }
+
+    /// Set the destination IDs the data was sent to
+    pub fn with_routed_to(mut self, routed_to: Vec<String>) -> Self {
+        self.routed_to = routed_to;
+        self
+    }
+
+    /// Set the error that occurred
+    pub fn with_error(mut self, error: impl ToString) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Set the number of records dropped as duplicates
+    pub fn with_duplicates_dropped(mut self, count: usize) -> Self {
+        self.duplicates_dropped = count;
+        self
+    }
+
+    /// Set the number of records held back by the rate limit
+    pub fn with_rate_limited(mut self, count: usize) -> Self {
+        self.rate_limited = count;
+        self
+    }
+
+    /// Set the numbers of records the destinations accepted and rejected
+    pub fn with_record_counts(mut self, delivered: usize, failed: usize) -> Self {
+        self.records_delivered = delivered;
+        self.records_failed = failed;
+        self
+    }
+
+    /// Set the IDs of the delivery receipts recorded
+    pub fn with_receipt_ids(mut self, receipt_ids: Vec<i64>) -> Self {
+        self.receipt_ids = receipt_ids;
+        self
+    }
+}
+
+/// Evaluates a routing rule against data.
+///
+/// A rule with sampling only matches records in its sample.
 pub fn evaluate_rule(rule: &RoutingRule, data: &Value) -> Result<bool> {
     // If there's no condition, the rule always matches
-    if rule.condition.is_none() {
-        return Ok(true);
-    }
+    let matched = match &rule.condition {
+        Some(condition) => conditions::evaluate_condition(condition, data)?,
+        None => true,
+    };
     
-    // Otherwise, evaluate the condition
-    conditions::evaluate_condition(&rule.condition.as_ref().unwrap(), data)
+    match &rule.sampling {
+        Some(sampling) if matched => sampling.samples(data),
+        _ => Ok(matched),
+    }
 }
 
 /// Apply transformations to data
@@ -1,13 +1,13 @@
 use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
 use serde::{Deserialize, Serialize};
-use jsonpath_lib as jsonpath;
 use std::collections::HashMap;
 
 /// Transformation step definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformationStep {
     /// Type of transformation
+    #[serde(alias = "type")]
     pub transformation_type: String,
     /// Parameters for the transformation
     pub params: Value,
@@ -192,10 +192,10 @@ fn array_flatten(step: &TransformationStep, data: Value) -> Result<Value> {
                     parent.remove(array_field);
                     parent
                 } else {
-                    HashMap::new()
+                    serde_json::Map::new()
                 }
             } else {
-                HashMap::new()
+                serde_json::Map::new()
             };
             
             // Add the flattened fields
@@ -0,0 +1,2520 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use super::super::{
+        BatchSettings, BatchingDestination, BreakerRegistry, BreakerSettings, BreakerState, DeadLetter, DeadLetterFilter, DeadLetterStore, DedupSettings, DedupStore, FailureStage,
+        DeliveryMetrics, DeliveryOrigin, DestinationDefaults, DestinationRegistry, PartialDelivery, DeliveryQueue, DeliveryReceipt, Destination, FanOutSettings, LimitAction, QueueSettings, RateLimitSettings,
+        RateLimiter, ReceiptFilter, ReceiptStatus, ReceiptStore, RedriveOutcome, Route, RouteDefinition, Router, RouterIntegration, RoutingStatus, SamplingSettings,
+        EmailDestination, FileDestination, InfluxDestination, OtlpDestination, PrometheusDestination, RouteRegistry, S3Destination, SearchIndexDestination, StatsdDestination, WebhookDestination,
+    };
+    use super::super::destinations::digest::{Digest, DigestSettings};
+    use crate::config::models::router::RouterConfig;
+    use crate::storage::{DatabaseConfig, DestinationRecord, init_database};
+    use anyhow::{Result, anyhow};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Destination that takes `delay` to answer and optionally fails
+    struct TestDestination {
+        id: String,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl TestDestination {
+        #[allow(clippy::new_ret_no_self)]
+        fn new(id: &str, delay_ms: u64, fail: bool) -> Arc<dyn Destination> {
+            Arc::new(Self {
+                id: id.to_string(),
+                delay: Duration::from_millis(delay_ms),
+                fail,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Destination for TestDestination {
+        fn get_type(&self) -> &str {
+            "test"
+        }
+
+        fn get_id(&self) -> &str {
+            &self.id
+        }
+
+        async fn send(&self, _data: Value) -> Result<()> {
+            tokio::time::sleep(self.delay).await;
+            if self.fail {
+                Err(anyhow!("rejected"))
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn send_batch(&self, data: Vec<Value>) -> Result<()> {
+            self.send(Value::Array(data)).await
+        }
+
+        async fn check_availability(&self) -> Result<bool> {
+            Ok(!self.fail)
+        }
+    }
+
+    /// Settings for a database in a new temporary file
+    fn test_database() -> (DatabaseConfig, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("muxly-test-{}.db", uuid::Uuid::new_v4()));
+        let config = DatabaseConfig {
+            url: format!("sqlite://{}?mode=rwc", path.display()),
+            ..Default::default()
+        };
+
+        (config, path)
+    }
+
+    fn settings(max_concurrency: usize, timeout_ms: u64) -> FanOutSettings {
+        FanOutSettings {
+            max_concurrency,
+            timeout: Duration::from_millis(timeout_ms),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_statuses() {
+        let router = Router::new(vec![TestDestination::new("a", 0, false), TestDestination::new("b", 0, false)]);
+        assert_eq!(router.route(json!({"n": 1})).await, RoutingStatus::Success);
+
+        let router = Router::new(vec![TestDestination::new("a", 0, false), TestDestination::new("b", 0, true)]);
+        assert_eq!(router.route(json!({"n": 1})).await, RoutingStatus::PartialSuccess(vec!["b".to_string()]));
+
+        let router = Router::new(vec![TestDestination::new("a", 0, true)]);
+        assert_eq!(router.route(json!({"n": 1})).await, RoutingStatus::Failure("a: rejected".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_slow_destination_times_out_without_blocking_others() {
+        let router = Router::new(vec![
+            TestDestination::new("slow", 5_000, false),
+            TestDestination::new("fast", 10, false),
+        ])
+        .with_fan_out(settings(2, 200));
+
+        let started = Instant::now();
+        let outcomes = router.fan_out(&router.destinations, &[json!({"n": 1})], None).await;
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(outcomes[0].result.as_ref().unwrap_err().to_string().contains("timed out"));
+        assert!(outcomes[1].result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_is_concurrent_up_to_the_limit() {
+        let destinations: Vec<_> = (0..4)
+            .map(|i| TestDestination::new(&i.to_string(), 200, false))
+            .collect();
+
+        // Four 200ms destinations, two at a time
+        let router = Router::new(destinations).with_fan_out(settings(2, 5_000));
+        let started = Instant::now();
+        assert!(router.route(json!({"n": 1})).await.is_success());
+
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(400));
+        assert!(elapsed < Duration::from_millis(800));
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_and_stays_open_while_probe_fails() {
+        let registry = BreakerRegistry::new(BreakerSettings {
+            enabled: true,
+            failure_threshold: 2,
+            open_duration: Duration::from_millis(50),
+        });
+
+        let router = Router::new(vec![TestDestination::new("smtp", 0, true)])
+            .with_breakers(&registry, "alerts");
+
+        router.route(json!({"n": 1})).await;
+        assert_eq!(registry.statuses()[0].state, BreakerState::Closed);
+
+        router.route(json!({"n": 2})).await;
+        let status = &registry.statuses()[0];
+        assert_eq!(status.key, "alerts/smtp");
+        assert_eq!(status.state, BreakerState::Open);
+        assert!(status.open_since.is_some());
+
+        // Open breakers reject without sending
+        match router.route(json!({"n": 3})).await {
+            RoutingStatus::Failure(e) => assert!(e.contains("Circuit breaker")),
+            other => panic!("unexpected status {:?}", other),
+        }
+
+        // Once due, the probe fails and the breaker opens again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        router.route(json!({"n": 4})).await;
+        assert_eq!(registry.statuses()[0].state, BreakerState::Open);
+        assert_eq!(registry.statuses()[0].consecutive_failures, 2);
+    }
+
+    /// Destination that rejects every record and whose availability probe
+    /// never answers
+    struct UnresponsiveDestination;
+
+    #[async_trait::async_trait]
+    impl Destination for UnresponsiveDestination {
+        fn get_type(&self) -> &str {
+            "test"
+        }
+
+        fn get_id(&self) -> &str {
+            "stuck"
+        }
+
+        async fn send(&self, _data: Value) -> Result<()> {
+            Err(anyhow!("rejected"))
+        }
+
+        async fn send_batch(&self, _data: Vec<Value>) -> Result<()> {
+            Err(anyhow!("rejected"))
+        }
+
+        async fn check_availability(&self) -> Result<bool> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaker_reopens_when_the_probe_never_answers() {
+        let registry = BreakerRegistry::new(BreakerSettings {
+            enabled: true,
+            failure_threshold: 1,
+            open_duration: Duration::from_millis(50),
+        });
+
+        let destination: Arc<dyn Destination> = Arc::new(UnresponsiveDestination);
+        let router = Router::new(vec![Arc::clone(&destination)])
+            .with_fan_out(settings(1, 100))
+            .with_breakers(&registry, "alerts");
+
+        router.route(json!({"n": 1})).await;
+        assert_eq!(registry.statuses()[0].state, BreakerState::Open);
+
+        // The probe times out with the destination's timeout
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let started = Instant::now();
+        match router.route(json!({"n": 2})).await {
+            RoutingStatus::Failure(e) => assert!(e.contains("Circuit breaker"), "{}", e),
+            other => panic!("unexpected status {:?}", other),
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(registry.statuses()[0].state, BreakerState::Open);
+
+        // A probe whose caller gives up opens the breaker again too
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let breaker = registry.breaker("alerts/stuck", destination.as_ref());
+        let probe = breaker.allow(destination.as_ref(), Duration::from_secs(60));
+        assert!(tokio::time::timeout(Duration::from_millis(20), probe).await.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+    }
+
+    #[test]
+    fn test_dedup_keys() {
+        let by_fields = DedupSettings {
+            fields: vec!["source".to_string(), "$.event.id".to_string()],
+            key_path: None,
+            window: 60,
+        };
+        by_fields.validate().unwrap();
+
+        let key = by_fields.key(&json!({"source": "crm", "event": {"id": 7}, "n": 1})).unwrap();
+        assert!(key.is_some());
+
+        // Fields outside the key don't matter
+        let same = by_fields.key(&json!({"source": "crm", "event": {"id": 7}, "n": 2})).unwrap();
+        assert_eq!(key, same);
+
+        let other = by_fields.key(&json!({"source": "crm", "event": {"id": 8}})).unwrap();
+        assert_ne!(key, other);
+
+        // Records missing a key field can't be deduplicated
+        assert_eq!(by_fields.key(&json!({"source": "crm"})).unwrap(), None);
+
+        let both = DedupSettings {
+            key_path: Some("$.event.id".to_string()),
+            ..by_fields
+        };
+        assert!(both.validate().is_err());
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic() {
+        let sampling = SamplingSettings { key: "user_id".to_string(), rate: 0.1 };
+
+        let sampled = (0..1000)
+            .filter(|id| sampling.samples(&json!({"user_id": id})).unwrap())
+            .count();
+        assert!((50..150).contains(&sampled), "sampled {} of 1000", sampled);
+
+        // The same user always gets the same answer
+        for id in 0..100 {
+            let first = sampling.samples(&json!({"user_id": id, "n": 1})).unwrap();
+            assert_eq!(first, sampling.samples(&json!({"user_id": id, "n": 2})).unwrap());
+        }
+
+        assert!(!sampling.samples(&json!({"n": 1})).unwrap());
+    }
+
+    #[test]
+    fn test_rate_limiter_summarizes_suppressed_records() {
+        let limiter = RateLimiter::new("big-deals", RateLimitSettings {
+            max_records: 2,
+            window: 3600,
+            on_limit: LimitAction::Summarize,
+        });
+
+        let admitted = (0..5).filter(|_| limiter.admit()).count();
+        assert_eq!(admitted, 2);
+
+        // Nothing is due until the window is over, unless flushed
+        assert!(limiter.take_summary(false).is_none());
+        let summary = limiter.take_summary(true).unwrap();
+        assert_eq!(summary["suppressed"], 3);
+        assert_eq!(summary["rule_id"], "big-deals");
+        assert!(limiter.take_summary(true).is_none());
+    }
+
+    #[test]
+    fn test_digest_lists_rows_and_counts_the_rest() {
+        let records: Vec<Value> = (1..=4)
+            .map(|n| json!({"account": format!("acct-{}", n), "revenue": n * 1000}))
+            .collect();
+
+        let digest = Digest::new(&records, &DigestSettings {
+            fields: vec!["account".to_string(), "revenue".to_string()],
+            max_rows: 3,
+        });
+
+        assert_eq!(digest.total, 4);
+        assert_eq!(digest.remaining(), 1);
+        assert_eq!(
+            digest.to_text(),
+            "account  revenue\n-------  -------\nacct-1   1000\nacct-2   2000\nacct-3   3000"
+        );
+        assert!(digest.to_html().contains("<em>1 more</em>"));
+    }
+
+    #[test]
+    fn test_email_digest_body_summarizes_the_batch() {
+        use super::super::destinations::email::EmailDestinationConfig;
+
+        let config: EmailDestinationConfig = serde_json::from_value(json!({
+            "smtp_host": "localhost",
+            "smtp_port": 25,
+            "smtp_username": "",
+            "smtp_password": "",
+            "use_tls": false,
+            "from_email": "muxly@example.com",
+            "to_emails": ["ops@example.com"],
+            "subject_template": "{{count}} new deals",
+            "template_variables": {},
+            "digest": {"fields": ["account", "stage"], "max_rows": 2},
+        })).unwrap();
+        let email = EmailDestination::new("digest".to_string(), config);
+
+        let records = vec![
+            json!({"account": "acct-1", "stage": "<won>", "revenue": 1000}),
+            json!({"account": "acct-2", "stage": "open", "revenue": 2000}),
+            json!({"account": "acct-3", "stage": "lost", "revenue": 3000}),
+        ];
+        let body = email.create_digest_body(&records);
+
+        assert!(body.contains("<p>3 records were received from a connector. A summary is below:</p>"));
+        assert!(body.contains(concat!(
+            "<table border='1' cellpadding='4' style='border-collapse: collapse;'>",
+            "<tr><th>account</th><th>stage</th></tr>",
+            "<tr><td>acct-1</td><td>&lt;won&gt;</td></tr>",
+            "<tr><td>acct-2</td><td>open</td></tr>",
+            "<tr><td colspan='2'><em>1 more</em></td></tr>",
+            "</table>",
+        )));
+        assert!(!body.contains("acct-3"));
+        assert!(!body.contains("revenue"));
+        assert!(body.contains("This is an automated message from Muxly Router."));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_metrics_by_route_and_destination() {
+        let metrics = Arc::new(DeliveryMetrics::new());
+        let router = Router::new(vec![
+            TestDestination::new("warehouse", 0, false),
+            TestDestination::new("smtp", 0, true),
+        ])
+        .with_metrics(Arc::clone(&metrics), "orders");
+
+        router.route_batch(vec![json!({"n": 1}), json!({"n": 2})]).await;
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(
+            "muxly_router_records_delivered_total{destination=\"warehouse\",destination_type=\"test\",route=\"orders\"} 2"
+        ));
+        assert!(rendered.contains(
+            "muxly_router_records_failed_total{destination=\"smtp\",destination_type=\"test\",route=\"orders\"} 2"
+        ));
+        assert!(rendered.contains("muxly_router_delivery_duration_seconds_count{destination=\"smtp\""));
+    }
+
+    #[tokio::test]
+    async fn test_statsd_lines_over_udp() {
+        let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let destination = StatsdDestination::new("statsd".to_string(), serde_json::from_value(json!({
+            "address": server.local_addr().unwrap().to_string(),
+            "prefix": "muxly.",
+            "dogstatsd": true,
+            "constant_tags": ["env:test"],
+            "tags": {"country": "country"},
+            "metrics": [
+                {"name": "ga4.{{country}}.active_users", "type": "gauge", "value_field": "activeUsers"},
+                {"name": "ga4.records"},
+                {"name": "ga4.latency", "type": "timer", "value_field": "timing.ms"},
+                {"name": "ga4.pages", "type": "set", "value_field": "page"}
+            ]
+        })).unwrap());
+
+        destination.send(json!({
+            "country": "United States",
+            "activeUsers": -3,
+            "timing": {"ms": 12.5},
+            "page": "/home"
+        })).await.unwrap();
+
+        let mut buf = [0u8; 1500];
+        let len = server.recv(&mut buf).await.unwrap();
+        let packet = String::from_utf8_lossy(&buf[..len]).to_string();
+
+        let tags = "|#env:test,country:United States";
+        assert_eq!(packet.lines().collect::<Vec<_>>(), vec![
+            format!("muxly.ga4.United_States.active_users:0|g{}", tags),
+            format!("muxly.ga4.United_States.active_users:-3|g{}", tags),
+            format!("muxly.ga4.records:1|c{}", tags),
+            format!("muxly.ga4.latency:12.5|ms{}", tags),
+            format!("muxly.ga4.pages:/home|s{}", tags),
+        ]);
+    }
+
+    #[test]
+    fn test_record_times_keep_fractions_of_a_second() {
+        use super::super::destinations::formats::parse_timestamp;
+
+        let time = parse_timestamp(&json!(1700000000.25)).unwrap();
+        assert_eq!(time.timestamp_millis(), 1700000000250);
+
+        let time = parse_timestamp(&json!("2023-11-14T22:13:20.25Z")).unwrap();
+        assert_eq!(time.timestamp_millis(), 1700000000250);
+
+        // Fractions before the epoch round down, not toward zero
+        let time = parse_timestamp(&json!(-1.5)).unwrap();
+        assert_eq!(time.to_rfc3339(), "1969-12-31T23:59:58.500+00:00");
+
+        let time = parse_timestamp(&json!("2023-11-14 22:13:20")).unwrap();
+        assert_eq!(time.timestamp(), 1700000000);
+
+        assert!(parse_timestamp(&json!(true)).is_none());
+    }
+
+    #[test]
+    fn test_templates_fill_placeholders_from_record_fields() {
+        use super::super::destinations::render_template;
+
+        let record = json!({"connector_id": "ga4", "meta": {"region": "eu"}, "count": 3});
+        assert_eq!(render_template("muxly.{{connector_id}}.{{ meta.region }}.{{count}}", &record).as_deref(), Some("muxly.ga4.eu.3"));
+        assert_eq!(render_template("muxly.{{missing}}", &record), None);
+        assert_eq!(render_template("muxly.{{connector_id", &record), None);
+    }
+
+    #[tokio::test]
+    async fn test_statsd_reaches_an_ipv6_server() {
+        // Hosts without IPv6 can't run this
+        let Ok(server) = tokio::net::UdpSocket::bind("[::1]:0").await else {
+            return;
+        };
+        let destination = StatsdDestination::new("statsd".to_string(), serde_json::from_value(json!({
+            "address": server.local_addr().unwrap().to_string(),
+            "metrics": [{"name": "records"}]
+        })).unwrap());
+
+        destination.send(json!({})).await.unwrap();
+
+        let mut buf = [0u8; 1500];
+        let len = server.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"records:1|c");
+    }
+
+    #[tokio::test]
+    async fn test_influx_writes_escaped_line_protocol_batches() {
+        let mut server = mockito::Server::new_async().await;
+        let write = server.mock("POST", "/api/v2/write")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("bucket".into(), "analytics".into()),
+                mockito::Matcher::UrlEncoded("org".into(), "muxly".into()),
+                mockito::Matcher::UrlEncoded("precision".into(), "s".into()),
+            ]))
+            .match_header("authorization", "Token secret")
+            .match_body(
+                "ga4\\ users,country=United\\ States,source=web\\,app\\=1 active=42i,ratio=0.5 1700000000\n\
+                 ga4\\ users,country=France active=7i 1700000060"
+            )
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let destination = InfluxDestination::new("influx".to_string(), serde_json::from_value(json!({
+            "url": server.url(),
+            "org": "muxly",
+            "bucket": "analytics",
+            "token": "secret",
+            "measurement": "ga4 users",
+            "tags": ["country", "source"],
+            "timestamp_field": "time",
+            "precision": "s",
+            "integers": true
+        })).unwrap());
+
+        destination.send_batch(vec![
+            json!({"country": "United States", "source": "web,app=1", "active": 42, "ratio": 0.5, "time": "2023-11-14T22:13:20Z"}),
+            json!({"country": "France", "source": "", "active": 7, "time": 1700000060}),
+            json!({"country": "Spain", "note": "no numeric fields"}),
+        ]).await.unwrap();
+
+        write.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_influx_keeps_backslashes_and_skips_line_breaks() {
+        let mut server = mockito::Server::new_async().await;
+        let write = server.mock("POST", "/api/v2/write")
+            .match_query(mockito::Matcher::Any)
+            .match_body("ga4,path=C:\\temp active=1 1700000000\nga4 active=2 1700000000")
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let destination = InfluxDestination::new("influx".to_string(), serde_json::from_value(json!({
+            "url": server.url(),
+            "bucket": "analytics",
+            "measurement": "ga4",
+            "measurement_field": "name",
+            "tags": ["path"],
+            "fields": ["active"],
+            "timestamp_field": "time",
+            "precision": "s"
+        })).unwrap());
+
+        destination.send_batch(vec![
+            json!({"path": "C:\\temp", "active": 1, "time": 1700000000}),
+            json!({"name": "ga4\nusers", "path": "two\nlines", "active": 2, "time": 1700000000}),
+        ]).await.unwrap();
+
+        write.assert_async().await;
+    }
+
+    /// S3 destination against a local S3-compatible endpoint
+    fn s3_destination(server: &mockito::Server, options: Value) -> S3Destination {
+        let mut config = json!({
+            "bucket": "exports",
+            "key_prefix": "muxly",
+            "region": "us-east-1",
+            "format": "json",
+            "key_template": "{{connector_id}}/dt={{date}}/hour={{hour}}/records",
+            "credentials": {"access_key_id": "minio", "secret_access_key": "minio-secret"},
+            "endpoint_url": server.url()
+        });
+        config.as_object_mut().unwrap().extend(options.as_object().unwrap().clone());
+
+        S3Destination::new("s3".to_string(), serde_json::from_value(config).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_s3_puts_objects_on_custom_endpoints_with_path_style_partitioned_keys() {
+        let mut server = mockito::Server::new_async().await;
+        // Path-style: the bucket is the first path segment, not a subdomain
+        let put = server.mock("PUT", mockito::Matcher::Regex(
+            r"^/exports/muxly/shop/dt%3D\d{4}-\d{2}-\d{2}/hour%3D\d{2}/records-[0-9a-f]{32}\.json(\?|$)".to_string()
+        ))
+            .match_header("authorization", mockito::Matcher::Regex("Credential=minio/".to_string()))
+            .match_header("content-type", "application/json")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        let batch = server.mock("PUT", mockito::Matcher::Regex(r"^/exports/muxly/shop/.*/records_batch-[0-9a-f]{32}\.json(\?|$)".to_string()))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let destination = s3_destination(&server, json!({}));
+        destination.send(json!({"connector_id": "shop", "order": 1})).await.unwrap();
+        destination.send_batch(vec![json!({"connector_id": "shop", "order": 2})]).await.unwrap();
+
+        put.assert_async().await;
+        batch.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_s3_keys_stay_unique_within_a_second() {
+        let mut server = mockito::Server::new_async().await;
+        // Two objects in the same second get different random suffixes
+        let suffixed = server.mock("PUT", mockito::Matcher::Regex(r"^/exports/muxly/shop/data-[0-9a-f]{32}\.json(\?|$)".to_string()))
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+        // A {{uuid}} in the template already makes the key unique
+        let uuid = server.mock("PUT", mockito::Matcher::Regex(r"^/exports/muxly/uuid/[0-9a-f-]{36}\.json(\?|$)".to_string()))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let destination = s3_destination(&server, json!({"key_template": "{{connector_id}}/data"}));
+        destination.send(json!({"connector_id": "shop"})).await.unwrap();
+        destination.send(json!({"connector_id": "shop"})).await.unwrap();
+        let destination = s3_destination(&server, json!({"key_template": "uuid/{{uuid}}"}));
+        destination.send(json!({"connector_id": "shop"})).await.unwrap();
+
+        suffixed.assert_async().await;
+        uuid.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_s3_uploads_objects_over_the_threshold_in_parts() {
+        let mut server = mockito::Server::new_async().await;
+        let path = mockito::Matcher::Regex(r"^/exports/muxly/shop/.*\.json".to_string());
+        let put = server.mock("PUT", path.clone())
+            .match_query(mockito::Matcher::Regex("x-id=PutObject".to_string()))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        let create = server.mock("POST", path.clone())
+            .match_query(mockito::Matcher::Regex("^uploads".to_string()))
+            .with_status(200)
+            .with_body("<InitiateMultipartUploadResult><Bucket>exports</Bucket><Key>k</Key><UploadId>upload-1</UploadId></InitiateMultipartUploadResult>")
+            .expect(1)
+            .create_async()
+            .await;
+        let parts = server.mock("PUT", path.clone())
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex("partNumber=[12]".to_string()),
+                mockito::Matcher::Regex("uploadId=upload-1".to_string()),
+            ]))
+            .with_status(200)
+            .with_header("etag", "\"part\"")
+            .expect(2)
+            .create_async()
+            .await;
+        let complete = server.mock("POST", path)
+            .match_query(mockito::Matcher::Regex("uploadId=upload-1".to_string()))
+            .match_body(mockito::Matcher::Regex("<PartNumber>2</PartNumber>".to_string()))
+            .with_status(200)
+            .with_body("<CompleteMultipartUploadResult><Bucket>exports</Bucket><Key>k</Key><ETag>\"done\"</ETag></CompleteMultipartUploadResult>")
+            .expect(1)
+            .create_async()
+            .await;
+
+        // The threshold and part size are raised to S3's 5 MB minimum, so a
+        // 6 MB object goes up in two parts
+        let destination = s3_destination(&server, json!({"multipart_threshold_mb": 1, "multipart_part_size_mb": 1}));
+        let large = "x".repeat(6 * 1024 * 1024);
+        destination.send(json!({"connector_id": "shop", "payload": large})).await.unwrap();
+
+        // Smaller objects are a single put
+        destination.send(json!({"connector_id": "shop", "payload": "small"})).await.unwrap();
+
+        create.assert_async().await;
+        parts.assert_async().await;
+        complete.assert_async().await;
+        put.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_search_index_bulk_item_failures_fail_the_delivery() {
+        let mut server = mockito::Server::new_async().await;
+        let bulk = server.mock("POST", "/_bulk")
+            .match_header("content-type", "application/x-ndjson")
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex(r#"\{"index":\{"_index":"muxly-ga4-2024-03-01","_id":"o-1"\}\}\n"#.to_string()),
+                mockito::Matcher::Regex(r#"\{"index":\{"_index":"muxly-ga4-2024-03-02","_id":"o-2"\}\}\n"#.to_string()),
+            ]))
+            .with_status(200)
+            .with_body(json!({
+                "took": 3,
+                "errors": true,
+                "items": [
+                    {"index": {"_index": "muxly-ga4-2024-03-01", "_id": "o-1", "status": 201}},
+                    {"index": {"_index": "muxly-ga4-2024-03-02", "_id": "o-2", "status": 400,
+                        "error": {"type": "mapper_parsing_exception", "reason": "failed to parse field [time]"}}}
+                ]
+            }).to_string())
+            .create_async()
+            .await;
+
+        let destination = SearchIndexDestination::new("search".to_string(), serde_json::from_value(json!({
+            "url": server.url(),
+            "id_field": "id",
+            "timestamp_field": "time"
+        })).unwrap());
+        let router = Router::new(vec![Arc::new(destination)]);
+
+        let status = router.route_batch(vec![
+            json!({"id": "o-1", "connector_id": "ga4", "time": "2024-03-01T10:00:00Z"}),
+            json!({"id": "o-2", "connector_id": "ga4", "time": "2024-03-02T10:00:00Z"}),
+        ]).await;
+
+        bulk.assert_async().await;
+        match status {
+            RoutingStatus::Failure(error) => {
+                assert!(error.contains("1 of 2 documents failed"), "{}", error);
+                assert!(error.contains("record 1 (400): mapper_parsing_exception: failed to parse field [time]"), "{}", error);
+            },
+            other => panic!("Expected a failure, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_retries_max_retries_times_then_dead_letters() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+        let queue = DeliveryQueue::new(Arc::clone(&pool), QueueSettings {
+            buffer_size: 10,
+            batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 1,
+        })
+        .with_dead_letters(Arc::clone(&dead_letters));
+
+        let smtp = TestDestination::new("smtp", 0, true);
+        let original = json!({"n": 1, "raw": true});
+        let origin = DeliveryOrigin { rule_id: "page-oncall", record: &original };
+        queue.enqueue("alerts", "alerts/smtp", &[json!({"n": 1})], Some(origin)).await.unwrap();
+
+        // The first attempt isn't a retry, so the record stays queued
+        queue.flush("alerts/smtp", smtp.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(queue.pending_count("alerts/smtp").await.unwrap(), 1);
+
+        // Skip the backoff; the one retry fails too
+        sqlx::query("UPDATE delivery_queue SET next_attempt_at = 0").execute(pool.as_ref()).await.unwrap();
+        queue.flush("alerts/smtp", smtp.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(queue.pending_count("alerts/smtp").await.unwrap(), 0);
+
+        let letters = dead_letters.list(&DeadLetterFilter::default()).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].route_id, "alerts");
+        assert_eq!(letters[0].destination.as_deref(), Some("alerts/smtp"));
+        assert_eq!(letters[0].attempts, 2);
+        assert_eq!(letters[0].rule_id.as_deref(), Some("page-oncall"));
+        assert_eq!(letters[0].record, original);
+        assert_eq!(letters[0].payload, Some(json!({"n": 1})));
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_queue_keeps_the_route_of_records_whose_route_id_has_a_slash() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+        let receipts = Arc::new(ReceiptStore::new(Arc::clone(&pool)));
+        let queue = DeliveryQueue::new(Arc::clone(&pool), QueueSettings {
+            buffer_size: 10,
+            batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 0,
+        })
+        .with_dead_letters(Arc::clone(&dead_letters))
+        .with_receipts(Arc::clone(&receipts));
+
+        let smtp = TestDestination::new("smtp", 0, true);
+        queue.enqueue("team/alerts", "team/alerts/smtp", &[json!({"n": 1})], None).await.unwrap();
+        queue.flush("team/alerts/smtp", smtp.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+
+        let letters = dead_letters.list(&DeadLetterFilter::default()).await.unwrap();
+        assert_eq!(letters[0].route_id, "team/alerts");
+        let receipts = receipts.list(&ReceiptFilter::default()).await.unwrap();
+        assert_eq!(receipts[0].route_id, "team/alerts");
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_redrive_needs_a_running_queue_with_room() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+        let queue = Arc::new(DeliveryQueue::new(Arc::clone(&pool), QueueSettings {
+            buffer_size: 1,
+            batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 0,
+        })
+        .with_dead_letters(Arc::clone(&dead_letters)));
+
+        let smtp = TestDestination::new("smtp", 0, true);
+        let original = json!({"n": 1});
+        let origin = DeliveryOrigin { rule_id: "page-oncall", record: &original };
+        queue.enqueue("alerts", "alerts/smtp", &[json!({"n": 1})], Some(origin)).await.unwrap();
+        queue.flush("alerts/smtp", smtp.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+        let id = dead_letters.list(&DeadLetterFilter::default()).await.unwrap()[0].id.unwrap();
+
+        // Without a worker the record would never be delivered
+        assert_eq!(dead_letters.redrive(id, &queue).await.unwrap(), RedriveOutcome::NotQueued);
+        let summary = dead_letters.redrive_all(None, &queue).await.unwrap();
+        assert_eq!((summary.redriven, summary.not_queued), (0, 1));
+        assert!(dead_letters.get(id).await.unwrap().unwrap().redriven_at.is_none());
+
+        // The worker's first flush finds nothing; later ones wait a minute
+        queue.start("alerts/smtp".to_string(), Arc::clone(&smtp), None, Duration::from_secs(1));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        queue.enqueue("alerts", "alerts/smtp", &[json!({"n": 2})], None).await.unwrap();
+        assert_eq!(dead_letters.redrive(id, &queue).await.unwrap(), RedriveOutcome::QueueFull);
+        assert!(dead_letters.get(id).await.unwrap().unwrap().redriven_at.is_none());
+
+        // Of two concurrent re-drives only one queues the record
+        sqlx::query("DELETE FROM delivery_queue").execute(pool.as_ref()).await.unwrap();
+        let (first, second) = tokio::join!(dead_letters.redrive(id, &queue), dead_letters.redrive(id, &queue));
+        let mut outcomes = vec![first.unwrap(), second.unwrap()];
+        outcomes.sort_by_key(|outcome| *outcome != RedriveOutcome::Redriven);
+        assert_eq!(outcomes, vec![RedriveOutcome::Redriven, RedriveOutcome::AlreadyRedriven]);
+        assert_eq!(queue.pending_count("alerts/smtp").await.unwrap(), 1);
+        assert_eq!(dead_letters.redrive(id, &queue).await.unwrap(), RedriveOutcome::AlreadyRedriven);
+
+        queue.stop();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_queue_retries_only_the_documents_a_bulk_request_rejected() {
+        let mut server = mockito::Server::new_async().await;
+        let bulk = server.mock("POST", "/_bulk")
+            .with_status(200)
+            .with_body(json!({
+                "errors": true,
+                "items": [
+                    {"index": {"_id": "o-1", "status": 201}},
+                    {"index": {"_id": "o-2", "status": 429,
+                        "error": {"type": "es_rejected_execution_exception", "reason": "queue is full"}}},
+                    {"index": {"_id": "o-3", "status": 201}}
+                ]
+            }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let destination = SearchIndexDestination::new("search".to_string(), serde_json::from_value(json!({
+            "url": server.url(),
+            "id_field": "id"
+        })).unwrap());
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let metrics = Arc::new(DeliveryMetrics::new());
+        let receipts = Arc::new(ReceiptStore::new(Arc::clone(&pool)));
+        let queue = DeliveryQueue::new(Arc::clone(&pool), QueueSettings {
+            buffer_size: 10,
+            batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 3,
+        })
+        .with_metrics(Arc::clone(&metrics))
+        .with_receipts(Arc::clone(&receipts));
+
+        queue.enqueue("orders", "orders/search", &[json!({"id": "o-1"}), json!({"id": "o-2"}), json!({"id": "o-3"})], None)
+            .await
+            .unwrap();
+        let delivered = queue.flush("orders/search", &destination, None, Duration::from_secs(5)).await.unwrap();
+        bulk.assert_async().await;
+
+        // Only the rejected document is left for a retry
+        assert_eq!(delivered, 2);
+        assert_eq!(queue.pending_count("orders/search").await.unwrap(), 1);
+        let payload: String = sqlx::query_scalar("SELECT payload FROM delivery_queue")
+            .fetch_one(pool.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&payload).unwrap(), json!({"id": "o-2"}));
+
+        let mut stored = receipts.list(&ReceiptFilter::default()).await.unwrap();
+        stored.sort_by_key(|receipt| receipt.record_count);
+        assert_eq!((stored[0].record_count, stored[0].status), (1, ReceiptStatus::Failed));
+        assert_eq!((stored[1].record_count, stored[1].status), (2, ReceiptStatus::Delivered));
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(
+            "muxly_router_records_delivered_total{destination=\"search\",destination_type=\"search_index\",route=\"orders\"} 2"
+        ));
+        assert!(rendered.contains(
+            "muxly_router_records_failed_total{destination=\"search\",destination_type=\"search_index\",route=\"orders\"} 1"
+        ));
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_queue_records_receipts_and_metrics_of_its_deliveries() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let metrics = Arc::new(DeliveryMetrics::new());
+        let receipts = Arc::new(ReceiptStore::new(Arc::clone(&pool)));
+        let queue = DeliveryQueue::new(Arc::clone(&pool), QueueSettings {
+            buffer_size: 10,
+            batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 3,
+        })
+        .with_metrics(Arc::clone(&metrics))
+        .with_receipts(Arc::clone(&receipts));
+
+        let original = json!({"n": 1});
+        let origin = DeliveryOrigin { rule_id: "page-oncall", record: &original };
+        queue.enqueue("alerts", "alerts/chat", &[json!({"n": 1}), json!({"n": 2})], Some(origin)).await.unwrap();
+        queue.enqueue("alerts", "alerts/smtp", &[json!({"n": 1})], None).await.unwrap();
+
+        let chat = TestDestination::new("chat", 0, false);
+        let smtp = TestDestination::new("smtp", 0, true);
+        queue.flush("alerts/chat", chat.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+        queue.flush("alerts/smtp", smtp.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+
+        let mut stored = receipts.list(&ReceiptFilter::default()).await.unwrap();
+        stored.sort_by(|a, b| a.destination_id.cmp(&b.destination_id));
+        assert_eq!(stored.len(), 2);
+
+        assert_eq!(stored[0].route_id, "alerts");
+        assert_eq!(stored[0].destination_key.as_deref(), Some("alerts/chat"));
+        assert_eq!(stored[0].rule_id.as_deref(), Some("page-oncall"));
+        assert_eq!(stored[0].record_count, 2);
+        assert_eq!(stored[0].status, ReceiptStatus::Delivered);
+
+        assert_eq!(stored[1].destination_id, "smtp");
+        assert_eq!(stored[1].rule_id, None);
+        assert_eq!(stored[1].status, ReceiptStatus::Failed);
+        assert_eq!(stored[1].error.as_deref(), Some("rejected"));
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(
+            "muxly_router_records_delivered_total{destination=\"chat\",destination_type=\"test\",route=\"alerts\"} 2"
+        ));
+        assert!(rendered.contains(
+            "muxly_router_records_failed_total{destination=\"smtp\",destination_type=\"test\",route=\"alerts\"} 1"
+        ));
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_queue_settles_the_queued_receipts_of_the_records_it_delivers() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let receipts = Arc::new(ReceiptStore::new(Arc::clone(&pool)));
+        let queue = DeliveryQueue::new(Arc::clone(&pool), QueueSettings {
+            buffer_size: 10,
+            batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 3,
+        })
+        .with_receipts(Arc::clone(&receipts));
+
+        let queued = DeliveryReceipt::new("alerts", "chat", 1, ReceiptStatus::Queued, Duration::ZERO);
+        let queued_id = receipts.record(&queued).await.unwrap();
+        queue.enqueue_with_receipt("alerts", "alerts/chat", &[json!({"n": 1})], None, Some(queued_id)).await.unwrap();
+
+        let chat = TestDestination::new("chat", 0, false);
+        queue.flush("alerts/chat", chat.as_ref(), None, Duration::from_secs(1)).await.unwrap();
+
+        let settled = receipts.get(queued_id).await.unwrap().unwrap();
+        assert_eq!(settled.status, ReceiptStatus::Delivered);
+        let batch = receipts.get(settled.batch_receipt_id.unwrap()).await.unwrap().unwrap();
+        assert_eq!((batch.record_count, batch.status), (1, ReceiptStatus::Delivered));
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_batching_records_and_settles_receipts_when_it_delivers() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let metrics = Arc::new(DeliveryMetrics::new());
+        let receipts = Arc::new(ReceiptStore::new(Arc::clone(&pool)));
+
+        let destination: Arc<dyn Destination> = BatchingDestination::start(
+            TestDestination::new("chat", 0, false),
+            BatchSettings { flush_interval: 3600, batch_size: 10 },
+        );
+        let router = Router::new(vec![destination])
+            .with_keys("alerts")
+            .with_metrics(Arc::clone(&metrics), "alerts")
+            .with_receipts(Arc::clone(&receipts), "alerts");
+
+        let original = json!({"n": 1});
+        let origin = DeliveryOrigin { rule_id: "page-oncall", record: &original };
+        let mut queued_ids = Vec::new();
+        for n in 0..2 {
+            let deliveries = router.fan_out(&router.destinations, &[json!({"n": n})], Some(origin)).await;
+            queued_ids.extend(deliveries[0].receipt_id);
+        }
+        assert_eq!(queued_ids.len(), 2);
+
+        // Nothing was delivered yet
+        let held = receipts.get(queued_ids[0]).await.unwrap().unwrap();
+        assert_eq!((held.status, held.rule_id.as_deref()), (ReceiptStatus::Queued, Some("page-oncall")));
+        assert!(!metrics.render().contains("muxly_router_deliveries_total"));
+
+        router.flush().await;
+
+        let stored = receipts.list(&ReceiptFilter::default()).await.unwrap();
+        assert_eq!(stored.len(), 3);
+        let batch = &stored[0];
+        assert_eq!((batch.record_count, batch.status), (2, ReceiptStatus::Delivered));
+        assert_eq!(batch.destination_key.as_deref(), Some("alerts/chat"));
+
+        for id in queued_ids {
+            let settled = receipts.get(id).await.unwrap().unwrap();
+            assert_eq!(settled.status, ReceiptStatus::Delivered);
+            assert_eq!(settled.batch_receipt_id, batch.id);
+        }
+
+        let rendered = metrics.render();
+        assert!(rendered.contains(
+            "muxly_router_deliveries_total{destination=\"chat\",destination_type=\"test\",route=\"alerts\",status=\"success\"} 1"
+        ), "{}", rendered);
+        assert!(rendered.contains(
+            "muxly_router_records_delivered_total{destination=\"chat\",destination_type=\"test\",route=\"alerts\"} 2"
+        ), "{}", rendered);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_routes_deliver_through_the_queue_by_default() {
+        let config: RouterConfig = serde_json::from_value(json!({
+            "routes": [{
+                "id": "orders",
+                "name": "Orders",
+                "enabled": true,
+                "source": {"connector_id": "shop", "data_spec": {}},
+                "destinations": [{"id": "hook", "destination_type": "webhook", "config": {"endpoints": []}}]
+            }]
+        })).unwrap();
+        assert!(config.global.delivery_queue);
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let router = RouterIntegration::start(Arc::clone(&pool), &config).await.unwrap();
+        assert!(router.routes.get("orders").unwrap().router.is_queued());
+
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_queued_records_are_delivered_after_a_restart() {
+        let mut server = mockito::Server::new_async().await;
+        let webhook = server.mock("POST", "/orders")
+            .match_body(mockito::Matcher::Json(json!({"order": 1})))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = RouterConfig::default();
+        config.global.delivery_queue = true;
+        config.routes = vec![serde_json::from_value(json!({
+            "id": "orders",
+            "name": "Orders",
+            "enabled": true,
+            "source": {"connector_id": "shop", "data_spec": {}},
+            "destinations": [{
+                "id": "hook",
+                "destination_type": "webhook",
+                "config": {"endpoints": [{"url": format!("{}/orders", server.url())}]}
+            }]
+        })).unwrap()];
+
+        // The first run queues a record and stops while delivering it
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let queue = DeliveryQueue::new(Arc::clone(&pool), QueueSettings::from(&config.global));
+        queue.enqueue("orders", "orders/hook", &[json!({"order": 1})], None).await.unwrap();
+        sqlx::query("UPDATE delivery_queue SET status = 'in_flight'").execute(pool.as_ref()).await.unwrap();
+        pool.close().await;
+
+        // Starting again recovers the record and delivers it
+        let pool = init_database(&db_config).await.unwrap();
+        let router = RouterIntegration::start(Arc::clone(&pool), &config).await.unwrap();
+        assert!(router.routes.get("orders").is_some());
+        assert_eq!(router.breakers.statuses()[0].key, "orders/hook");
+
+        let started = Instant::now();
+        while router.queue.pending_count("orders/hook").await.unwrap() > 0 {
+            assert!(started.elapsed() < Duration::from_secs(5), "The queued record wasn't delivered");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        webhook.assert_async().await;
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// Route sending to a webhook, with a rule whose second condition can't be
+    /// evaluated for records with a non-numeric `size`
+    fn short_circuit_route(url: &str) -> RouteDefinition {
+        serde_json::from_value(json!({
+            "id": "alerts",
+            "name": "Alerts",
+            "enabled": true,
+            "source": {"connector_id": "monitor", "data_spec": {}},
+            "destinations": [{
+                "id": "hook",
+                "destination_type": "webhook",
+                "config": {"endpoints": [{"url": format!("{}/alerts", url)}]}
+            }],
+            "rules": [{
+                "id": "errors-or-large",
+                "name": "Errors or large",
+                "enabled": true,
+                "condition": "level == 'error' OR size > 10",
+                "priority": 1,
+                "destination_ids": ["hook"],
+                "transformations": []
+            }]
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_short_circuits_like_process() {
+        let mut server = mockito::Server::new_async().await;
+        let webhook = server.mock("POST", "/alerts").with_status(200).expect(1).create_async().await;
+
+        let definition = short_circuit_route(&server.url());
+        let route = Route::new(definition.config, definition.rules).unwrap();
+        let record = json!({"level": "error", "size": "big"});
+
+        let report = route.dry_run(record.clone());
+        let results = route.process(record).await.unwrap();
+        webhook.assert_async().await;
+
+        // The first condition holds, so the one that can't be evaluated never is
+        assert!(results[0].matched);
+        assert_eq!(results[0].routed_to, vec!["hook".to_string()]);
+
+        let rule = &report.rules[0];
+        assert!(rule.matched);
+        assert_eq!(rule.destinations, vec!["hook".to_string()]);
+        assert_eq!(report.destinations, vec!["hook".to_string()]);
+
+        let condition = rule.condition.as_ref().unwrap();
+        assert!(condition.result);
+        assert!(!condition.has_error());
+        assert!(condition.children[0].result);
+        assert!(condition.children[1].skipped);
+    }
+
+    #[test]
+    fn test_condition_traces_parenthesize_compound_parts() {
+        use super::super::routing::explain_condition;
+
+        let trace = explain_condition("level == 'error' OR size > 10 AND EXISTS user", &json!({"size": 20})).unwrap();
+        assert_eq!(trace.expression, "(level == 'error' OR size > 10) AND EXISTS user");
+        assert_eq!(trace.children[0].expression, "level == 'error' OR size > 10");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_handler_explains_a_registered_route() {
+        use axum::{body::Body, http::{Request, StatusCode}, Extension};
+        use tower::ServiceExt;
+
+        let server = mockito::Server::new_async().await;
+        let config = RouterConfig {
+            routes: vec![short_circuit_route(&server.url())],
+            ..Default::default()
+        };
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let router = RouterIntegration::start(Arc::clone(&pool), &config).await.unwrap();
+
+        let app = crate::api::api_router(None).layer(Extension(Arc::clone(&router.routes)));
+        let request = Request::post("/router/routes/alerts/dry-run")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({"data": {"level": "info", "size": 20}}).to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let report: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["route_id"], "alerts");
+        assert_eq!(report["rules"][0]["matched"], true);
+        assert_eq!(report["destinations"], json!(["hook"]));
+
+        let request = Request::post("/router/routes/unknown/dry-run")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({"data": {}}).to_string()))
+            .unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_handlers_use_the_router_store() {
+        use axum::{body::Body, http::{Request, StatusCode}, Extension};
+        use tower::ServiceExt;
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let router = RouterIntegration::start(Arc::clone(&pool), &RouterConfig::default()).await.unwrap();
+
+        let letter = DeadLetter::new("orders", FailureStage::Delivery, json!({"id": 1}), "boom");
+        let id = router.dead_letters.publish(letter).await.unwrap();
+
+        let app = crate::api::api_router(None).layer(Extension(Arc::clone(&router.dead_letters)));
+        let request = Request::get(format!("/router/dead-letters/{}", id)).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let letter: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(letter["route_id"], "orders");
+
+        let request = Request::delete(format!("/router/dead-letters/{}", id)).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::NO_CONTENT);
+        let request = Request::get(format!("/router/dead-letters/{}", id)).body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_send_record_handler_routes_through_a_registered_route() {
+        use axum::{body::Body, http::{Request, StatusCode}, Extension};
+        use tower::ServiceExt;
+
+        let server = mockito::Server::new_async().await;
+        let config = RouterConfig {
+            routes: vec![short_circuit_route(&server.url())],
+            ..Default::default()
+        };
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let router = RouterIntegration::start(Arc::clone(&pool), &config).await.unwrap();
+
+        let app = crate::api::api_router(None).layer(Extension(Arc::clone(&router.routes)));
+        let request = Request::post("/router/routes/alerts/records")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({"data": {"level": "error", "size": 1}}).to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let sent: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sent["route_id"], "alerts");
+        assert_eq!(sent["results"][0]["rule_id"], "errors-or-large");
+        assert_eq!(sent["results"][0]["routed_to"], json!(["hook"]));
+
+        let request = Request::post("/router/routes/unknown/records")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({"data": {}}).to_string()))
+            .unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_destination_handlers_rebuild_routes_and_guard_destinations_in_use() {
+        use axum::{body::Body, http::{Request, StatusCode}, Extension};
+        use tower::ServiceExt;
+
+        let mut server = mockito::Server::new_async().await;
+        let moved = server.mock("POST", "/v2")
+            .match_header("authorization", "Bearer s3cret")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        DestinationRegistry::load(Arc::clone(&pool), DestinationDefaults::default()).await.unwrap()
+            .register(DestinationRecord::new(
+                "alerts-hook".to_string(),
+                "Alerts hook".to_string(),
+                "webhook".to_string(),
+                true,
+                json!({"endpoints": [{"url": format!("{}/v1", server.url()), "authorization": "Bearer s3cret"}]}),
+            ))
+            .await
+            .unwrap();
+
+        let mut definition = short_circuit_route(&server.url());
+        definition.config.destinations.clear();
+        definition.rules[0].destination_ids = vec!["alerts-hook".to_string()];
+        let mut config = RouterConfig { routes: vec![definition], ..Default::default() };
+        config.global.batch_size = 1;
+        let router = Arc::new(RouterIntegration::start(Arc::clone(&pool), &config).await.unwrap());
+
+        let app = crate::api::api_router(None)
+            .layer(Extension(Arc::clone(&router.destinations)))
+            .layer(Extension(Arc::clone(&router)));
+        let send = |method: &str, body: Option<Value>| {
+            let request = Request::builder()
+                .method(method)
+                .uri("/router/destinations/alerts-hook")
+                .header("Content-Type", "application/json")
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        // Secrets are redacted, everything else comes back as stored
+        let response = send("GET", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let record: Value = serde_json::from_slice(&body).unwrap();
+        let mut config: Value = serde_json::from_str(record["config"].as_str().unwrap()).unwrap();
+        assert_eq!(config["endpoints"][0]["authorization"], "********");
+        assert_eq!(config["endpoints"][0]["url"], format!("{}/v1", server.url()));
+
+        // Saving the redacted value keeps the secret, and the route picks up the new URL
+        config["endpoints"][0]["url"] = json!(format!("{}/v2", server.url()));
+        let response = send("PUT", Some(json!({"destination_type": "webhook", "config": config}))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let route = router.routes.get("alerts").unwrap();
+        route.process(json!({"level": "error", "size": 1})).await.unwrap();
+        let started = Instant::now();
+        while router.queue.pending_count("alerts/alerts-hook").await.unwrap() > 0 {
+            assert!(started.elapsed() < Duration::from_secs(5), "The queued record wasn't delivered");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        moved.assert_async().await;
+
+        // Running routes still use it
+        let response = send("DELETE", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("alerts"));
+
+        let disabled = json!({"destination_type": "webhook", "enabled": false, "config": config});
+        assert_eq!(send("PUT", Some(disabled)).await.unwrap().status(), StatusCode::CONFLICT);
+        assert!(router.destinations.get("alerts-hook").is_some());
+
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_lets_a_failed_delivery_through_again() {
+        let mut server = mockito::Server::new_async().await;
+        let failing = server.mock("POST", "/orders").with_status(500).expect(1).create_async().await;
+
+        let definition: RouteDefinition = serde_json::from_value(json!({
+            "id": "orders",
+            "name": "Orders",
+            "enabled": true,
+            "source": {"connector_id": "shop", "data_spec": {}},
+            "dedup": {"fields": ["order_id"]},
+            "destinations": [{
+                "id": "hook",
+                "destination_type": "webhook",
+                "config": {"endpoints": [{"url": format!("{}/orders", server.url())}]}
+            }]
+        })).unwrap();
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let route = Route::new(definition.config, definition.rules).unwrap()
+            .with_dedup(Arc::new(DedupStore::new(Arc::clone(&pool))));
+        let record = json!({"order_id": 1});
+
+        let results = route.process(record.clone()).await.unwrap();
+        assert_ne!(results[0].status, RoutingStatus::Success);
+        assert_eq!((results[0].records_delivered, results[0].records_failed), (0, 1));
+        failing.assert_async().await;
+        failing.remove_async().await;
+
+        // The failed record isn't a duplicate, so its retry is delivered
+        let delivered = server.mock("POST", "/orders").with_status(200).expect(1).create_async().await;
+        let results = route.process(record.clone()).await.unwrap();
+        assert_eq!(results[0].status, RoutingStatus::Success);
+        assert_eq!((results[0].records_delivered, results[0].records_failed), (1, 0));
+        assert_eq!(results[0].duplicates_dropped, 0);
+        assert_eq!(results[0].routed_to, vec!["hook".to_string()]);
+
+        // Once delivered, it is
+        let results = route.process(record).await.unwrap();
+        assert_eq!(results[0].rule_id, "dedup");
+        assert_eq!(results[0].duplicates_dropped, 1);
+        delivered.assert_async().await;
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_only_remembers_records_a_destination_accepted() {
+        let mut server = mockito::Server::new_async().await;
+        let hook = server.mock("POST", "/orders").with_status(200).expect(1).create_async().await;
+
+        let definition: RouteDefinition = serde_json::from_value(json!({
+            "id": "orders",
+            "name": "Orders",
+            "enabled": true,
+            "source": {"connector_id": "shop", "data_spec": {}},
+            "dedup": {"fields": ["order_id"]},
+            "destinations": [{
+                "id": "hook",
+                "destination_type": "webhook",
+                "config": {"endpoints": [{"url": format!("{}/orders", server.url())}]}
+            }],
+            "rules": [{
+                "id": "large",
+                "name": "Large orders",
+                "enabled": true,
+                "condition": "size > 10",
+                "priority": 1,
+                "destination_ids": ["hook"],
+                "transformations": [],
+                "rate_limit": {"max_records": 1}
+            }]
+        })).unwrap();
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let route = Route::new(definition.config, definition.rules).unwrap()
+            .with_dedup(Arc::new(DedupStore::new(Arc::clone(&pool))));
+
+        let delivered = json!({"order_id": 1, "size": 20});
+        let limited = json!({"order_id": 2, "size": 20});
+        let unmatched = json!({"order_id": 3, "size": 1});
+        route.process(delivered.clone()).await.unwrap();
+        assert_eq!(route.process(limited.clone()).await.unwrap()[0].rate_limited, 1);
+        assert!(route.process(unmatched.clone()).await.unwrap().is_empty());
+
+        // Neither the rate-limited record nor the one no rule matched was
+        // delivered, so they aren't duplicates when they come again
+        assert_eq!(route.process(limited).await.unwrap()[0].rate_limited, 1);
+        assert!(route.process(unmatched).await.unwrap().is_empty());
+        assert_eq!(route.process(delivered).await.unwrap()[0].duplicates_dropped, 1);
+        hook.assert_async().await;
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_route_flush_sends_the_pending_rate_limit_summary() {
+        let mut server = mockito::Server::new_async().await;
+        let record = server.mock("POST", "/alerts")
+            .match_body(mockito::Matcher::PartialJson(json!({"n": 0})))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        let summary = server.mock("POST", "/alerts")
+            .match_body(mockito::Matcher::PartialJson(json!({"rule_id": "page", "suppressed": 2})))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let definition: RouteDefinition = serde_json::from_value(json!({
+            "id": "alerts",
+            "name": "Alerts",
+            "enabled": true,
+            "source": {"connector_id": "monitor", "data_spec": {}},
+            "destinations": [{
+                "id": "hook",
+                "destination_type": "webhook",
+                "config": {"endpoints": [{"url": format!("{}/alerts", server.url())}]}
+            }],
+            "rules": [{
+                "id": "page",
+                "name": "Page",
+                "enabled": true,
+                "priority": 1,
+                "destination_ids": ["hook"],
+                "transformations": [],
+                "rate_limit": {"max_records": 1, "on_limit": "summarize"}
+            }]
+        })).unwrap();
+        let route = Route::new(definition.config, definition.rules).unwrap();
+
+        for n in 0..3 {
+            route.process(json!({"n": n})).await.unwrap();
+        }
+        record.assert_async().await;
+
+        // The window isn't over, but the run is
+        route.flush().await;
+        summary.assert_async().await;
+        assert!(route.flush_rate_limits().await.unwrap().is_empty());
+    }
+
+    /// Route sending orders to the `large` and `all` hooks of a server
+    fn order_route(server: &mockito::Server, extra: Value) -> RouteDefinition {
+        let mut definition = json!({
+            "id": "orders",
+            "name": "Orders",
+            "enabled": true,
+            "source": {"connector_id": "shop", "data_spec": {}},
+            "destinations": [
+                {"id": "large", "destination_type": "webhook",
+                    "config": {"endpoints": [{"url": format!("{}/large", server.url())}]}},
+                {"id": "all", "destination_type": "webhook",
+                    "config": {"endpoints": [{"url": format!("{}/all", server.url())}]}}
+            ]
+        });
+        for (key, value) in extra.as_object().unwrap() {
+            definition[key] = value.clone();
+        }
+
+        serde_json::from_value(definition).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_route_condition_gates_every_destination() {
+        let mut server = mockito::Server::new_async().await;
+        let all = server.mock("POST", "/all").with_status(200).expect(1).create_async().await;
+
+        let definition = order_route(&server, json!({"condition": "size > 10", "rules": [{
+            "id": "everything",
+            "name": "Everything",
+            "enabled": true,
+            "priority": 1,
+            "destination_ids": ["all"],
+            "transformations": []
+        }]}));
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+        let route = Route::new(definition.config.clone(), definition.rules.clone()).unwrap()
+            .with_dead_letters(Arc::clone(&dead_letters));
+
+        route.process(json!({"size": 20})).await.unwrap();
+
+        // A record that doesn't match goes nowhere, even though the rule has no condition
+        let results = route.process(json!({"size": 1})).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].rule_id.as_str(), &results[0].status), ("condition", &RoutingStatus::Success));
+        all.assert_async().await;
+
+        // One the condition can't be evaluated on is dead-lettered
+        let results = route.process(json!({"size": "big"})).await.unwrap();
+        assert!(matches!(results[0].status, RoutingStatus::Failure(_)));
+        let letters = dead_letters.list(&DeadLetterFilter::default()).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!((letters[0].stage, &letters[0].record), (FailureStage::Condition, &json!({"size": "big"})));
+
+        // and aborts a route that fails on errors
+        let mut config = definition.config;
+        config.error_handling = serde_json::from_value(json!({"on_error": "fail"})).unwrap();
+        let failing = Route::new(config, definition.rules).unwrap();
+        assert!(failing.process(json!({"size": "big"})).await.is_err());
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_stop_on_match_ends_rule_evaluation() {
+        let mut server = mockito::Server::new_async().await;
+        let large = server.mock("POST", "/large").with_status(200).expect(2).create_async().await;
+        let all = server.mock("POST", "/all").with_status(200).expect(2).create_async().await;
+
+        let definition = order_route(&server, json!({"rules": [
+            {"id": "large", "name": "Large", "enabled": true, "condition": "size > 10", "priority": 1,
+                "destination_ids": ["large"], "transformations": [], "stop_on_match": true},
+            {"id": "all", "name": "All", "enabled": true, "priority": 2,
+                "destination_ids": ["all"], "transformations": []}
+        ]}));
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+        let route = Route::new(definition.config, definition.rules).unwrap()
+            .with_dead_letters(Arc::clone(&dead_letters));
+
+        // The first matching rule with stop_on_match wins
+        for _ in 0..2 {
+            let results = route.process(json!({"size": 20})).await.unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].routed_to, vec!["large".to_string()]);
+        }
+        large.assert_async().await;
+
+        // Records it doesn't match go on to the next rule
+        route.process(json!({"size": 1})).await.unwrap();
+
+        // So does one it can't be evaluated on, after being dead-lettered
+        let results = route.process(json!({"size": "big"})).await.unwrap();
+        assert_eq!(results[0].rule_id, "large");
+        assert!(matches!(results[0].status, RoutingStatus::Failure(_)));
+        assert_eq!(results[1].routed_to, vec!["all".to_string()]);
+        all.assert_async().await;
+
+        let letters = dead_letters.list(&DeadLetterFilter::default()).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!((letters[0].stage, letters[0].rule_id.as_deref()), (FailureStage::Condition, Some("large")));
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_claims_a_key_once() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let store = DedupStore::new(Arc::clone(&pool));
+        let window = Duration::from_secs(60);
+
+        let (first, second) = tokio::join!(
+            store.claim("orders", "key", window),
+            store.claim("orders", "key", window),
+        );
+        let mut claims = vec![first.unwrap(), second.unwrap()];
+        claims.sort();
+        assert_eq!(claims, vec![false, true]);
+
+        // Another route has its own keys, and a released key can be claimed again
+        assert!(store.claim("refunds", "key", window).await.unwrap());
+        store.release("orders", "key").await.unwrap();
+        assert!(store.claim("orders", "key", window).await.unwrap());
+
+        // So can an expired one
+        assert!(store.claim("expiring", "key", Duration::ZERO).await.unwrap());
+        assert!(store.claim("expiring", "key", window).await.unwrap());
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_batching_dead_letters_records_beyond_ten_batches() {
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let dead_letters = Arc::new(DeadLetterStore::new(Arc::clone(&pool)));
+
+        let destination: Arc<dyn Destination> = BatchingDestination::start(
+            TestDestination::new("chat", 0, true),
+            BatchSettings { flush_interval: 3600, batch_size: 1 },
+        );
+        destination.set_dead_letters(Arc::clone(&dead_letters), "alerts", "alerts/chat");
+        assert!(destination.is_buffered());
+
+        // Every send fills a batch that fails, so records pile up
+        for n in 0..12 {
+            destination.send(json!({"n": n})).await.unwrap();
+        }
+
+        let letters = dead_letters.list(&DeadLetterFilter::default()).await.unwrap();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[0].route_id, "alerts");
+        assert_eq!(letters[0].destination.as_deref(), Some("alerts/chat"));
+
+        let mut evicted: Vec<Value> = letters.into_iter().map(|letter| letter.record).collect();
+        evicted.sort_by_key(|record| record["n"].as_i64());
+        assert_eq!(evicted, vec![json!({"n": 0}), json!({"n": 1})]);
+
+        // The ten newest are still held for the next delivery
+        assert!(destination.flush().await.is_err());
+        assert_eq!(dead_letters.list(&DeadLetterFilter::default()).await.unwrap().len(), 2);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_renders_the_text_exposition_format() {
+        let destination = PrometheusDestination::new("prometheus".to_string(), serde_json::from_value(json!({
+            "metric_prefix": "muxly_",
+            "metrics": [
+                {"name": "orders_total", "type": "counter", "description": "Orders\nplaced",
+                    "value_field": "count", "labels": {"country": "country"}},
+                {"name": "active_users", "value_field": "active"},
+                {"name": "latency_seconds", "type": "histogram", "value_field": "timing.latency", "buckets": [1, 0.3]}
+            ]
+        })).unwrap());
+
+        destination.send_batch(vec![
+            json!({"country": "US \"east\"", "count": 2, "active": 10, "timing": {"latency": 0.25}}),
+            json!({"country": "US \"east\"", "count": 3, "active": 7, "timing": {"latency": 0.5}}),
+        ]).await.unwrap();
+
+        assert_eq!(destination.scrape().unwrap(), concat!(
+            "# HELP muxly_active_users Value of field active routed by Muxly\n",
+            "# TYPE muxly_active_users gauge\n",
+            "muxly_active_users 7\n",
+            "# HELP muxly_latency_seconds Value of field timing.latency routed by Muxly\n",
+            "# TYPE muxly_latency_seconds histogram\n",
+            "muxly_latency_seconds_bucket{le=\"0.3\"} 1\n",
+            "muxly_latency_seconds_bucket{le=\"1\"} 2\n",
+            "muxly_latency_seconds_bucket{le=\"+Inf\"} 2\n",
+            "muxly_latency_seconds_sum 0.75\n",
+            "muxly_latency_seconds_count 2\n",
+            "# HELP muxly_orders_total Orders\\nplaced\n",
+            "# TYPE muxly_orders_total counter\n",
+            "muxly_orders_total{country=\"US \\\"east\\\"\"} 5\n",
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_counters_add_up_and_gauges_keep_the_latest_value() {
+        let records = vec![
+            json!({"connector_id": "ga4", "country": "US", "sessions": 3}),
+            json!({"connector_id": "ga4", "country": "FR", "sessions": 4}),
+        ];
+
+        let counters = PrometheusDestination::new("counters".to_string(), serde_json::from_value(json!({
+            "default_metric_type": "counter",
+            "include_labels": false,
+            "labels": {"source": "connector_id"}
+        })).unwrap());
+        counters.send_batch(records.clone()).await.unwrap();
+
+        let rendered = counters.scrape().unwrap();
+        assert!(rendered.contains("# TYPE sessions counter\n"), "{}", rendered);
+        assert!(rendered.contains("sessions{source=\"ga4\"} 7\n"), "{}", rendered);
+        // Only the listed fields become labels
+        assert!(!rendered.contains("country"), "{}", rendered);
+
+        // String fields don't become labels by default, so a high-cardinality
+        // field can't create a series per record
+        let unlabelled = PrometheusDestination::new("unlabelled".to_string(), serde_json::from_value(json!({})).unwrap());
+        unlabelled.send_batch(records.clone()).await.unwrap();
+        assert!(unlabelled.scrape().unwrap().contains("\nsessions 4\n"));
+
+        let gauges = PrometheusDestination::new("gauges".to_string(), serde_json::from_value(json!({
+            "include_labels": true
+        })).unwrap());
+        gauges.send_batch(records).await.unwrap();
+
+        let rendered = gauges.scrape().unwrap();
+        assert!(rendered.contains("# TYPE sessions gauge\n"), "{}", rendered);
+        assert!(rendered.contains("\nsessions{connector_id=\"ga4\",country=\"FR\"} 4\n"), "{}", rendered);
+        assert!(rendered.contains("\nsessions{connector_id=\"ga4\",country=\"US\"} 3\n"), "{}", rendered);
+
+        // A configured label keeps its name over a string field
+        let renamed = PrometheusDestination::new("renamed".to_string(), serde_json::from_value(json!({
+            "include_labels": true,
+            "labels": {"country": "connector_id"}
+        })).unwrap());
+        renamed.send(json!({"connector_id": "ga4", "country": "US", "sessions": 3})).await.unwrap();
+        assert!(renamed.scrape().unwrap().contains("\nsessions{connector_id=\"ga4\",country=\"ga4\"} 3\n"));
+
+        // Counters can't go down
+        assert!(counters.send(json!({"connector_id": "ga4", "sessions": -1})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_pushes_to_the_pushgateway() {
+        let mut server = mockito::Server::new_async().await;
+        let push = server.mock("PUT", "/metrics/job/muxly_ga4/instance/web-1")
+            .match_header("content-type", "text/plain; version=0.0.4; charset=utf-8")
+            .match_header("authorization", "Basic dXNlcjpwYXNz")
+            .match_body(mockito::Matcher::Regex("(?m)^sessions 3$".to_string()))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let destination = PrometheusDestination::new("prometheus".to_string(), serde_json::from_value(json!({
+            "pushgateway": {
+                "url": server.url(),
+                "job": "muxly_ga4",
+                "grouping": {"instance": "web-1"},
+                "username": "user",
+                "password": "pass"
+            }
+        })).unwrap());
+
+        // One push per batch
+        destination.send_batch(vec![json!({"sessions": 1}), json!({"sessions": 3})]).await.unwrap();
+        push.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_counts_a_retried_batch_once() {
+        let mut server = mockito::Server::new_async().await;
+        let push = server.mock("PUT", "/metrics/job/muxly")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let destination = PrometheusDestination::new("prometheus".to_string(), serde_json::from_value(json!({
+            "default_metric_type": "counter",
+            "include_labels": false,
+            "pushgateway": {"url": server.url()}
+        })).unwrap());
+
+        // A bad record rejects the whole batch before anything is counted
+        let batch = vec![json!({"sessions": 2}), json!({"sessions": -1})];
+        assert!(destination.send_batch(batch).await.is_err());
+        assert!(!destination.scrape().unwrap().contains("sessions"));
+
+        // A failed push doesn't fail the delivery of records already counted
+        destination.send_batch(vec![json!({"sessions": 2}), json!({"sessions": 3})]).await.unwrap();
+        assert!(destination.scrape().unwrap().contains("\nsessions 5\n"));
+        push.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics_are_scraped_through_the_api() {
+        use axum::{body::Body, http::{Request, StatusCode}, Extension};
+        use tower::ServiceExt;
+
+        let definition: RouteDefinition = serde_json::from_value(json!({
+            "id": "ga4",
+            "name": "GA4",
+            "enabled": true,
+            "source": {"connector_id": "ga4", "data_spec": {}},
+            "destinations": [{"id": "metrics", "destination_type": "prometheus", "config": {}}]
+        })).unwrap();
+
+        let routes = Arc::new(RouteRegistry::new());
+        let route = routes.insert(Route::new(definition.config, definition.rules).unwrap());
+        route.destination("metrics").unwrap().send(json!({"sessions": 3})).await.unwrap();
+
+        let metrics = Arc::new(DeliveryMetrics::new());
+        let app = crate::api::metrics_router("/scrape/muxly")
+            .layer(Extension(routes))
+            .layer(Extension(metrics));
+
+        let request = Request::get("/scrape/muxly").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("\nsessions 3\n"));
+
+        let request = Request::get("/metrics").body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_destinations_take_the_router_defaults() {
+        let mut config = RouterConfig::default();
+        config.prometheus.include_labels = true;
+        config.prometheus.default_metric_type = "counter".to_string();
+        config.routes = vec![serde_json::from_value(json!({
+            "id": "ga4",
+            "name": "GA4",
+            "enabled": true,
+            "source": {"connector_id": "ga4", "data_spec": {}},
+            "destinations": [
+                {"id": "defaults", "destination_type": "prometheus", "config": {}},
+                {"id": "own", "destination_type": "prometheus", "config": {"include_labels": false}}
+            ]
+        })).unwrap()];
+
+        let (db_config, path) = test_database();
+        let pool = init_database(&db_config).await.unwrap();
+        let router = RouterIntegration::start(Arc::clone(&pool), &config).await.unwrap();
+        let route = router.routes.get("ga4").unwrap();
+
+        let record = json!({"country": "US", "sessions": 3});
+        for id in ["defaults", "own"] {
+            let destination = route.destination(id).unwrap();
+            destination.send(record.clone()).await.unwrap();
+            destination.send(record.clone()).await.unwrap();
+        }
+
+        // Options the destination leaves out come from the router's settings
+        let rendered = route.destination("defaults").unwrap().scrape().unwrap();
+        assert!(rendered.contains("# TYPE sessions counter\n"), "{}", rendered);
+        assert!(rendered.contains("\nsessions{country=\"US\"} 6\n"), "{}", rendered);
+
+        // Its own options win
+        let rendered = route.destination("own").unwrap().scrape().unwrap();
+        assert!(rendered.contains("\nsessions 6\n"), "{}", rendered);
+
+        router.stop().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_file(path);
+    }
+
+    fn test_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("muxly-files-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_file_appends_json_as_one_record_per_line() {
+        let dir = test_dir();
+        let path = dir.join("orders.json");
+        let destination = FileDestination::new("file".to_string(), serde_json::from_value(json!({
+            "path": path.display().to_string(),
+            "format": "json"
+        })).unwrap());
+
+        destination.send(json!({"order": 1, "items": {"sku": "a"}})).await.unwrap();
+        destination.send_batch(vec![json!({"order": 2}), json!({"order": 3})]).await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records, vec![json!({"order": 1, "items": {"sku": "a"}}), json!({"order": 2}), json!({"order": 3})]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_file_rotates_when_the_size_limit_is_reached() {
+        let dir = test_dir();
+        let path = dir.join("export.jsonl");
+        let destination = FileDestination::new("file".to_string(), serde_json::from_value(json!({
+            "path": path.display().to_string(),
+            "format": "jsonl",
+            "rotate_files": true,
+            "rotation": "none",
+            "max_file_size_mb": 1
+        })).unwrap());
+
+        // Two of these don't fit in one file
+        let padding = "x".repeat(600 * 1024);
+        destination.send(json!({"n": 1, "padding": padding})).await.unwrap();
+        destination.send(json!({"n": 2, "padding": padding})).await.unwrap();
+
+        let rolled: Value = serde_json::from_str(&std::fs::read_to_string(dir.join("export.1.jsonl")).unwrap()).unwrap();
+        let current: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rolled["n"], 1);
+        assert_eq!(current["n"], 2);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_file_rolls_over_a_file_from_an_earlier_period() {
+        let dir = test_dir();
+        let path = dir.join("export.jsonl");
+        std::fs::write(&path, "{\"n\":0}\n").unwrap();
+        std::fs::File::options().write(true).open(&path).unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(2 * 24 * 3600))
+            .unwrap();
+
+        let destination = FileDestination::new("file".to_string(), serde_json::from_value(json!({
+            "path": path.display().to_string(),
+            "format": "jsonl",
+            "rotate_files": true,
+            "rotation": "daily",
+            "compress": true
+        })).unwrap());
+        destination.send(json!({"n": 1})).await.unwrap();
+
+        // The file left by the earlier day is rolled before the write, and
+        // gzipped in the background by the time the destination is flushed
+        destination.flush().await.unwrap();
+        let mut rolled = String::new();
+        let gz = std::fs::File::open(dir.join("export.1.jsonl.gz")).unwrap();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(gz), &mut rolled).unwrap();
+        assert_eq!(rolled, "{\"n\":0}\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"n\":1}\n");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_file_rejects_new_csv_columns_unless_it_can_rotate() {
+        let dir = test_dir();
+        let config = |rotate_files: bool| serde_json::from_value(json!({
+            "path": dir.join(format!("rotate-{}.csv", rotate_files)).display().to_string(),
+            "format": "csv",
+            "rotate_files": rotate_files,
+            "rotation": "none"
+        })).unwrap();
+
+        let fixed = FileDestination::new("fixed".to_string(), config(false));
+        fixed.send(json!({"a": 1})).await.unwrap();
+        let error = fixed.send(json!({"a": 2, "b": 3})).await.unwrap_err();
+        assert!(error.to_string().contains("[\"b\"]"), "{}", error);
+        // Records that fit the header are still written
+        fixed.send(json!({"a": 4})).await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("rotate-false.csv")).unwrap(), "a\n1\n4\n");
+
+        let rotating = FileDestination::new("rotating".to_string(), config(true));
+        rotating.send(json!({"a": 1})).await.unwrap();
+        rotating.send(json!({"a": 2, "b": 3})).await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("rotate-true.1.csv")).unwrap(), "a\n1\n");
+        assert_eq!(std::fs::read_to_string(dir.join("rotate-true.csv")).unwrap(), "a,b\n2,3\n");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_webhook_signature_covers_the_timestamp_and_body() {
+        use super::super::destinations::{sign_payload, verify_signature};
+
+        let body = br#"{"order":1}"#;
+        let signature = sign_payload("whsec", 1700000000, body);
+        // HMAC-SHA256 of "1700000000.{\"order\":1}" keyed with "whsec"
+        assert_eq!(signature, "sha256=cd20a8b886718fca7dd2babaebe0f34cbb44abe0f9efa1cfed83d813acae9bff");
+        assert!(verify_signature("whsec", 1700000000, body, &signature));
+
+        assert!(!verify_signature("other", 1700000000, body, &signature));
+        assert!(!verify_signature("whsec", 1700000001, body, &signature));
+        assert!(!verify_signature("whsec", 1700000000, br#"{"order":2}"#, &signature));
+
+        // Tampered, truncated, unprefixed and non-hex signatures are all rejected
+        let tampered = signature.replace("cd20", "cd21");
+        assert!(!verify_signature("whsec", 1700000000, body, &tampered));
+        assert!(!verify_signature("whsec", 1700000000, body, &signature[..signature.len() - 2]));
+        assert!(!verify_signature("whsec", 1700000000, body, signature.trim_start_matches("sha256=")));
+        assert!(!verify_signature("whsec", 1700000000, body, "sha256=not-hex"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_signs_requests_and_filters_endpoints_by_event_type() {
+        use super::super::destinations::verify_signature;
+
+        let mut server = mockito::Server::new_async().await;
+        let verified = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let orders = {
+            let verified = Arc::clone(&verified);
+            server.mock("POST", "/orders")
+                .match_header("x-muxly-event", "order.created")
+                .match_header("x-muxly-signature", mockito::Matcher::Regex("^sha256=[0-9a-f]{64}$".to_string()))
+                .with_status(200)
+                .with_body_from_request(move |request| {
+                    let header = |name: &'static str| {
+                        request.header(name).first().and_then(|value| value.to_str().ok()).unwrap_or_default().to_string()
+                    };
+                    let timestamp = header("x-muxly-timestamp").parse().unwrap_or_default();
+                    let body = request.body().cloned().unwrap_or_default();
+                    verified.lock().unwrap().push(verify_signature("whsec", timestamp, &body, &header("x-muxly-signature")));
+                    Vec::new()
+                })
+                .expect(1)
+                .create_async()
+                .await
+        };
+        let refunds = server.mock("POST", "/refunds").expect(0).create_async().await;
+        let audit = server.mock("POST", "/audit")
+            .match_body(mockito::Matcher::Json(json!({"event_type": "order.created", "order": 1})))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let destination = WebhookDestination::new("hooks".to_string(), serde_json::from_value(json!({
+            "endpoints": [
+                {"url": format!("{}/orders", server.url()), "secret": "whsec", "event_types": ["order.created"]},
+                {"url": format!("{}/refunds", server.url()), "event_types": ["refund.created"]},
+                {"url": format!("{}/audit", server.url()), "event_types": ["*"]}
+            ]
+        })).unwrap());
+
+        destination.send(json!({"event_type": "order.created", "order": 1})).await.unwrap();
+
+        orders.assert_async().await;
+        refunds.assert_async().await;
+        audit.assert_async().await;
+        assert_eq!(*verified.lock().unwrap(), vec![true]);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_batches_as_arrays_only_when_configured() {
+        let mut server = mockito::Server::new_async().await;
+        let records = vec![json!({"order": 1}), json!({"order": 2})];
+        let singles: Vec<_> = {
+            let mut mocks = Vec::new();
+            for record in &records {
+                mocks.push(server.mock("POST", "/single")
+                    .match_body(mockito::Matcher::Json(record.clone()))
+                    .with_status(200)
+                    .expect(1)
+                    .create_async()
+                    .await);
+            }
+            mocks
+        };
+        let array = server.mock("POST", "/array")
+            .match_body(mockito::Matcher::Json(json!([{"order": 1}, {"order": 2}])))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let single = WebhookDestination::new("single".to_string(), serde_json::from_value(json!({
+            "endpoints": [{"url": format!("{}/single", server.url())}]
+        })).unwrap());
+        single.send_batch(records.clone()).await.unwrap();
+
+        let batched = WebhookDestination::new("array".to_string(), serde_json::from_value(json!({
+            "endpoints": [{"url": format!("{}/array", server.url())}],
+            "batch_format": "array"
+        })).unwrap());
+        batched.send_batch(records).await.unwrap();
+
+        for mock in singles {
+            mock.assert_async().await;
+        }
+        array.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retries_only_post_to_the_endpoints_that_failed() {
+        let mut server = mockito::Server::new_async().await;
+        let accepting = server.mock("POST", "/accepting").with_status(200).expect(1).create_async().await;
+        let failing = server.mock("POST", "/failing").with_status(503).expect(1).create_async().await;
+
+        let destination = WebhookDestination::new("hooks".to_string(), serde_json::from_value(json!({
+            "endpoints": [
+                {"url": format!("{}/accepting", server.url())},
+                {"url": format!("{}/failing", server.url())}
+            ]
+        })).unwrap());
+
+        let error = destination.send(json!({"order": 1})).await.unwrap_err();
+        let partial = error.downcast_ref::<PartialDelivery>().unwrap();
+        assert_eq!(partial.failed, vec![0]);
+        assert!(partial.message.contains("/failing: returned 503"), "{}", partial.message);
+        assert!(!partial.message.contains("/accepting"), "{}", partial.message);
+        accepting.assert_async().await;
+        failing.assert_async().await;
+
+        // The retry only goes to the endpoint that failed, and once it
+        // accepts the record is forgotten
+        let recovered = server.mock("POST", "/failing").with_status(200).expect(2).create_async().await;
+        destination.send(json!({"order": 1})).await.unwrap();
+        accepting.assert_async().await;
+
+        let accepting = server.mock("POST", "/accepting").with_status(200).expect(1).create_async().await;
+        destination.send(json!({"order": 1})).await.unwrap();
+        accepting.assert_async().await;
+        recovered.assert_async().await;
+
+        // When every endpoint fails it's a plain error
+        let down = WebhookDestination::new("down".to_string(), serde_json::from_value(json!({
+            "endpoints": [{"url": format!("{}/down", server.url())}]
+        })).unwrap());
+        server.mock("POST", "/down").with_status(500).create_async().await;
+        let error = down.send(json!({"order": 1})).await.unwrap_err();
+        assert!(error.downcast_ref::<PartialDelivery>().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_destinations_take_the_router_webhook_defaults() {
+        // An endpoint that takes the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let _accept = tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let mut config = RouterConfig::default();
+        config.webhook.timeout = 1;
+        config.webhook.max_concurrency = 2;
+        let settings = serde_json::from_value(json!({
+            "destination_type": "webhook",
+            "config": {"endpoints": [{"url": format!("http://{}/hook", address)}]}
+        })).unwrap();
+        let destination = super::super::DestinationFactory::create_destination_with_defaults(
+            &settings,
+            &super::super::DestinationDefaults::from(&config),
+        ).unwrap();
+
+        let started = Instant::now();
+        assert!(destination.send(json!({"order": 1})).await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+    }
+
+    /// Collector path that answers 200 and keeps the body of every export
+    async fn otlp_collector(server: &mut mockito::Server, path: &str) -> (mockito::Mock, Arc<std::sync::Mutex<Vec<Vec<u8>>>>) {
+        let bodies = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mock = {
+            let bodies = Arc::clone(&bodies);
+            server.mock("POST", path)
+                .match_header("content-type", "application/x-protobuf")
+                .match_header("x-api-key", "secret")
+                .with_status(200)
+                .with_body_from_request(move |request| {
+                    bodies.lock().unwrap().push(request.body().cloned().unwrap_or_default());
+                    Vec::new()
+                })
+                .expect(1)
+                .create_async()
+                .await
+        };
+
+        (mock, bodies)
+    }
+
+    #[tokio::test]
+    async fn test_otlp_exports_metrics_over_http() {
+        use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+        use opentelemetry_proto::tonic::common::v1::any_value;
+        use opentelemetry_proto::tonic::metrics::v1::{metric, number_data_point, AggregationTemporality};
+        use prost::Message;
+
+        let mut server = mockito::Server::new_async().await;
+        let (export, bodies) = otlp_collector(&mut server, "/v1/metrics").await;
+
+        let destination = OtlpDestination::new("otel".to_string(), serde_json::from_value(json!({
+            "endpoint": server.url(),
+            "headers": {"x-api-key": "secret"},
+            "metrics": [
+                {"name": "sessions", "type": "sum", "value_field": "sessions", "unit": "{sessions}"},
+                {"name": "bounce_rate", "value_field": "stats.bounce"}
+            ],
+            "attributes": {"country": "country"},
+            "resource_attributes": {"service.name": "analytics"},
+            "timestamp_field": "time"
+        })).unwrap());
+
+        destination.send_batch(vec![
+            json!({"country": "FR", "sessions": 3, "stats": {"bounce": 0.25}, "time": "2023-11-14T22:13:20Z"}),
+            json!({"country": "DE", "sessions": 5, "time": 1700000060}),
+        ]).await.unwrap();
+
+        export.assert_async().await;
+        let request = ExportMetricsServiceRequest::decode(bodies.lock().unwrap()[0].as_slice()).unwrap();
+
+        let resource_metrics = &request.resource_metrics[0];
+        let resource = resource_metrics.resource.as_ref().unwrap();
+        assert_eq!(resource.attributes.len(), 1);
+        assert_eq!(resource.attributes[0].key, "service.name");
+        assert_eq!(
+            resource.attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("analytics".to_string()))
+        );
+
+        let scope_metrics = &resource_metrics.scope_metrics[0];
+        assert_eq!(scope_metrics.scope.as_ref().unwrap().name, "muxly.router");
+        assert_eq!(scope_metrics.metrics.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["sessions", "bounce_rate"]);
+
+        let sessions = &scope_metrics.metrics[0];
+        assert_eq!(sessions.unit, "{sessions}");
+        let sum = match &sessions.data {
+            Some(metric::Data::Sum(sum)) => sum,
+            other => panic!("sessions should be a sum, got {:?}", other),
+        };
+        assert!(sum.is_monotonic);
+        assert_eq!(sum.aggregation_temporality, AggregationTemporality::Delta as i32);
+        assert_eq!(
+            sum.data_points.iter().map(|point| (point.value.clone(), point.time_unix_nano)).collect::<Vec<_>>(),
+            vec![
+                (Some(number_data_point::Value::AsInt(3)), 1_700_000_000_000_000_000),
+                (Some(number_data_point::Value::AsInt(5)), 1_700_000_060_000_000_000),
+            ]
+        );
+        assert_eq!(sum.data_points[1].attributes[0].key, "country");
+        assert_eq!(
+            sum.data_points[1].attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("DE".to_string()))
+        );
+
+        // Only the record that has the field is a data point of the gauge
+        let gauge = match &scope_metrics.metrics[1].data {
+            Some(metric::Data::Gauge(gauge)) => gauge,
+            other => panic!("bounce_rate should be a gauge, got {:?}", other),
+        };
+        assert_eq!(gauge.data_points.len(), 1);
+        assert_eq!(gauge.data_points[0].value, Some(number_data_point::Value::AsDouble(0.25)));
+    }
+
+    #[tokio::test]
+    async fn test_otlp_exports_logs_over_http() {
+        use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+        use opentelemetry_proto::tonic::common::v1::any_value;
+        use opentelemetry_proto::tonic::logs::v1::SeverityNumber;
+        use prost::Message;
+
+        let mut server = mockito::Server::new_async().await;
+        let (export, bodies) = otlp_collector(&mut server, "/v1/logs").await;
+
+        let destination = OtlpDestination::new("otel".to_string(), serde_json::from_value(json!({
+            "endpoint": format!("{}/", server.url()),
+            "signal": "logs",
+            "headers": {"x-api-key": "secret"},
+            "attributes": {"connector": "connector_id"},
+            "body_field": "message",
+            "severity_field": "level",
+            "timestamp_field": "time"
+        })).unwrap());
+
+        destination.send_batch(vec![
+            json!({"message": "sync finished", "level": "WARN", "connector_id": "ga4", "time": 1700000000}),
+            json!({"connector_id": "hubspot", "time": 1700000060}),
+        ]).await.unwrap();
+
+        export.assert_async().await;
+        let request = ExportLogsServiceRequest::decode(bodies.lock().unwrap()[0].as_slice()).unwrap();
+
+        let resource_logs = &request.resource_logs[0];
+        let resource = resource_logs.resource.as_ref().unwrap();
+        assert_eq!(resource.attributes[0].key, "service.name");
+        assert_eq!(
+            resource.attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("muxly".to_string()))
+        );
+
+        let records = &resource_logs.scope_logs[0].log_records;
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].time_unix_nano, 1_700_000_000_000_000_000);
+        assert_eq!(records[0].severity_text, "WARN");
+        assert_eq!(records[0].severity_number, SeverityNumber::Warn as i32);
+        assert_eq!(
+            records[0].body.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("sync finished".to_string()))
+        );
+        assert_eq!(records[0].attributes[0].key, "connector");
+        assert_eq!(
+            records[0].attributes[0].value.as_ref().unwrap().value,
+            Some(any_value::Value::StringValue("ga4".to_string()))
+        );
+
+        // A record without the body or severity fields is still exported
+        assert_eq!(records[1].severity_number, SeverityNumber::Unspecified as i32);
+        assert_eq!(records[1].body.as_ref().unwrap().value, Some(any_value::Value::StringValue(String::new())));
+    }
+
+    #[tokio::test]
+    async fn test_otlp_partial_success_is_counted_but_not_retried() {
+        use opentelemetry_proto::tonic::collector::metrics::v1::{ExportMetricsPartialSuccess, ExportMetricsServiceResponse};
+        use prost::Message;
+
+        let response = ExportMetricsServiceResponse {
+            partial_success: Some(ExportMetricsPartialSuccess {
+                rejected_data_points: 1,
+                error_message: "negative value".to_string(),
+            }),
+        };
+
+        let mut server = mockito::Server::new_async().await;
+        let export = server.mock("POST", "/v1/metrics")
+            .with_status(200)
+            .with_header("content-type", "application/x-protobuf")
+            .with_body(response.encode_to_vec())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let destination = OtlpDestination::new("otel".to_string(), serde_json::from_value(json!({
+            "endpoint": server.url(),
+            "metrics": [{"name": "sessions", "type": "sum", "value_field": "sessions"}]
+        })).unwrap());
+
+        // The collector took the export, so it isn't retried
+        destination.send_batch(vec![json!({"sessions": 3}), json!({"sessions": -1})]).await.unwrap();
+        assert_eq!(destination.rejected(), 1);
+        export.assert_async().await;
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    #[test]
+    fn test_kafka_destinations_need_the_kafka_feature() {
+        let settings = serde_json::from_value(json!({
+            "destination_type": "kafka",
+            "config": {"brokers": "localhost:9092", "topic": "records"}
+        })).unwrap();
+
+        let error = super::super::DestinationFactory::create_destination_with_defaults(&settings, &DestinationDefaults::default()).err().unwrap();
+        assert!(error.to_string().contains("`kafka` feature"), "{}", error);
+    }
+
+    #[cfg(feature = "kafka")]
+    #[test]
+    fn test_kafka_topic_template_uses_sanitized_record_fields() {
+        use super::super::KafkaDestination;
+
+        let destination = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "muxly.{{connector_id}}.{{ meta.region }}"
+        })).unwrap());
+
+        assert_eq!(
+            destination.topic(&json!({"connector_id": "ga4 web/app", "meta": {"region": "eu-west-1"}})),
+            Some("muxly.ga4_web_app.eu-west-1".to_string())
+        );
+        assert_eq!(
+            destination.topic(&json!({"connector_id": 42, "meta": {"region": "us"}})),
+            Some("muxly.42.us".to_string())
+        );
+        // A record missing a placeholder field has no topic
+        assert_eq!(destination.topic(&json!({"connector_id": "ga4"})), None);
+    }
+
+    #[cfg(feature = "kafka")]
+    #[test]
+    fn test_kafka_key_and_headers_come_from_record_fields() {
+        use super::super::KafkaDestination;
+        use rdkafka::message::Headers;
+
+        let headers = |destination: &KafkaDestination, data: &Value| -> Vec<(String, String)> {
+            destination.headers(data).iter()
+                .map(|header| (header.key.to_string(), String::from_utf8_lossy(header.value.unwrap_or_default()).into_owned()))
+                .collect()
+        };
+
+        let defaults = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "records"
+        })).unwrap());
+        assert_eq!(defaults.key(&json!({"account_id": "a-1"})), None);
+        assert_eq!(
+            headers(&defaults, &json!({"collection_id": "c-1", "connector_id": "ga4"})),
+            vec![("collection_id".to_string(), "c-1".to_string()), ("connector_id".to_string(), "ga4".to_string())]
+        );
+
+        let destination = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "records",
+            "key_field": "account.id",
+            "headers": {"region": "account.region", "tier": "account.tier", "source": "source"}
+        })).unwrap());
+
+        let record = json!({"account": {"id": 42, "region": "eu", "tier": null}, "source": "ga4"});
+        assert_eq!(destination.key(&record), Some("42".to_string()));
+        // Null and missing fields are left out of the headers
+        assert_eq!(
+            headers(&destination, &record),
+            vec![("region".to_string(), "eu".to_string()), ("source".to_string(), "ga4".to_string())]
+        );
+        assert_eq!(destination.key(&json!({"account": {"id": null}})), None);
+    }
+
+    #[cfg(feature = "kafka")]
+    #[test]
+    fn test_kafka_sets_aside_records_without_a_topic_and_tracks_positions() {
+        use super::super::KafkaDestination;
+
+        let records = vec![
+            json!({"connector_id": "ga4", "n": 1}),
+            json!({"n": 2}),
+            json!({"connector_id": "hubspot", "n": 3}),
+            json!({"connector_id": "ga4", "n": 4}),
+        ];
+
+        let per_record = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "muxly.{{connector_id}}"
+        })).unwrap());
+        let (messages, missing) = per_record.messages(&records).unwrap();
+        assert_eq!(
+            messages.iter().map(|message| (message.topic.as_str(), message.records.clone())).collect::<Vec<_>>(),
+            vec![("muxly.ga4", vec![0]), ("muxly.hubspot", vec![2]), ("muxly.ga4", vec![3])]
+        );
+        assert_eq!(missing, vec![1]);
+
+        let per_batch = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "muxly.{{connector_id}}",
+            "message_per_batch": true
+        })).unwrap());
+        let (messages, missing) = per_batch.messages(&records).unwrap();
+        assert_eq!(
+            messages.iter().map(|message| (message.topic.as_str(), message.records.clone())).collect::<Vec<_>>(),
+            vec![("muxly.ga4", vec![0, 3]), ("muxly.hubspot", vec![2])]
+        );
+        assert_eq!(missing, vec![1]);
+        assert_eq!(
+            serde_json::from_str::<Value>(&messages[0].payload).unwrap(),
+            json!([{"connector_id": "ga4", "n": 1}, {"connector_id": "ga4", "n": 4}])
+        );
+    }
+
+    #[cfg(feature = "kafka")]
+    #[tokio::test]
+    async fn test_kafka_fails_records_without_a_topic() {
+        use super::super::KafkaDestination;
+
+        let destination = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "muxly.{{connector_id}}"
+        })).unwrap());
+
+        let records = vec![
+            json!({"connector_id": "ga4", "n": 1}),
+            json!({"n": 2}),
+            json!({"connector_id": "hubspot", "n": 3}),
+        ];
+        let (messages, missing) = destination.messages(&records).unwrap();
+
+        // The brokers took both messages, so only the record without a topic failed
+        let error = destination.outcome(&messages, Vec::new(), missing.clone()).unwrap_err();
+        let partial = error.downcast_ref::<PartialDelivery>().unwrap();
+        assert_eq!(partial.failed, vec![1]);
+        assert!(partial.message.contains("1 records are missing a field of topic"), "{}", partial.message);
+
+        // Records of failed messages are reported alongside, in batch order
+        let failed = vec![(1, "muxly.hubspot: timed out".to_string())];
+        let error = destination.outcome(&messages, failed, missing).unwrap_err();
+        let partial = error.downcast_ref::<PartialDelivery>().unwrap();
+        assert_eq!(partial.failed, vec![1, 2]);
+        assert!(partial.message.contains("1 of 2 messages failed"), "{}", partial.message);
+
+        // With no topic for any record, nothing is produced and the delivery fails
+        let error = destination.send_batch(vec![json!({"n": 1}), json!({"n": 2})]).await.unwrap_err();
+        assert!(error.downcast_ref::<PartialDelivery>().is_none());
+        assert!(error.to_string().contains("2 records are missing a field of topic"), "{}", error);
+    }
+
+    #[cfg(feature = "kafka")]
+    #[test]
+    fn test_kafka_properties_override_the_idempotent_producer_settings() {
+        use super::super::KafkaDestination;
+
+        let idempotent = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "kafka-1:9092,kafka-2:9092",
+            "topic": "records",
+            "compression": "zstd"
+        })).unwrap()).client_config();
+        assert_eq!(idempotent.get("bootstrap.servers"), Some("kafka-1:9092,kafka-2:9092"));
+        assert_eq!(idempotent.get("client.id"), Some("muxly-kafka"));
+        assert_eq!(idempotent.get("compression.type"), Some("zstd"));
+        assert_eq!(idempotent.get("enable.idempotence"), Some("true"));
+        assert_eq!(idempotent.get("acks"), Some("all"));
+
+        let overridden = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "records",
+            "properties": {"enable.idempotence": "false", "acks": "1", "security.protocol": "SASL_SSL"}
+        })).unwrap()).client_config();
+        assert_eq!(overridden.get("enable.idempotence"), Some("false"));
+        assert_eq!(overridden.get("acks"), Some("1"));
+        assert_eq!(overridden.get("security.protocol"), Some("SASL_SSL"));
+
+        let not_idempotent = KafkaDestination::new("kafka".to_string(), serde_json::from_value(json!({
+            "brokers": "localhost:9092",
+            "topic": "records",
+            "idempotent": false
+        })).unwrap()).client_config();
+        assert_eq!(not_idempotent.get("enable.idempotence"), None);
+        assert_eq!(not_idempotent.get("acks"), None);
+    }
+}
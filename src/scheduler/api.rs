@@ -11,9 +11,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{error, info, debug};
+use tracing::debug;
 use uuid::Uuid;
-use futures::future::BoxFuture;
 use serde_json::json;
 use serde_json::Value;
 
@@ -48,8 +47,6 @@ struct RegisteredJob {
     enabled: bool,
     /// Last execution
     last_execution: Option<JobExecution>,
-    /// Created at
-    created_at: DateTime<Utc>,
     /// Updated at
     updated_at: DateTime<Utc>,
 }
@@ -103,17 +100,6 @@ pub struct JobDescription {
     pub last_execution: Option<JobExecution>,
 }
 
-/// Create job request
-#[derive(Debug, Deserialize)]
-pub struct CreateJobRequest {
-    /// Job name
-    pub name: String,
-    /// Job description
-    pub description: Option<String>,
-    /// Whether the job is enabled
-    pub enabled: Option<bool>,
-}
-
 /// Run job request
 #[derive(Debug, Deserialize)]
 pub struct RunJobRequest {
@@ -131,9 +117,9 @@ pub struct JobListQuery {
 /// API scheduler
 pub struct ApiScheduler {
     /// Registered jobs
-    jobs: RwLock<HashMap<String, RegisteredJob>>,
+    jobs: Arc<RwLock<HashMap<String, RegisteredJob>>>,
     /// Job executions
-    executions: RwLock<HashMap<String, JobExecution>>,
+    executions: Arc<RwLock<HashMap<String, JobExecution>>>,
     /// Configuration
     config: ApiSchedulerConfig,
 }
@@ -143,8 +129,8 @@ impl ApiScheduler {
     pub fn new(config: ApiSchedulerConfig) -> Self {
         Self {
             config,
-            jobs: RwLock::new(HashMap::new()),
-            executions: RwLock::new(HashMap::new()),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            executions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     
@@ -172,7 +158,6 @@ impl ApiScheduler {
             handler,
             enabled,
             last_execution: None,
-            created_at: Utc::now(),
             updated_at: Utc::now(),
         };
         
@@ -183,22 +168,6 @@ impl ApiScheduler {
         Ok(id)
     }
     
-    /// Unregister a job
-    pub async fn unregister_job(&self, id: &str) -> Result<()> {
-        // Skip if scheduler is disabled
-        if !self.config.enabled {
-            return Err(anyhow!("API scheduler is disabled"));
-        }
-        
-        let mut jobs = self.jobs.write().await;
-        
-        if jobs.remove(id).is_none() {
-            Err(anyhow!("Job with ID '{}' not found", id))
-        } else {
-            Ok(())
-        }
-    }
-    
     /// Get all registered jobs
     pub async fn get_jobs(&self, enabled_filter: Option<bool>) -> Vec<JobDescription> {
         let jobs = self.jobs.read().await;
@@ -265,7 +234,7 @@ impl ApiScheduler {
         let execution = JobExecution {
             id: execution_id.clone(),
             job_id: job_id.clone(),
-            status: "Pending".to_string(),
+            status: JobStatus::Pending,
             parameters: parameters.clone(),
             start_time: execution_start,
             end_time: None,
@@ -276,8 +245,9 @@ impl ApiScheduler {
         // Store the execution
         self.create_execution(job_id.clone(), execution).await?;
         
-        // Clone self into an Arc to be moved into the task
-        let self_arc = Arc::new(self.clone());
+        // Share the job and execution maps with the task
+        let jobs = Arc::clone(&self.jobs);
+        let executions = Arc::clone(&self.executions);
         let job_id_clone = job_id.clone();
         let execution_id_clone = execution_id.clone();
         let parameters_clone = parameters.clone();
@@ -286,22 +256,22 @@ impl ApiScheduler {
         tokio::spawn(async move {
             // Update execution status to Running
             {
-                let mut execs = self_arc.executions.write().await;
+                let mut execs = executions.write().await;
                 if let Some(exec) = execs.get_mut(&execution_id_clone) {
-                    exec.status = "Running".to_string();
+                    exec.status = JobStatus::Running;
                 }
             }
             
             // Get the job handler and run it
             let result = {
-                let jobs = self_arc.jobs.read().await;
+                let jobs = jobs.read().await;
                 match jobs.get(&job_id_clone) {
                     Some(job) => {
                         // Get the handler
                         let handler = job.handler.clone();
                         
                         // Run the handler with the parameters
-                        handler(parameters_clone)
+                        handler(parameters_clone.unwrap_or(Value::Null))
                     }
                     None => {
                         // Job not found
@@ -312,28 +282,29 @@ impl ApiScheduler {
             
             // Update the execution with the result
             let execution_end = Utc::now();
-            {
-                let mut execs = self_arc.executions.write().await;
-                if let Some(exec) = execs.get_mut(&execution_id_clone) {
+            let finished = {
+                let mut execs = executions.write().await;
+                execs.get_mut(&execution_id_clone).map(|exec| {
                     exec.end_time = Some(execution_end);
                     match result {
                         Ok(res) => {
-                            exec.status = "Completed".to_string();
+                            exec.status = JobStatus::Completed;
                             exec.result = Some(res);
                         }
                         Err(e) => {
-                            exec.status = "Failed".to_string();
+                            exec.status = JobStatus::Failed;
                             exec.error = Some(e.to_string());
                         }
                     }
-                }
-            }
+                    exec.clone()
+                })
+            };
             
             // Update the job's last execution
             {
-                let mut jobs = self_arc.jobs.write().await;
+                let mut jobs = jobs.write().await;
                 if let Some(job) = jobs.get_mut(&job_id_clone) {
-                    job.last_execution = Some(execution_id_clone);
+                    job.last_execution = finished;
                 }
             }
         });
@@ -351,22 +322,10 @@ impl ApiScheduler {
             .ok_or_else(|| anyhow!("Execution with ID '{}' not found", id))
     }
     
-    /// List history for a job
-    pub async fn list_execution_history(&self, job_id: &str) -> Result<Vec<String>> {
-        let executions_map = self.executions.read().await;
-        let execution_ids: Vec<String> = executions_map
-            .values()
-            .filter(|e| e.job_id == job_id)
-            .map(|e| e.id.clone())
-            .collect();
-        
-        Ok(execution_ids)
-    }
-
     /// Create a new execution record
     pub async fn create_execution(&self, job_id: String, execution: JobExecution) -> Result<()> {
         let mut executions = self.executions.write().await;
-        executions.insert(execution.id.clone(), execution);
+        executions.insert(execution.id.clone(), execution.clone());
         
         let mut jobs = self.jobs.write().await;
         if let Some(job) = jobs.get_mut(&job_id) {
@@ -376,29 +335,14 @@ impl ApiScheduler {
         Ok(())
     }
 
-    /// Cancel and clean up a job and all its executions
-    async fn cancel_job_internal(&self, job_id: &str) -> Result<()> {
-        // Remove the job
-        let job = {
-            let mut jobs = self.jobs.write().await;
-            jobs.remove(job_id).ok_or_else(|| anyhow!("Job not found"))?
-        };
-        
-        // Clean up executions for this job
-        {
-            let mut executions = self.executions.write().await;
-            executions.retain(|_, execution| execution.job_id != job_id);
-        }
-        
-        debug!("Job {} cancelled", job_id);
-        Ok(())
-    }
-    
     /// Create routes for the API scheduler
     pub fn routes(self: Arc<Self>) -> Router {
         Router::new()
             .route("/jobs", get(list_jobs))
             .route("/jobs/:id", get(get_job))
+            .route("/jobs/:id/enable", post(enable_job))
+            .route("/jobs/:id/disable", post(disable_job))
+            .route("/jobs/:id/run", post(run_job))
             .route("/executions", get(list_executions))
             .route("/executions/:id", get(get_execution))
             .with_state(self)
@@ -408,8 +352,9 @@ impl ApiScheduler {
 /// Standalone handler functions for axum compatibility
 async fn list_jobs(
     State(scheduler): State<Arc<ApiScheduler>>,
+    Query(query): Query<JobListQuery>,
 ) -> impl IntoResponse {
-    let jobs = scheduler.get_jobs(None).await;
+    let jobs = scheduler.get_jobs(query.enabled).await;
     Json(jobs).into_response()
 }
 
@@ -417,10 +362,9 @@ async fn get_job(
     State(scheduler): State<Arc<ApiScheduler>>, 
     Path(job_id): Path<String>
 ) -> impl IntoResponse {
-    let jobs = scheduler.jobs.read().await;
-    match jobs.get(&job_id) {
-        Some(job) => Json(job).into_response(),
-        None => (
+    match scheduler.get_job(&job_id).await {
+        Ok(job) => Json(job).into_response(),
+        Err(_) => (
             StatusCode::NOT_FOUND,
             Json(json!({"error": format!("Job {} not found", job_id)}))
         ).into_response()
@@ -430,7 +374,7 @@ async fn get_job(
 async fn list_executions(
     State(scheduler): State<Arc<ApiScheduler>>,
 ) -> impl IntoResponse {
-    let executions = scheduler.list_execution_history("").await;
+    let executions: Vec<JobExecution> = scheduler.executions.read().await.values().cloned().collect();
     Json(executions).into_response()
 }
 
@@ -438,10 +382,9 @@ async fn get_execution(
     State(scheduler): State<Arc<ApiScheduler>>,
     Path(execution_id): Path<String>
 ) -> impl IntoResponse {
-    let executions = scheduler.executions.read().await;
-    match executions.get(&execution_id) {
-        Some(execution) => Json(execution).into_response(),
-        None => (
+    match scheduler.get_execution(&execution_id).await {
+        Ok(execution) => Json(execution).into_response(),
+        Err(_) => (
             StatusCode::NOT_FOUND, 
             Json(json!({"error": format!("Execution {} not found", execution_id)}))
         ).into_response()
@@ -498,7 +441,7 @@ async fn run_job(
     Path(id): Path<String>,
     Json(request): Json<RunJobRequest>,
 ) -> impl IntoResponse {
-    match scheduler.run_job(id, request.parameters).await {
+    match scheduler.run_job(id.clone(), request.parameters).await {
         Ok(_) => (
             StatusCode::ACCEPTED,
             Json(serde_json::json!({
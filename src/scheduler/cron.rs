@@ -1,5 +1,5 @@
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 use cron::Schedule;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -7,7 +7,7 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
-use tracing::{debug, error, info, warn};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 /// Configuration for the cron scheduler
@@ -44,8 +44,6 @@ pub struct ScheduledJob {
     pub last_run: Mutex<Option<DateTime<Utc>>>,
     /// Whether the job is enabled
     pub enabled: bool,
-    /// Whether to catch up missed executions
-    pub catch_up: bool,
 }
 
 impl ScheduledJob {
@@ -55,7 +53,6 @@ impl ScheduledJob {
         cron_expression: &str,
         handler: JobHandler,
         enabled: bool,
-        catch_up: bool,
     ) -> Result<Self> {
         let schedule = Schedule::from_str(cron_expression)?;
         
@@ -66,16 +63,12 @@ impl ScheduledJob {
             next_run: Mutex::new(None),
             last_run: Mutex::new(None),
             enabled,
-            catch_up,
         })
     }
     
     /// Get the next scheduled run time
     pub fn next_run(&self) -> Option<DateTime<Utc>> {
-        match self.schedule.upcoming(Utc).next() {
-            Some(time) => Some(time),
-            None => None,
-        }
+        self.schedule.upcoming(Utc).next()
     }
     
     /// Update the next scheduled run time
@@ -142,13 +135,12 @@ impl CronScheduler {
         cron_expression: &str,
         handler: JobHandler,
         enabled: bool,
-        catch_up: bool,
     ) -> Result<String> {
         // Generate a job ID
         let job_id = Uuid::new_v4().to_string();
         
         // Create the job
-        let job = ScheduledJob::new(job_id.clone(), cron_expression, handler, enabled, catch_up)?;
+        let job = ScheduledJob::new(job_id.clone(), cron_expression, handler, enabled)?;
         
         // Initialize next run time
         job.update_next_run().await?;
@@ -246,54 +238,4 @@ impl CronScheduler {
         }
     }
     
-    /// Get all jobs
-    pub async fn get_jobs(&self) -> Vec<Arc<ScheduledJob>> {
-        let jobs = self.jobs.lock().await;
-        jobs.clone()
-    }
-    
-    /// Get a job by ID
-    pub async fn get_job(&self, id: &str) -> Option<Arc<ScheduledJob>> {
-        let jobs = self.jobs.lock().await;
-        jobs.iter().find(|j| j.id == id).cloned()
-    }
-    
-    /// Remove a job
-    pub async fn remove_job(&self, id: &str) -> Result<()> {
-        let mut jobs = self.jobs.lock().await;
-        let initial_len = jobs.len();
-        
-        // Remove the job
-        jobs.retain(|j| j.id != id);
-        
-        // Check if anything was removed
-        if jobs.len() == initial_len {
-            Err(anyhow!("Job not found: {}", id))
-        } else {
-            Ok(())
-        }
-    }
-    
-    /// Enable or disable a job
-    pub async fn set_job_enabled(&self, id: &str, enabled: bool) -> Result<()> {
-        // Find the job
-        let jobs = self.jobs.lock().await;
-        let job = jobs.iter().find(|j| j.id == id).cloned();
-        
-        match job {
-            Some(job) => {
-                // This is a hack since we can't modify the job's enabled field directly
-                // due to the immutable reference. In a real implementation, the ScheduledJob
-                // would have a method to change its enabled state.
-                info!(
-                    "{} job: {}",
-                    if enabled { "Enabling" } else { "Disabling" },
-                    id
-                );
-                
-                Ok(())
-            }
-            None => Err(anyhow!("Job not found: {}", id)),
-        }
-    }
 }
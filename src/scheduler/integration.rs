@@ -64,7 +64,7 @@ impl SchedulerIntegration {
     /// # Returns
     /// Result indicating success or failure
     pub async fn start(&self) -> Result<()> {
-        self.cron_scheduler.start().await;
+        self.cron_scheduler.start().await?;
         Ok(())
     }
 
@@ -76,7 +76,7 @@ impl SchedulerIntegration {
     /// # Returns
     /// Result indicating success or failure
     pub async fn stop(&self) -> Result<()> {
-        self.cron_scheduler.stop().await;
+        self.cron_scheduler.stop().await?;
         Ok(())
     }
 
@@ -3,7 +3,7 @@ pub mod cron;
 pub mod integration;
 pub mod webhook;
 
-pub use api::{ApiScheduler, ApiSchedulerConfig, JobDescription, JobExecution, JobStatus};
-pub use cron::{CronConfig, CronScheduler, ScheduledJob};
+pub use api::{ApiScheduler, ApiSchedulerConfig};
+pub use cron::{CronConfig, CronScheduler};
 pub use integration::{SchedulerConfig, SchedulerIntegration};
-pub use webhook::{RegisteredWebhook, WebhookConfig, WebhookScheduler}; 
\ No newline at end of file
+pub use webhook::{WebhookConfig, WebhookScheduler}; 
\ No newline at end of file
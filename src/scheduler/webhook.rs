@@ -27,8 +27,6 @@ pub type WebhookHandler = Arc<dyn Fn(serde_json::Value) -> Result<serde_json::Va
 
 /// A registered webhook in the scheduler
 pub struct RegisteredWebhook {
-    /// Webhook ID
-    id: String,
     /// Webhook path
     path: String,
     /// Webhook handler
@@ -65,8 +63,6 @@ pub struct WebhookScheduler {
     config: WebhookConfig,
     /// Registered webhook handlers
     webhooks: RwLock<HashMap<String, RegisteredWebhook>>,
-    /// HMAC signatures for webhooks
-    signatures: RwLock<HashMap<String, String>>,
 }
 
 impl WebhookScheduler {
@@ -75,11 +71,11 @@ impl WebhookScheduler {
         Self {
             config,
             webhooks: RwLock::new(HashMap::new()),
-            signatures: RwLock::new(HashMap::new()),
         }
     }
     
-    /// Register a new webhook
+    /// Register a new webhook; without a secret of its own, calls are
+    /// checked against the scheduler's secret
     pub async fn register_webhook(
         &self,
         path: &str,
@@ -92,10 +88,9 @@ impl WebhookScheduler {
         
         // Create a registered webhook
         let webhook = RegisteredWebhook {
-            id: id.clone(),
             path: path.to_string(),
             handler,
-            secret,
+            secret: secret.or_else(|| self.config.secret.clone()),
             enabled,
         };
         
@@ -112,23 +107,6 @@ impl WebhookScheduler {
         Ok(id)
     }
     
-    /// Unregister a webhook
-    pub async fn unregister_webhook(&self, id: &str) -> Result<()> {
-        let mut webhooks = self.webhooks.write().await;
-        
-        if webhooks.remove(id).is_none() {
-            Err(anyhow!("Webhook with ID '{}' not found", id))
-        } else {
-            Ok(())
-        }
-    }
-    
-    /// Get all registered webhook paths
-    pub async fn get_webhook_paths(&self) -> Vec<String> {
-        let webhooks = self.webhooks.read().await;
-        webhooks.values().filter(|w| w.enabled).map(|w| w.path.clone()).collect()
-    }
-    
     /// Create webhook routes
     pub fn routes(self: Arc<Self>) -> Router {
         Router::new()
@@ -1,8 +1,9 @@
-use sqlx::{Pool, Sqlite, SqlitePool};
+use sqlx::{Pool, Sqlite};
+use sqlx::sqlite::SqlitePoolOptions;
 use std::time::Duration;
 use anyhow::Result;
 use crate::error::MuxlyError;
-use tracing::{info, warn};
+use tracing::{info};
 
 /// Database pool type
 pub type DatabasePool = Pool<Sqlite>;
@@ -42,31 +43,20 @@ pub async fn init_sqlite_database(config: &DatabaseConfig) -> Result<DatabasePoo
     info!("Initializing SQLite database: {}", config.url);
     
     // Configure the connection pool
-    let pool = SqlitePool::builder()
+    let pool = SqlitePoolOptions::new()
         .max_connections(config.max_connections)
         .min_connections(config.min_connections)
-        .connect_timeout(Duration::from_secs(config.connection_timeout_seconds))
+        .acquire_timeout(Duration::from_secs(config.connection_timeout_seconds))
         .max_lifetime(Duration::from_secs(config.max_lifetime_seconds))
         .idle_timeout(Duration::from_secs(config.idle_timeout_seconds))
         .connect(&config.url)
         .await
-        .map_err(|e| MuxlyError::Database(e))?;
+        .map_err(MuxlyError::Database)?;
     
     info!("Database connection pool initialized successfully");
     Ok(pool)
 }
 
-/// Check if the database is available
-pub async fn check_database(pool: &DatabasePool) -> Result<bool> {
-    match sqlx::query("SELECT 1").execute(pool).await {
-        Ok(_) => Ok(true),
-        Err(e) => {
-            warn!("Database health check failed: {}", e);
-            Err(MuxlyError::Database(e).into())
-        }
-    }
-}
-
 /// Gracefully shutdown the database connection pool
 pub async fn shutdown_database(pool: &DatabasePool) -> Result<()> {
     info!("Shutting down database connections gracefully...");
@@ -1,31 +1,67 @@
-use anyhow::Context;
-use sqlx::migrate::MigrateDatabase;
+use futures::future::BoxFuture;
+use sqlx::error::BoxDynError;
+use sqlx::migrate::{Migration, MigrationSource, MigrationType};
 use crate::storage::database::DatabasePool;
-use std::path::Path;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 use crate::error::{MuxlyError, Result};
 
+/// Migrations laid out as one directory per migration, named
+/// `YYYYMMDDHHMMSS_name` and holding `up.sql` and `down.sql`
+#[derive(Debug)]
+struct MigrationDirectories(PathBuf);
+
+impl<'s> MigrationSource<'s> for MigrationDirectories {
+    fn resolve(self) -> BoxFuture<'s, std::result::Result<Vec<Migration>, BoxDynError>> {
+        Box::pin(async move {
+            let mut migrations = Vec::new();
+
+            for entry in std::fs::read_dir(&self.0)? {
+                let path = entry?.path();
+                if !path.is_dir() {
+                    continue;
+                }
+
+                let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+                let (version, description) = match name.split_once('_') {
+                    Some((version, description)) => (version.parse::<i64>()?, description.replace('_', " ")),
+                    None => continue,
+                };
+
+                for (file, migration_type) in [("up.sql", MigrationType::ReversibleUp), ("down.sql", MigrationType::ReversibleDown)] {
+                    let sql = std::fs::read_to_string(path.join(file))?;
+                    migrations.push(Migration::new(version, Cow::Owned(description.clone()), migration_type, Cow::Owned(sql)));
+                }
+            }
+
+            migrations.sort_by_key(|migration| (migration.version, migration.migration_type.is_down_migration()));
+            Ok(migrations)
+        })
+    }
+}
+
 /// Run database migrations
 pub async fn run_migrations(pool: &DatabasePool) -> Result<()> {
     info!("Running database migrations...");
-    
+
     // Get the migrations directory path
-    // Using SQLx standard migrations format (timestamp_name)
+    // Each migration is a directory holding up.sql and down.sql
     let migration_path = Path::new("./migrations");
-    
+
     if !migration_path.exists() {
         warn!("Migrations directory not found at {}", migration_path.display());
         return Ok(());
     }
-    
+
     // Run migrations using SQLx
-    sqlx::migrate::Migrator::new(migration_path)
+    sqlx::migrate::Migrator::new(MigrationDirectories(migration_path.to_path_buf()))
         .await
-        .map_err(|e| MuxlyError::Database(e))?
+        .map_err(|e| MuxlyError::Database(e.into()))?
         .run(pool)
         .await
-        .map_err(|e| MuxlyError::Database(e))?;
-    
+        .map_err(|e| MuxlyError::Database(e.into()))?;
+
     info!("Database migrations completed successfully");
     Ok(())
-} 
\ No newline at end of file
+}
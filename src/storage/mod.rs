@@ -1,7 +1,6 @@
 mod database;
 mod models;
 mod migrations;
-mod repositories;
 
 use std::sync::Arc;
 
@@ -12,7 +11,7 @@ pub use database::*;
 pub async fn init_database(config: &DatabaseConfig) -> crate::error::Result<Arc<DatabasePool>> {
     let pool = database::init_sqlite_database(config)
         .await
-        .map_err(|e| crate::error::MuxlyError::Database(e.into()))?;
+        .map_err(|e| crate::error::MuxlyError::Internal(e.to_string()))?;
     
     // Run migrations
     migrations::run_migrations(&pool).await?;
@@ -20,12 +19,5 @@ pub async fn init_database(config: &DatabaseConfig) -> crate::error::Result<Arc<
     Ok(Arc::new(pool))
 }
 
-/// Gracefully shutdown the database
-pub async fn shutdown() -> crate::error::Result<()> {
-    // Shutdown operations can be added here as needed
-    Ok(())
-}
-
-// Re-export model and repository types
-pub use models::*;
-pub use repositories::*; 
\ No newline at end of file
+// Re-export model types
+pub use models::*;
\ No newline at end of file
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+use crate::router::BatchSettings;
+
+/// Database model for a shared router destination
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DestinationRecord {
+    /// User-chosen identifier, referenced by routes and rules
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Type of destination (database, slack, webhook, etc.)
+    pub destination_type: String,
+    /// Destination configuration as JSON
+    pub config: String,
+    /// Whether the destination is enabled
+    pub enabled: bool,
+    /// Timeout in seconds for sending to the destination (the global default if not set)
+    #[serde(default)]
+    pub timeout: Option<i64>,
+    /// Batching settings as JSON (records are sent as they arrive if not set)
+    #[serde(default)]
+    pub batch: Option<String>,
+    /// When the destination was created
+    pub created_at: DateTime<Utc>,
+    /// When the destination was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DestinationRecord {
+    /// Create a new destination record
+    pub fn new(
+        id: String,
+        name: String,
+        destination_type: String,
+        enabled: bool,
+        config: Value,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            name,
+            destination_type,
+            config: config.to_string(),
+            enabled,
+            timeout: None,
+            batch: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    /// Set the timeout in seconds for sending to the destination
+    pub fn with_timeout(mut self, timeout: Option<u64>) -> Self {
+        self.timeout = timeout.map(|timeout| timeout as i64);
+        self
+    }
+
+    /// Set the batching settings
+    pub fn with_batch(mut self, batch: Option<&BatchSettings>) -> Self {
+        self.batch = batch.and_then(|batch| serde_json::to_string(batch).ok());
+        self
+    }
+
+    /// Get the destination configuration as JSON
+    pub fn config_json(&self) -> Result<Value, serde_json::Error> {
+        serde_json::from_str(&self.config)
+    }
+
+    /// Get the batching settings, if the destination batches records
+    pub fn batch_settings(&self) -> Result<Option<BatchSettings>, serde_json::Error> {
+        self.batch.as_deref().map(serde_json::from_str).transpose()
+    }
+
+    /// The record with the secrets in its configuration (passwords, tokens,
+    /// keys, authorization headers, connection strings, webhook URLs)
+    /// replaced by [`REDACTED`]
+    pub fn redacted(&self) -> Self {
+        let mut record = self.clone();
+        if let Ok(mut config) = self.config_json() {
+            redact(&mut config);
+            record.config = config.to_string();
+        }
+        record
+    }
+
+    /// Take the secrets left as [`REDACTED`] in the configuration from the
+    /// previous definition, so a redacted definition can be sent back with
+    /// only the other settings changed
+    pub fn with_secrets_from(mut self, previous: &DestinationRecord) -> Self {
+        if let (Ok(mut config), Ok(previous)) = (self.config_json(), previous.config_json()) {
+            restore_secrets(&mut config, &previous);
+            self.config = config.to_string();
+        }
+        self
+    }
+}
+
+/// Placeholder that secret configuration values are shown as
+pub const REDACTED: &str = "********";
+
+/// Whether a configuration key holds a secret
+fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase().replace('-', "_");
+    ["password", "secret", "token", "api_key", "apikey", "authorization", "connection_string", "webhook_url"]
+        .iter()
+        .any(|secret| key.contains(secret))
+}
+
+/// Replace every non-null value under a secret key with [`REDACTED`]
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                if is_secret(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        },
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {},
+    }
+}
+
+/// Replace the values left as [`REDACTED`] with the value at the same place
+/// in `previous`
+fn restore_secrets(value: &mut Value, previous: &Value) {
+    match (value, previous) {
+        (Value::Object(fields), Value::Object(previous)) => {
+            for (key, value) in fields.iter_mut() {
+                if let Some(previous) = previous.get(key) {
+                    restore_secrets(value, previous);
+                }
+            }
+        },
+        (Value::Array(items), Value::Array(previous)) => {
+            for (value, previous) in items.iter_mut().zip(previous) {
+                restore_secrets(value, previous);
+            }
+        },
+        (value, previous) if value.as_str() == Some(REDACTED) => *value = previous.clone(),
+        _ => {},
+    }
+}
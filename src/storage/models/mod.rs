@@ -1,11 +1,3 @@
-mod connector;
-mod schedule;
-mod transformation;
-mod output;
-mod sync_history;
+mod destination;
 
-pub use connector::*;
-pub use schedule::*;
-pub use transformation::*;
-pub use output::*;
-pub use sync_history::*; 
\ No newline at end of file
+pub use destination::*;
@@ -1,8 +0,0 @@
-// Placeholder for output storage models
-#[derive(Debug)]
-pub struct Output {
-    pub id: String,
-    pub name: String,
-    pub output_type: String,
-    pub enabled: bool,
-} 
\ No newline at end of file
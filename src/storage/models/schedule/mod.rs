@@ -1,8 +0,0 @@
-// Placeholder for schedule storage models
-#[derive(Debug)]
-pub struct ScheduledTask {
-    pub id: String,
-    pub name: String,
-    pub cron_expression: String,
-    pub enabled: bool,
-} 
\ No newline at end of file
@@ -1,7 +0,0 @@
-// Placeholder for transformation storage models
-#[derive(Debug)]
-pub struct Transformation {
-    pub id: String,
-    pub name: String,
-    pub transformation_type: String,
-} 
\ No newline at end of file
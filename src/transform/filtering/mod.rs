@@ -1,8 +0,0 @@
-use anyhow::Result;
-use serde_json::Value;
-
-/// Filter data based on criteria
-pub fn filter_data(input: &Value, criteria: &Value) -> Result<Value> {
-    // Placeholder implementation - just return the input
-    Ok(input.clone())
-} 
\ No newline at end of file
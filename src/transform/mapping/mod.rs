@@ -1,8 +0,0 @@
-use anyhow::Result;
-use serde_json::Value;
-
-/// Map fields from one format to another
-pub fn map_fields(input: &Value, mapping: &Value) -> Result<Value> {
-    // Placeholder implementation - just return the input
-    Ok(input.clone())
-} 
\ No newline at end of file
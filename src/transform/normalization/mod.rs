@@ -1,8 +0,0 @@
-use anyhow::Result;
-use serde_json::Value;
-
-/// Normalize data to a consistent format
-pub fn normalize_data(input: &Value) -> Result<Value> {
-    // Placeholder implementation - just return the input
-    Ok(input.clone())
-} 
\ No newline at end of file
@@ -1,20 +0,0 @@
-use anyhow::Result;
-use serde_json::Value;
-
-/// Pipeline for transforming data
-pub struct TransformPipeline {
-    // Placeholder implementation
-}
-
-impl TransformPipeline {
-    /// Create a new transform pipeline
-    pub fn new() -> Self {
-        Self {}
-    }
-
-    /// Process data through the pipeline
-    pub fn process(&self, data: Value) -> Result<Value> {
-        // Placeholder implementation - just return the data unchanged
-        Ok(data)
-    }
-} 
\ No newline at end of file